@@ -109,9 +109,5 @@ fn bench_fastx(c: &mut Criterion) {
     group.finish();
 }
 
-fn bench_needletail(c: &mut Criterion) {
-    // Merged into bench_fastx for better grouping
-}
-
 criterion_group!(benches, bench_fastx);
 criterion_main!(benches);
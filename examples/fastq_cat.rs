@@ -21,7 +21,7 @@ fn main() -> io::Result<()>
                 fastq_record.desc(),
                 String::from_utf8_lossy(&fastq_record.seq()),
                 fastq_record.comment(),
-                String::from_utf8_lossy(&fastq_record.qual())
+                String::from_utf8_lossy(fastq_record.qual())
             );
         }
     }
@@ -0,0 +1,318 @@
+//! Chromosome name aliases (UCSC, Ensembl, RefSeq, GenBank).
+//!
+//! The same sequence is called `chr1`, `1`, `NC_000001.11` or `CM000663.2` depending
+//! on where a reference comes from. [`ChromAliases`] maps between these names using
+//! a UCSC `chromAlias.txt` table and two built-in rules:
+//!
+//! - a `chr` prefix is added or removed on chromosome-like names (`chr1` ↔ `1`,
+//!   `chrX` ↔ `X`, `chrUn_KI270302v1` ↔ `Un_KI270302v1`), not on accessions such
+//!   as `NC_000001.11`
+//! - the mitochondrion is `chrM`, `chrMT`, `MT` or `M`
+//!
+//! Attach aliases to a [`FaiIndex`](crate::fai::FaiIndex) or
+//! [`IndexedFastXReader`](crate::indexed::IndexedFastXReader) with `with_aliases`, and
+//! lookups by any alias find the sequence under its name in the reference.
+//!
+//! # chromAlias.txt
+//!
+//! Current UCSC tables have a `#` header naming the sources, followed by one
+//! tab-separated row of equivalent names per sequence:
+//!
+//! ```text
+//! # ucsc  assembly  genbank     ncbi  refseq
+//! chr1    1         CM000663.2  1     NC_000001.11
+//! ```
+//!
+//! The older three-column format (`alias`, UCSC name, source) is read as well.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Names of the mitochondrial sequence, all treated as aliases of each other.
+const MITOCHONDRION: [&str; 4] = ["chrM", "chrMT", "MT", "M"];
+
+/// A set of chromosome name aliases.
+///
+/// # Example
+///
+/// ```
+/// use fastx::alias::ChromAliases;
+/// use std::io::Cursor;
+///
+/// let table = "# ucsc\trefseq\nchr1\tNC_000001.11\n";
+/// let aliases = ChromAliases::from_reader(Cursor::new(table)).unwrap();
+///
+/// // Table entries and the built-in rules combine
+/// let candidates = aliases.candidates("NC_000001.11");
+/// assert!(candidates.contains(&"chr1".to_string()));
+/// assert!(candidates.contains(&"1".to_string()));
+/// assert!(aliases.candidates("MT").contains(&"chrM".to_string()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChromAliases
+{
+    /// Groups of equivalent names
+    groups: Vec<Vec<String>>,
+    /// Group index by name
+    lookup: HashMap<String, usize>,
+    /// Apply the `chr` prefix and mitochondrion rules
+    builtin_rules: bool,
+}
+
+impl Default for ChromAliases
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl ChromAliases
+{
+    /// Create aliases with only the built-in `chr` prefix and mitochondrion rules.
+    pub fn new() -> Self
+    {
+        Self { groups: Vec::new(), lookup: HashMap::new(), builtin_rules: true }
+    }
+
+    /// Load a UCSC `chromAlias.txt` table.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the table
+    pub fn from_path(path: &Path) -> io::Result<Self>
+    {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Parse a UCSC `chromAlias.txt` table from a buffered reader.
+    ///
+    /// Rows sharing a name are merged, so the older format with one alias per row
+    /// yields a single group per UCSC name.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self>
+    {
+        let mut aliases = Self::new();
+        let mut has_header = false;
+        for line in reader.lines()
+        {
+            let line = line?;
+            if line.starts_with('#')
+            {
+                has_header = true;
+                continue;
+            }
+            let mut names: Vec<&str> =
+                line.split('\t').map(str::trim).filter(|name| !name.is_empty()).collect();
+            // The older format ends with the source of the alias, not a name
+            if !has_header && names.len() == 3
+            {
+                names.pop();
+            }
+            aliases.add(&names);
+        }
+        Ok(aliases)
+    }
+
+    /// Declare names as aliases of each other.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastx::alias::ChromAliases;
+    ///
+    /// let mut aliases = ChromAliases::new().with_builtin_rules(false);
+    /// aliases.add(&["chr1", "NC_000001.11"]);
+    /// assert_eq!(aliases.candidates("chr1"), vec!["NC_000001.11".to_string()]);
+    /// assert!(aliases.candidates("chr2").is_empty());
+    /// ```
+    pub fn add(&mut self, names: &[&str])
+    {
+        if names.len() < 2
+        {
+            return;
+        }
+        let group = match names.iter().find_map(|name| self.lookup.get(*name))
+        {
+            Some(&group) => group,
+            None =>
+            {
+                self.groups.push(Vec::new());
+                self.groups.len() - 1
+            }
+        };
+        for name in names
+        {
+            match self.lookup.get(*name)
+            {
+                Some(&other) if other != group =>
+                {
+                    // The row joins two groups
+                    let moved = std::mem::take(&mut self.groups[other]);
+                    for name in &moved
+                    {
+                        self.lookup.insert(name.clone(), group);
+                    }
+                    self.groups[group].extend(moved);
+                }
+                Some(_) => (),
+                None =>
+                {
+                    self.lookup.insert(name.to_string(), group);
+                    self.groups[group].push(name.to_string());
+                }
+            }
+        }
+    }
+
+    /// Enable or disable the built-in `chr` prefix and mitochondrion rules.
+    pub fn with_builtin_rules(mut self, enabled: bool) -> Self
+    {
+        self.builtin_rules = enabled;
+        self
+    }
+
+    /// Get the number of alias groups loaded from tables.
+    pub fn len(&self) -> usize
+    {
+        self.groups.iter().filter(|group| !group.is_empty()).count()
+    }
+
+    /// Check if no alias groups are loaded.
+    pub fn is_empty(&self) -> bool
+    {
+        self.len() == 0
+    }
+
+    /// Get the other names of a sequence, in the order they should be tried.
+    ///
+    /// Built-in variants of `name` come first, then table aliases of `name` and of
+    /// its variants, then built-in variants of those aliases. `name` itself is not
+    /// included.
+    pub fn candidates(&self, name: &str) -> Vec<String>
+    {
+        let mut candidates: Vec<String> = Vec::new();
+        self.find_candidate(name, |alias| {
+            if alias != name && !candidates.iter().any(|c| c == alias)
+            {
+                candidates.push(alias.to_string());
+            }
+            None::<()>
+        });
+        candidates
+    }
+
+    /// Try the names of [`candidates`](Self::candidates) in order until `lookup`
+    /// gives a value, without collecting them first.
+    ///
+    /// Names may be tried more than once, and `name` itself may be tried.
+    pub(crate) fn find_candidate<T>(
+        &self,
+        name: &str,
+        mut lookup: impl FnMut(&str) -> Option<T>,
+    ) -> Option<T>
+    {
+        let variants = self.variants(name);
+        if let Some(found) = variants.iter().find_map(|variant| lookup(variant))
+        {
+            return Some(found);
+        }
+        let aliases = || {
+            std::iter::once(name)
+                .chain(variants.iter().map(String::as_str))
+                .filter_map(|name| self.lookup.get(name))
+                .flat_map(|&group| self.groups[group].iter().map(String::as_str))
+        };
+        if let Some(found) = aliases().find_map(&mut lookup)
+        {
+            return Some(found);
+        }
+        variants
+            .iter()
+            .map(String::as_str)
+            .chain(aliases())
+            .find_map(|alias| self.variants(alias).iter().find_map(|variant| lookup(variant)))
+    }
+
+    /// Built-in variants of a name.
+    fn variants(&self, name: &str) -> Vec<String>
+    {
+        if !self.builtin_rules || name.is_empty()
+        {
+            return Vec::new();
+        }
+        if MITOCHONDRION.contains(&name)
+        {
+            return MITOCHONDRION.iter().map(|m| m.to_string()).collect();
+        }
+        match name.strip_prefix("chr")
+        {
+            Some(stripped) if is_chromosome_like(stripped) => vec![stripped.to_string()],
+            Some(_) => Vec::new(),
+            None if is_chromosome_like(name) => vec![format!("chr{}", name)],
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Check if a name without `chr` prefix looks like a UCSC chromosome: a number,
+/// a sex chromosome, or an unplaced (`Un_*`) or unlocalized (`*_random`) contig.
+fn is_chromosome_like(name: &str) -> bool
+{
+    (!name.is_empty() && name.bytes().all(|b| b.is_ascii_digit()))
+        || matches!(name, "X" | "Y" | "W" | "Z")
+        || name.starts_with("Un_")
+        || name.ends_with("_random")
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_builtin_rules()
+    {
+        let aliases = ChromAliases::new();
+        assert_eq!(aliases.candidates("chr1"), vec!["1"]);
+        assert_eq!(aliases.candidates("X"), vec!["chrX"]);
+        assert_eq!(aliases.candidates("MT"), vec!["chrM", "chrMT", "M"]);
+        assert_eq!(aliases.candidates("chrM"), vec!["chrMT", "MT", "M"]);
+        assert_eq!(aliases.candidates("Un_KI270302v1"), vec!["chrUn_KI270302v1"]);
+        assert_eq!(aliases.candidates("chr1_KI270706v1_random"), vec!["1_KI270706v1_random"]);
+        assert!(aliases.candidates("NC_000001.11").is_empty());
+        assert!(aliases.candidates("chrNC_000001.11").is_empty());
+        assert!(aliases.candidates("scaffold_12").is_empty());
+        assert!(ChromAliases::new().with_builtin_rules(false).candidates("chr1").is_empty());
+    }
+
+    #[test]
+    fn test_alias_tables()
+    {
+        let table = "# ucsc\tassembly\tgenbank\tncbi\trefseq\n\
+                     chr1\t1\tCM000663.2\t1\tNC_000001.11\n\
+                     chrM\tMT\tJ01415.2\tMT\tNC_012920.1\n";
+        let aliases = ChromAliases::from_reader(Cursor::new(table)).unwrap();
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(aliases.candidates("NC_000001.11"), vec!["chr1", "1", "CM000663.2"]);
+        assert_eq!(aliases.candidates("1"), vec!["chr1", "CM000663.2", "NC_000001.11"]);
+        assert!(aliases.candidates("NC_012920.1").contains(&"M".to_string()));
+
+        // Three-column format: alias, UCSC name, source
+        let old = "1\tchr1\tensembl\nNC_000001.11\tchr1\trefseq\n";
+        let aliases = ChromAliases::from_reader(Cursor::new(old)).unwrap();
+        assert_eq!(aliases.len(), 1);
+        assert!(aliases.candidates("NC_000001.11").contains(&"1".to_string()));
+        assert!(!aliases.candidates("chr1").contains(&"refseq".to_string()));
+
+        // Rows joining two groups merge them
+        let mut aliases = ChromAliases::new().with_builtin_rules(false);
+        aliases.add(&["a", "b"]);
+        aliases.add(&["c", "d"]);
+        aliases.add(&["b", "c"]);
+        assert_eq!(aliases.len(), 1);
+        assert_eq!(aliases.candidates("a"), vec!["b", "c", "d"]);
+    }
+}
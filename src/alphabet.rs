@@ -0,0 +1,503 @@
+//! Sequence alphabet validation and normalization.
+//!
+//! This module provides alphabet classes for nucleotide and protein sequences,
+//! validators that report the first offending byte, and normalizers that clean up
+//! sequence data (case, `U`→`T`, invalid symbols, whitespace and digits).
+//!
+//! The functions work on plain byte slices (e.g. the result of `seq()`), and a
+//! [`Normalizer`] can be applied to records while parsing via
+//! [`fasta_for_each_normalized`](crate::FastX::fasta_for_each_normalized),
+//! [`fastq_for_each_normalized`](crate::FastX::fastq_for_each_normalized) or
+//! [`FastXIterator::normalized`](crate::FastX::FastXIterator::normalized).
+//!
+//! # Example
+//!
+//! ```
+//! use fastx::alphabet::{Alphabet, Normalizer};
+//!
+//! let err = Alphabet::Dna.validate(b"ACGTXACGT").unwrap_err();
+//! assert_eq!(err.byte, b'X');
+//! assert_eq!(err.position, 4);
+//!
+//! let normalizer = Normalizer::new()
+//!     .with_uppercase(true)
+//!     .with_rna_to_dna(true)
+//!     .with_replace_invalid(Some(Alphabet::Dna));
+//! let mut seq = b"acgu*ACGU".to_vec();
+//! normalizer.apply(&mut seq).unwrap();
+//! assert_eq!(seq, b"ACGTNACGT");
+//! ```
+
+use std::fmt;
+use std::io;
+
+/// A sequence alphabet.
+///
+/// All alphabets are case-insensitive, so soft-masked (lowercase) sequence
+/// validates against the same alphabet as its uppercase form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alphabet
+{
+    /// DNA: `A`, `C`, `G`, `T` and the unknown base `N`
+    Dna,
+    /// RNA: `A`, `C`, `G`, `U` and the unknown base `N`
+    Rna,
+    /// IUPAC nucleotide codes: `ACGTU`, `RYSWKM`, `BDHV`, `N` and the gap `-`
+    IupacNucleotide,
+    /// Protein: the 20 standard amino acids, `BJZUOX`, the stop `*` and the gap `-`
+    Protein,
+}
+
+impl Alphabet
+{
+    /// Check whether a single byte belongs to this alphabet.
+    pub fn is_valid(&self, byte: u8) -> bool
+    {
+        let upper = byte.to_ascii_uppercase();
+        match self
+        {
+            Alphabet::Dna => matches!(upper, b'A' | b'C' | b'G' | b'T' | b'N'),
+            Alphabet::Rna => matches!(upper, b'A' | b'C' | b'G' | b'U' | b'N'),
+            Alphabet::IupacNucleotide => matches!(
+                upper,
+                b'A' | b'C'
+                    | b'G'
+                    | b'T'
+                    | b'U'
+                    | b'R'
+                    | b'Y'
+                    | b'S'
+                    | b'W'
+                    | b'K'
+                    | b'M'
+                    | b'B'
+                    | b'D'
+                    | b'H'
+                    | b'V'
+                    | b'N'
+                    | b'-'
+            ),
+            Alphabet::Protein => upper.is_ascii_uppercase() || upper == b'*' || upper == b'-',
+        }
+    }
+
+    /// The symbol used to replace invalid bytes: `N` for nucleotides, `X` for protein.
+    pub fn unknown(&self) -> u8
+    {
+        match self
+        {
+            Alphabet::Protein => b'X',
+            _ => b'N',
+        }
+    }
+
+    /// Validate a sequence against this alphabet.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every byte belongs to the alphabet
+    /// * `Err(InvalidSymbol)` - The first offending byte and its 0-based position
+    pub fn validate(&self, seq: &[u8]) -> Result<(), InvalidSymbol>
+    {
+        match seq.iter().position(|&b| !self.is_valid(b))
+        {
+            None => Ok(()),
+            Some(position) => Err(InvalidSymbol {
+                byte: seq[position],
+                position,
+            }),
+        }
+    }
+
+    /// Validate raw, possibly multi-line sequence data (e.g. `seq_raw()`).
+    ///
+    /// Line terminators (`\n`, `\r`) are skipped, and the reported position is the
+    /// 0-based base position, i.e. the position in `seq()`.
+    pub fn validate_raw(&self, raw_seq: &[u8]) -> Result<(), InvalidSymbol>
+    {
+        let mut position = 0;
+        for &b in raw_seq
+        {
+            if is_line_terminator(b)
+            {
+                continue;
+            }
+            if !self.is_valid(b)
+            {
+                return Err(InvalidSymbol { byte: b, position });
+            }
+            position += 1;
+        }
+        Ok(())
+    }
+}
+
+/// The first byte of a sequence that does not belong to an alphabet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSymbol
+{
+    /// The offending byte
+    pub byte: u8,
+    /// 0-based position of the byte in the sequence (excluding line terminators)
+    pub position: usize,
+}
+
+impl fmt::Display for InvalidSymbol
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(
+            f,
+            "Invalid sequence symbol {:?} (0x{:02x}) at position {}",
+            self.byte as char, self.byte, self.position
+        )
+    }
+}
+
+impl std::error::Error for InvalidSymbol {}
+
+impl From<InvalidSymbol> for io::Error
+{
+    fn from(e: InvalidSymbol) -> Self
+    {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+fn is_line_terminator(b: u8) -> bool
+{
+    b == b'\n' || b == b'\r'
+}
+
+/// Convert a sequence to uppercase in place (removes soft-masking).
+pub fn uppercase(seq: &mut [u8])
+{
+    seq.make_ascii_uppercase();
+}
+
+/// Replace `U`/`u` by `T`/`t` in place.
+pub fn rna_to_dna(seq: &mut [u8])
+{
+    for b in seq.iter_mut()
+    {
+        match *b
+        {
+            b'U' => *b = b'T',
+            b'u' => *b = b't',
+            _ =>
+            {}
+        }
+    }
+}
+
+/// Replace bytes not in `alphabet` by its unknown symbol (`N` or `X`).
+///
+/// Line terminators are left untouched so this can be applied to `seq_raw()` data.
+///
+/// # Returns
+///
+/// The number of replaced bytes.
+pub fn replace_invalid(alphabet: Alphabet, seq: &mut [u8]) -> usize
+{
+    let unknown = alphabet.unknown();
+    let mut replaced = 0;
+    for b in seq.iter_mut()
+    {
+        if !is_line_terminator(*b) && !alphabet.is_valid(*b)
+        {
+            *b = unknown;
+            replaced += 1;
+        }
+    }
+    replaced
+}
+
+/// Remove all ASCII whitespace (including line terminators) from a sequence.
+pub fn strip_whitespace(seq: &mut Vec<u8>)
+{
+    seq.retain(|b| !b.is_ascii_whitespace());
+}
+
+/// Remove all ASCII digits from a sequence (e.g. GenBank-style position numbers).
+pub fn strip_digits(seq: &mut Vec<u8>)
+{
+    seq.retain(|b| !b.is_ascii_digit());
+}
+
+/// A configurable sequence normalizer.
+///
+/// Steps are applied in a fixed order: stripping of whitespace and digits,
+/// uppercasing, `U`→`T` conversion, replacement of invalid symbols and
+/// finally validation.
+///
+/// # Example
+///
+/// ```
+/// use fastx::alphabet::{Alphabet, Normalizer};
+///
+/// let normalizer = Normalizer::new()
+///     .with_strip_whitespace(true)
+///     .with_strip_digits(true)
+///     .with_uppercase(true)
+///     .with_validate(Some(Alphabet::Dna));
+///
+/// let mut seq = b"1 acgtac gt\n11 ACGT".to_vec();
+/// normalizer.apply(&mut seq).unwrap();
+/// assert_eq!(seq, b"ACGTACGTACGT");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Normalizer
+{
+    uppercase: bool,
+    rna_to_dna: bool,
+    strip_whitespace: bool,
+    strip_digits: bool,
+    replace_invalid: Option<Alphabet>,
+    validate: Option<Alphabet>,
+}
+
+impl Normalizer
+{
+    /// Create a normalizer that leaves sequences unchanged.
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Convert sequences to uppercase.
+    pub fn with_uppercase(mut self, enable: bool) -> Self
+    {
+        self.uppercase = enable;
+        self
+    }
+
+    /// Convert `U` to `T`.
+    pub fn with_rna_to_dna(mut self, enable: bool) -> Self
+    {
+        self.rna_to_dna = enable;
+        self
+    }
+
+    /// Remove whitespace, which joins multi-line sequences into one line.
+    pub fn with_strip_whitespace(mut self, enable: bool) -> Self
+    {
+        self.strip_whitespace = enable;
+        self
+    }
+
+    /// Remove digits.
+    pub fn with_strip_digits(mut self, enable: bool) -> Self
+    {
+        self.strip_digits = enable;
+        self
+    }
+
+    /// Replace bytes not in the given alphabet by `N` (nucleotides) or `X` (protein).
+    pub fn with_replace_invalid(mut self, alphabet: Option<Alphabet>) -> Self
+    {
+        self.replace_invalid = alphabet;
+        self
+    }
+
+    /// Fail with [`InvalidSymbol`] if the normalized sequence is not in the given alphabet.
+    pub fn with_validate(mut self, alphabet: Option<Alphabet>) -> Self
+    {
+        self.validate = alphabet;
+        self
+    }
+
+    /// Normalize a sequence in place.
+    ///
+    /// Line terminators are preserved unless whitespace is stripped, so this works
+    /// on both `seq()` and raw multi-line sequence data.
+    pub fn apply(&self, seq: &mut Vec<u8>) -> Result<(), InvalidSymbol>
+    {
+        if self.strip_whitespace
+        {
+            strip_whitespace(seq);
+        }
+        if self.strip_digits
+        {
+            strip_digits(seq);
+        }
+        self.apply_in_place(seq)
+    }
+
+    /// Normalize a sequence together with its per-base quality scores.
+    ///
+    /// Quality scores of stripped bases are removed as well, keeping both in sync.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The sequence and qualities were normalized
+    /// * `Err(io::Error)` - `InvalidData` if the sequence and quality lengths differ
+    ///   or validation fails; both are left unchanged if the lengths differ
+    pub fn apply_with_qual(&self, seq: &mut Vec<u8>, qual: &mut Vec<u8>) -> io::Result<()>
+    {
+        if seq.len() != qual.len()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Sequence length {} does not match quality length {}",
+                    seq.len(),
+                    qual.len()
+                ),
+            ));
+        }
+        if self.strip_whitespace || self.strip_digits
+        {
+            let keep = |b: u8| {
+                !(self.strip_whitespace && b.is_ascii_whitespace()
+                    || self.strip_digits && b.is_ascii_digit())
+            };
+            let mut kept = 0;
+            for i in 0..seq.len()
+            {
+                if keep(seq[i])
+                {
+                    seq[kept] = seq[i];
+                    qual[kept] = qual[i];
+                    kept += 1;
+                }
+            }
+            seq.truncate(kept);
+            qual.truncate(kept);
+        }
+        Ok(self.apply_in_place(seq)?)
+    }
+
+    fn apply_in_place(&self, seq: &mut [u8]) -> Result<(), InvalidSymbol>
+    {
+        if self.uppercase
+        {
+            uppercase(seq);
+        }
+        if self.rna_to_dna
+        {
+            rna_to_dna(seq);
+        }
+        if let Some(alphabet) = self.replace_invalid
+        {
+            replace_invalid(alphabet, seq);
+        }
+        if let Some(alphabet) = self.validate
+        {
+            alphabet.validate_raw(seq)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records whose sequence can be normalized in place.
+///
+/// Implemented by [`FastARecord`](crate::FastX::FastARecord) and
+/// [`FastQRecord`](crate::FastX::FastQRecord).
+pub trait Normalize
+{
+    /// Apply a [`Normalizer`] to the record's sequence.
+    ///
+    /// Failures are `io::ErrorKind::InvalidData` errors.
+    fn normalize(&mut self, normalizer: &Normalizer) -> io::Result<()>;
+}
+
+/// Iterator adapter applying a [`Normalizer`] to every record.
+///
+/// Created by [`FastXIterator::normalized`](crate::FastX::FastXIterator::normalized).
+pub struct Normalized<I>
+{
+    inner: I,
+    normalizer: Normalizer,
+}
+
+impl<I> Normalized<I>
+{
+    pub(crate) fn new(inner: I, normalizer: Normalizer) -> Self
+    {
+        Self { inner, normalizer }
+    }
+}
+
+impl<I, T> Iterator for Normalized<I>
+where
+    I: Iterator<Item = io::Result<T>>,
+    T: Normalize,
+{
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        let mut record = match self.inner.next()?
+        {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        match record.normalize(&self.normalizer)
+        {
+            Ok(()) => Some(Ok(record)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_validate()
+    {
+        assert!(Alphabet::Dna.validate(b"ACGTNacgtn").is_ok());
+        assert_eq!(
+            Alphabet::Dna.validate(b"ACGU"),
+            Err(InvalidSymbol {
+                byte: b'U',
+                position: 3
+            })
+        );
+        assert!(Alphabet::Rna.validate(b"ACGU").is_ok());
+        assert!(Alphabet::IupacNucleotide.validate(b"ACGTRYKMSWBDHVN-").is_ok());
+        assert!(Alphabet::Protein.validate(b"MKV*").is_ok());
+        assert!(Alphabet::Protein.validate(b"MK1").is_err());
+    }
+
+    #[test]
+    fn test_validate_raw_skips_newlines()
+    {
+        let err = Alphabet::Dna.validate_raw(b"ACGT\nAC*T").unwrap_err();
+        assert_eq!(err.byte, b'*');
+        assert_eq!(err.position, 6);
+    }
+
+    #[test]
+    fn test_normalizer_keeps_lines()
+    {
+        let normalizer = Normalizer::new()
+            .with_uppercase(true)
+            .with_replace_invalid(Some(Alphabet::Dna));
+        let mut seq = b"acgt\nacXt".to_vec();
+        normalizer.apply(&mut seq).unwrap();
+        assert_eq!(seq, b"ACGT\nACNT");
+    }
+
+    #[test]
+    fn test_apply_with_qual()
+    {
+        let normalizer = Normalizer::new().with_strip_whitespace(true);
+        let mut seq = b"AC GT".to_vec();
+        let mut qual = b"12345".to_vec();
+        normalizer.apply_with_qual(&mut seq, &mut qual).unwrap();
+        assert_eq!(seq, b"ACGT");
+        assert_eq!(qual, b"1245");
+
+        // Mismatched lengths are rejected instead of desynchronizing the two
+        let mut seq = b"AC GT".to_vec();
+        let mut qual = b"1234".to_vec();
+        let err = normalizer.apply_with_qual(&mut seq, &mut qual).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!((seq.as_slice(), qual.as_slice()), (&b"AC GT"[..], &b"1234"[..]));
+        let mut qual = b"1234".to_vec();
+        assert!(Normalizer::new().apply_with_qual(&mut b"ACGTA".to_vec(), &mut qual).is_err());
+    }
+}
@@ -0,0 +1,1193 @@
+//! Async readers for tokio.
+//!
+//! - [`FastXStream`] yields FASTA/FASTQ records from any [`AsyncBufRead`] as a
+//!   [`Stream`]. Records are framed asynchronously and then parsed by
+//!   [`FastXRead::read`], so they match the blocking iterators byte for byte.
+//! - [`AsyncBgzfReader`] decompresses BGZF data from an [`AsyncRead`].
+//! - [`AsyncIndexedReader`] fetches indexed sequences and regions of local or
+//!   remote files, awaiting one range request per fetch.
+
+use crate::bgzf::{
+    block_from_bytes, block_len_from_extra, decompress_block, extra_field_len, BLOCK_HEADER_LEN,
+};
+use crate::fai::FaiIndex;
+use crate::gzi::GziIndex;
+use crate::indexed::{find_index_file, parse_fai_from_bytes, parse_gzi_from_bytes};
+use crate::region::Region;
+use crate::remote::{ranges_unsupported, status_code_failure, Failure, RemoteConfig, SpillFile};
+use crate::shared::{
+    block_containing, collect_blocks, range_span, record_from_span, record_span, span_bases,
+};
+use crate::source::{resolve_url, FileSource, Location, RangeSource};
+use crate::FastX::{FastARecord, FastQRecord, FastXRead};
+use futures_core::Stream;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWriteExt, ReadBuf};
+
+/// A stream of FASTA or FASTQ records read from an [`AsyncBufRead`].
+///
+/// Use [`fasta_stream`] or [`fastq_stream`] to create instances. Like
+/// [`FastXIterator`](crate::FastX::FastXIterator), the stream ends after the
+/// first error.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::asynchronous::fastq_stream;
+/// use fastx::FastX::FastXRead;
+/// use tokio::io::BufReader;
+///
+/// async fn count_bases() -> std::io::Result<usize>
+/// {
+///     let file = tokio::fs::File::open("reads.fastq").await?;
+///     let mut records = fastq_stream(BufReader::new(file));
+///     let mut bases = 0;
+///     while let Some(record) = records.next_record().await
+///     {
+///         bases += record?.seq_len();
+///     }
+///     Ok(bases)
+/// }
+/// ```
+pub struct FastXStream<R, T>
+{
+    reader: R,
+    /// Bytes of the record being framed
+    buf: Vec<u8>,
+    /// Line breaks in `buf`
+    lines: usize,
+    fastq: bool,
+    done: bool,
+    _phantom: PhantomData<T>,
+}
+
+/// Create a stream of FASTA records.
+pub fn fasta_stream<R: AsyncBufRead + Unpin>(reader: R) -> FastXStream<R, FastARecord>
+{
+    FastXStream::new(reader, false)
+}
+
+/// Create a stream of FASTQ records.
+pub fn fastq_stream<R: AsyncBufRead + Unpin>(reader: R) -> FastXStream<R, FastQRecord>
+{
+    FastXStream::new(reader, true)
+}
+
+impl<R: AsyncBufRead + Unpin, T: FastXRead + Default + Unpin> FastXStream<R, T>
+{
+    fn new(reader: R, fastq: bool) -> Self
+    {
+        Self { reader, buf: Vec::new(), lines: 0, fastq, done: false, _phantom: PhantomData }
+    }
+
+    /// Read the next record, for callers without a `StreamExt` at hand.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Ok(record))` - The next record
+    /// * `Some(Err(e))` - If reading or parsing fails
+    /// * `None` - At the end of the input
+    pub async fn next_record(&mut self) -> Option<io::Result<T>>
+    {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    /// Get the underlying reader back.
+    pub fn into_inner(self) -> R
+    {
+        self.reader
+    }
+
+    /// Parse the framed record, ending the stream on errors and empty records.
+    fn parse(&mut self) -> Option<io::Result<T>>
+    {
+        let mut record = T::default();
+        let result = record.read(&mut &self.buf[..]);
+        self.buf.clear();
+        self.lines = 0;
+        match result
+        {
+            Ok(0) =>
+            {
+                self.done = true;
+                None
+            }
+            Ok(_) => Some(Ok(record)),
+            Err(e) =>
+            {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin, T: FastXRead + Default + Unpin> Stream for FastXStream<R, T>
+{
+    type Item = io::Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>
+    {
+        let this = self.get_mut();
+        loop
+        {
+            if this.done
+            {
+                return Poll::Ready(None);
+            }
+            let chunk = match ready!(Pin::new(&mut this.reader).poll_fill_buf(cx))
+            {
+                Ok(chunk) => chunk,
+                Err(e) =>
+                {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            };
+            if chunk.is_empty()
+            {
+                if this.buf.is_empty()
+                {
+                    this.done = true;
+                    return Poll::Ready(None);
+                }
+                return Poll::Ready(this.parse());
+            }
+
+            let (used, complete) = frame(this.fastq, &mut this.lines, chunk);
+            this.buf.extend_from_slice(&chunk[..used]);
+            Pin::new(&mut this.reader).consume(used);
+            if complete
+            {
+                return Poll::Ready(this.parse());
+            }
+        }
+    }
+}
+
+/// Find where the record being framed ends in `chunk`.
+///
+/// `lines` counts the line breaks of the record seen so far. Returns the number
+/// of bytes of `chunk` that belong to the record and whether the record is
+/// complete. A FASTQ record is four lines; a FASTA record is its header line and
+/// everything up to the next `>`, where [`FastARecord::read`] stops as well.
+fn frame(fastq: bool, lines: &mut usize, chunk: &[u8]) -> (usize, bool)
+{
+    let mut pos = 0;
+    loop
+    {
+        if fastq && *lines == 4
+        {
+            return (pos, true);
+        }
+        if !fastq && *lines > 0
+        {
+            return match memchr::memchr(b'>', &chunk[pos..])
+            {
+                Some(i) => (pos + i, true),
+                None => (chunk.len(), false),
+            };
+        }
+        match memchr::memchr(b'\n', &chunk[pos..])
+        {
+            Some(i) =>
+            {
+                pos += i + 1;
+                *lines += 1;
+            }
+            None => return (chunk.len(), false),
+        }
+    }
+}
+
+/// Which part of a BGZF block [`AsyncBgzfReader`] is reading.
+enum Stage
+{
+    /// The fixed header up to XLEN
+    Header,
+    /// The extra field holding the block size
+    Extra,
+    /// The rest of the block
+    Body,
+}
+
+/// An async reader for BGZF-compressed data.
+///
+/// Reads the blocks of a BGZF file in order and implements [`AsyncRead`] and
+/// [`AsyncBufRead`] over their decompressed contents. Blocks are checked and
+/// inflated by the same code as [`BgzfReader`](crate::bgzf::BgzfReader),
+/// including the CRC32 of every block. A missing EOF marker only fails reading
+/// with [`with_strict_eof`](AsyncBgzfReader::with_strict_eof).
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::asynchronous::{fasta_stream, AsyncBgzfReader};
+/// use fastx::FastX::FastXRead;
+///
+/// async fn print_ids() -> std::io::Result<()>
+/// {
+///     let file = tokio::fs::File::open("ref.fa.gz").await?;
+///     let mut records = fasta_stream(AsyncBgzfReader::new(file));
+///     while let Some(record) = records.next_record().await
+///     {
+///         println!("{}", record?.id());
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct AsyncBgzfReader<R>
+{
+    inner: R,
+    /// Compressed bytes of the block being read, sized to what is needed so far
+    raw: Vec<u8>,
+    /// Bytes of `raw` read so far
+    filled: usize,
+    stage: Stage,
+    /// Decompressed data of the current block
+    data: Vec<u8>,
+    /// Read position in `data`
+    pos: usize,
+    /// Compressed offset of the block being read
+    offset: u64,
+    /// Whether the last block was empty, as the EOF marker is
+    after_empty_block: bool,
+    /// Fail at the end of the input without the EOF marker
+    strict_eof: bool,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncBgzfReader<R>
+{
+    /// Create a reader for BGZF data starting at a block boundary.
+    pub fn new(inner: R) -> Self
+    {
+        Self {
+            inner,
+            raw: vec![0; BLOCK_HEADER_LEN],
+            filled: 0,
+            stage: Stage::Header,
+            data: Vec::new(),
+            pos: 0,
+            offset: 0,
+            after_empty_block: false,
+            strict_eof: false,
+            eof: false,
+        }
+    }
+
+    /// Fail reads that reach the end of the input without the BGZF EOF marker.
+    ///
+    /// Off by default, as for
+    /// [`BgzfReader::with_strict_eof`](crate::bgzf::BgzfReader::with_strict_eof).
+    pub fn with_strict_eof(mut self, strict: bool) -> Self
+    {
+        self.strict_eof = strict;
+        self
+    }
+
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R
+    {
+        &self.inner
+    }
+
+    /// Get the underlying reader back.
+    pub fn into_inner(self) -> R
+    {
+        self.inner
+    }
+
+    /// Read and decompress the next block, or reach the end of the input.
+    fn poll_block(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    {
+        loop
+        {
+            while self.filled < self.raw.len()
+            {
+                let mut buf = ReadBuf::new(&mut self.raw[self.filled..]);
+                ready!(Pin::new(&mut self.inner).poll_read(cx, &mut buf))?;
+                let n = buf.filled().len();
+                if n == 0
+                {
+                    return Poll::Ready(self.end_of_input());
+                }
+                self.filled += n;
+            }
+
+            match self.stage
+            {
+                Stage::Header =>
+                {
+                    let mut header = [0u8; BLOCK_HEADER_LEN];
+                    header.copy_from_slice(&self.raw);
+                    let xlen = extra_field_len(&header).map_err(|e| self.block_error(e))?;
+                    self.raw.resize(BLOCK_HEADER_LEN + xlen, 0);
+                    self.stage = Stage::Extra;
+                }
+                Stage::Extra =>
+                {
+                    let block_len = block_len_from_extra(&self.raw[BLOCK_HEADER_LEN..])
+                        .map_err(|e| self.block_error(e))?;
+                    if block_len < self.raw.len()
+                    {
+                        let e = io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Invalid BGZF block size: {}", block_len),
+                        );
+                        return Poll::Ready(Err(self.block_error(e)));
+                    }
+                    self.raw.resize(block_len, 0);
+                    self.stage = Stage::Body;
+                }
+                Stage::Body =>
+                {
+                    self.data = decompress_block(&self.raw).map_err(|e| self.block_error(e))?;
+                    self.pos = 0;
+                    self.offset += self.raw.len() as u64;
+                    self.after_empty_block = self.data.is_empty();
+                    self.raw.truncate(BLOCK_HEADER_LEN);
+                    self.filled = 0;
+                    self.stage = Stage::Header;
+                    return Poll::Ready(Ok(()));
+                }
+            }
+        }
+    }
+
+    /// Handle the end of the input while reading a block.
+    fn end_of_input(&mut self) -> io::Result<()>
+    {
+        if !matches!(self.stage, Stage::Header) || self.filled > 0
+        {
+            let e = io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated BGZF block");
+            return Err(self.block_error(e));
+        }
+        // A complete file ends with the empty EOF marker block
+        if self.strict_eof && self.offset > 0 && !self.after_empty_block
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("BGZF EOF marker missing at offset {}", self.offset),
+            ));
+        }
+        self.eof = true;
+        Ok(())
+    }
+
+    /// Add the offset of the current block to an error.
+    fn block_error(&self, e: io::Error) -> io::Error
+    {
+        io::Error::new(e.kind(), format!("{} (BGZF block at offset {})", e, self.offset))
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncBufRead for AsyncBgzfReader<R>
+{
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>>
+    {
+        let this = self.get_mut();
+        // Loop past empty blocks, so concatenated files read as one
+        while this.pos >= this.data.len() && !this.eof
+        {
+            ready!(this.poll_block(cx))?;
+        }
+        Poll::Ready(Ok(&this.data[this.pos.min(this.data.len())..]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize)
+    {
+        let this = self.get_mut();
+        this.pos = (this.pos + amt).min(this.data.len());
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncBgzfReader<R>
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>>
+    {
+        let available = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let n = available.len().min(buf.remaining());
+        buf.put_slice(&available[..n]);
+        self.consume(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A boxed future, as returned by [`AsyncRangeSource::read_range`].
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A byte source serving ranges asynchronously, such as a local file or a URL.
+///
+/// This is the async counterpart of [`RangeSource`](crate::source::RangeSource).
+pub trait AsyncRangeSource: Send + Sync
+{
+    /// Read the bytes `[start, end)`, or from `start` to the end of the file if
+    /// `end` is None.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bytes)` - The bytes, fewer if the file ends before `end`
+    /// * `Err(io::Error)` - If reading fails
+    fn read_range(&self, start: u64, end: Option<u64>) -> BoxFuture<'_, io::Result<Vec<u8>>>;
+}
+
+/// A local file read with tokio.
+///
+/// The file is opened once and read with positional reads on tokio's blocking
+/// thread pool, so concurrent reads do not share a file cursor.
+pub struct AsyncFileSource
+{
+    source: Arc<FileSource>,
+}
+
+impl AsyncFileSource
+{
+    /// Open a file as a source.
+    pub async fn open(path: impl AsRef<Path>) -> io::Result<Self>
+    {
+        let path = path.as_ref().to_path_buf();
+        let source = blocking(move || FileSource::open(path)).await?;
+        Ok(Self { source: Arc::new(source) })
+    }
+}
+
+impl AsyncRangeSource for AsyncFileSource
+{
+    fn read_range(&self, start: u64, end: Option<u64>) -> BoxFuture<'_, io::Result<Vec<u8>>>
+    {
+        let source = Arc::clone(&self.source);
+        Box::pin(blocking(move || {
+            let size = source.size()?;
+            let from = start.min(size);
+            let to = end.map_or(size, |end| end.min(size)).max(from);
+            let mut data = vec![0; (to - from) as usize];
+            source.read_exact_at(from, &mut data)?;
+            Ok(data)
+        }))
+    }
+}
+
+/// Run blocking file I/O on tokio's blocking thread pool.
+async fn blocking<T, F>(f: F) -> io::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await.map_err(io::Error::other)?
+}
+
+/// A remote file read with async HTTP range requests.
+///
+/// Requests carry the headers of the [`RemoteConfig`] and are retried with its
+/// [`RetryPolicy`](crate::remote::RetryPolicy). `s3://` URLs are signed like
+/// for the blocking readers.
+///
+/// A server that ignores range requests is downloaded once into a spill file
+/// in the directory set by
+/// [`RemoteConfig::with_spill_dir`](crate::remote::RemoteConfig::with_spill_dir),
+/// which then serves all reads; without a spill directory such reads fail with
+/// `ErrorKind::Unsupported`.
+pub struct AsyncHttpSource
+{
+    client: reqwest::Client,
+    url: String,
+    config: RemoteConfig,
+    spill: Mutex<Option<Arc<SpillFile>>>,
+}
+
+impl AsyncHttpSource
+{
+    /// Create a source for an `http://` or `https://` URL.
+    pub fn new(url: &str, config: &RemoteConfig) -> io::Result<Self>
+    {
+        let client = config.build_async_client()?;
+        Ok(Self { client, url: url.to_string(), config: config.clone(), spill: Mutex::new(None) })
+    }
+
+    /// The spill file, if the whole file has been downloaded.
+    fn spill(&self) -> io::Result<Option<Arc<SpillFile>>>
+    {
+        let spill = self.spill.lock().map_err(|_| io::Error::other("Spill lock poisoned"))?;
+        Ok(spill.clone())
+    }
+
+    /// Send one ranged GET request.
+    ///
+    /// A server ignoring the range sends the whole file, which is written to a
+    /// spill file unless `whole` asks for the whole file anyway.
+    async fn get(&self, range: &str, whole: bool, timeout: Option<Duration>)
+        -> Result<Fetched, Failure>
+    {
+        let mut request = self.client.get(&self.url).header("Range", range);
+        for (name, value) in self.config.headers()
+        {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Ok(uri) = self.url.parse()
+        {
+            for (name, value) in self.config.s3_signature("GET", &uri, Some(range))
+            {
+                request = request.header(name, value);
+            }
+        }
+        if let Some(timeout) = timeout
+        {
+            request = request.timeout(timeout);
+        }
+
+        let response = request.send().await.map_err(request_failure)?;
+        let status = response.status().as_u16();
+        if !response.status().is_success()
+        {
+            let retry_after = response.headers().get("Retry-After").and_then(|v| v.to_str().ok());
+            return Err(status_code_failure(status, retry_after, &self.url));
+        }
+        if status != 206 && !whole
+        {
+            let dir = self.config.spill_dir().ok_or_else(|| ranges_unsupported(&self.url));
+            let dir = dir.map_err(Failure::fatal)?.to_path_buf();
+            return Ok(Fetched::Spilled(self.spill_response(dir, response).await?));
+        }
+
+        let expected = if status == 206 { content_range_len(&response) } else { None };
+        let body = read_body(response).await?;
+        if let Some(expected) = expected.filter(|&n| (body.len() as u64) < n)
+        {
+            return Err(Failure::transient(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "Truncated response for {}: got {} of {} bytes",
+                    range,
+                    body.len(),
+                    expected
+                ),
+            )));
+        }
+        Ok(Fetched::Range(body))
+    }
+
+    /// Stream a whole-file response into a spill file in `dir`.
+    async fn spill_response(
+        &self,
+        dir: PathBuf,
+        mut response: reqwest::Response,
+    ) -> Result<SpillFile, Failure>
+    {
+        let size = response.content_length();
+        let created = blocking(move || SpillFile::new_file(&dir)).await;
+        let (file, path) = created.map_err(Failure::fatal)?;
+        let writer = tokio::fs::File::from_std(file.try_clone().map_err(Failure::fatal)?);
+        let written = write_body(writer, &mut response).await;
+        // A failed download is removed when dropped
+        let spill = SpillFile::from_file(file, path, *written.as_ref().unwrap_or(&0));
+        written?;
+        match size
+        {
+            Some(size) if size != spill.len() => Err(Failure::transient(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "Truncated download of {}: got {} of {} bytes",
+                    self.url,
+                    spill.len(),
+                    size
+                ),
+            ))),
+            _ => Ok(spill),
+        }
+    }
+}
+
+/// The answer to a ranged GET request.
+enum Fetched
+{
+    /// The requested bytes
+    Range(Vec<u8>),
+    /// The whole file, from a server ignoring the range
+    Spilled(SpillFile),
+}
+
+impl AsyncRangeSource for AsyncHttpSource
+{
+    fn read_range(&self, start: u64, end: Option<u64>) -> BoxFuture<'_, io::Result<Vec<u8>>>
+    {
+        Box::pin(async move {
+            if end.is_some_and(|end| end <= start)
+            {
+                return Ok(Vec::new());
+            }
+            if let Some(spill) = self.spill()?
+            {
+                return read_spill(spill, start, end).await;
+            }
+            let range = match end
+            {
+                Some(end) => format!("bytes={}-{}", start, end - 1),
+                None => format!("bytes={}-", start),
+            };
+            let whole = start == 0 && end.is_none();
+            let policy = self.config.retry_policy();
+            match policy.run_async(|timeout| self.get(&range, whole, timeout)).await?
+            {
+                Fetched::Range(body) => Ok(body),
+                Fetched::Spilled(spill) =>
+                {
+                    let spill = Arc::new(spill);
+                    *self.spill.lock().map_err(|_| io::Error::other("Spill lock poisoned"))? =
+                        Some(Arc::clone(&spill));
+                    read_spill(spill, start, end).await
+                }
+            }
+        })
+    }
+}
+
+/// Read the bytes `[start, end)` of a spill file.
+async fn read_spill(spill: Arc<SpillFile>, start: u64, end: Option<u64>) -> io::Result<Vec<u8>>
+{
+    blocking(move || spill.read(start, end.map_or(u64::MAX, |end| end - start))).await
+}
+
+/// Get the length of the range in a `Content-Range: bytes start-end/size` header.
+fn content_range_len(response: &reqwest::Response) -> Option<u64>
+{
+    let value = response.headers().get("Content-Range")?.to_str().ok()?;
+    let (range, _) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    end.trim().parse::<u64>().ok()?.checked_sub(start.trim().parse().ok()?)?.checked_add(1)
+}
+
+/// Read a response body chunk by chunk.
+async fn read_body(mut response: reqwest::Response) -> Result<Vec<u8>, Failure>
+{
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(request_failure)?
+    {
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Write a response body to a file chunk by chunk, returning its length.
+async fn write_body(
+    mut file: tokio::fs::File,
+    response: &mut reqwest::Response,
+) -> Result<u64, Failure>
+{
+    let mut len = 0;
+    while let Some(chunk) = response.chunk().await.map_err(request_failure)?
+    {
+        file.write_all(&chunk).await.map_err(Failure::fatal)?;
+        len += chunk.len() as u64;
+    }
+    file.flush().await.map_err(Failure::fatal)?;
+    Ok(len)
+}
+
+/// Classify a request error from reqwest.
+///
+/// Timeouts, failed connections and bodies cut short are retried; HTTP statuses
+/// are classified by `status_code_failure`.
+fn request_failure(error: reqwest::Error) -> Failure
+{
+    let message = format!("HTTP GET request failed: {}", error);
+    if error.is_timeout()
+    {
+        Failure::transient(io::Error::new(io::ErrorKind::TimedOut, message))
+    }
+    else if error.is_connect()
+    {
+        Failure::transient(io::Error::new(io::ErrorKind::ConnectionRefused, message))
+    }
+    else if error.is_body() || error.is_decode()
+    {
+        Failure::transient(io::Error::new(io::ErrorKind::UnexpectedEof, message))
+    }
+    else if error.is_builder()
+    {
+        Failure::fatal(io::Error::new(io::ErrorKind::InvalidInput, message))
+    }
+    else
+    {
+        Failure::fatal(io::Error::other(message))
+    }
+}
+
+/// Open a URL as an async byte source, choosing the backend by scheme.
+///
+/// # Arguments
+///
+/// * `url` - A `file://`, `http://`, `https://` or `s3://` URL
+/// * `config` - Connection settings for remote backends
+pub async fn open_url(url: &str, config: &RemoteConfig) -> io::Result<Arc<dyn AsyncRangeSource>>
+{
+    Ok(match resolve_url(url, config)?
+    {
+        Location::File(path) => Arc::new(AsyncFileSource::open(path).await?),
+        Location::Http(url, config) => Arc::new(AsyncHttpSource::new(&url, &config)?),
+    })
+}
+
+/// An async indexed FASTA reader for local and remote files.
+///
+/// Reads BGZF-compressed files with a .gzi index, or uncompressed files
+/// without one. Each fetch awaits a single range request covering the
+/// requested bases; decompression and parsing share their code with
+/// [`SharedIndexedReader`](crate::shared::SharedIndexedReader). Fetches take
+/// `&self` and clones share the source and indexes, so many fetches can be in
+/// flight at once.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::asynchronous::AsyncIndexedReader;
+/// use fastx::remote::RemoteConfig;
+///
+/// async fn fetch() -> std::io::Result<()>
+/// {
+///     let reader = AsyncIndexedReader::from_url(
+///         "https://example.com/ref.fa.gz",
+///         "https://example.com/ref.fa.gz.fai",
+///         Some("https://example.com/ref.fa.gz.gzi"),
+///         &RemoteConfig::default(),
+///     )
+///     .await?;
+///     let bases = reader.fetch_range("chr1", 10_000, 20_000).await?;
+///     println!("{} bp", bases.len());
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct AsyncIndexedReader
+{
+    source: Arc<dyn AsyncRangeSource>,
+    fai_index: Arc<FaiIndex>,
+    /// Index of the BGZF blocks, None for uncompressed files
+    gzi_index: Option<Arc<GziIndex>>,
+}
+
+impl AsyncIndexedReader
+{
+    /// Create a reader from a source and its indexes.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The FASTA file
+    /// * `fai_index` - The .fai index
+    /// * `gzi_index` - The .gzi index if the file is BGZF-compressed, None if it
+    ///   is uncompressed
+    pub fn new(
+        source: Arc<dyn AsyncRangeSource>,
+        fai_index: FaiIndex,
+        gzi_index: Option<GziIndex>,
+    ) -> Self
+    {
+        Self { source, fai_index: Arc::new(fai_index), gzi_index: gzi_index.map(Arc::new) }
+    }
+
+    /// Open a local FASTA file with its .fai index, and its .gzi index if it
+    /// is BGZF-compressed.
+    ///
+    /// The indexes are looked up like in
+    /// [`IndexedFastXReader::from_path`](crate::indexed::IndexedFastXReader::from_path).
+    pub async fn from_path(path: &Path) -> io::Result<Self>
+    {
+        let fai_path = find_index_file(path, "fai").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("FAI index not found for {}", path.display()),
+            )
+        })?;
+        let fai_index = parse_fai_from_bytes(&tokio::fs::read(fai_path).await?)?;
+        let gzi_index = match find_index_file(path, "gzi")
+        {
+            Some(gzi_path) => Some(parse_gzi_from_bytes(&tokio::fs::read(gzi_path).await?)?),
+            None => None,
+        };
+
+        let source = AsyncFileSource::open(path).await?;
+        if gzi_index.is_none() && source.read_range(0, Some(2)).await? == [0x1f, 0x8b]
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("GZI index not found for compressed file {}", path.display()),
+            ));
+        }
+        Ok(Self::new(Arc::new(source), fai_index, gzi_index))
+    }
+
+    /// Open a FASTA file and its indexes from URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_url` - URL of the FASTA file
+    /// * `fai_url` - URL of the .fai index
+    /// * `gzi_url` - URL of the .gzi index for BGZF-compressed files, None for
+    ///   uncompressed files
+    /// * `config` - Connection settings
+    ///
+    /// URLs may use the `file`, `http`, `https` and `s3` schemes.
+    pub async fn from_url(
+        data_url: &str,
+        fai_url: &str,
+        gzi_url: Option<&str>,
+        config: &RemoteConfig,
+    ) -> io::Result<Self>
+    {
+        let read_index = |url: &str| {
+            let url = url.to_string();
+            async move { open_url(&url, config).await?.read_range(0, None).await }
+        };
+        let fai_index = parse_fai_from_bytes(&read_index(fai_url).await?)?;
+        let gzi_index = match gzi_url
+        {
+            Some(url) => Some(parse_gzi_from_bytes(&read_index(url).await?)?),
+            None => None,
+        };
+        Ok(Self::new(open_url(data_url, config).await?, fai_index, gzi_index))
+    }
+
+    /// Get a reference to the FAI index.
+    pub fn index(&self) -> &FaiIndex
+    {
+        &self.fai_index
+    }
+
+    /// Get a reference to the GZI index, if the file is compressed.
+    pub fn gzi_index(&self) -> Option<&GziIndex>
+    {
+        self.gzi_index.as_deref()
+    }
+
+    /// Check if a sequence exists in the index.
+    pub fn contains(&self, seq_id: &str) -> bool
+    {
+        self.fai_index.contains(seq_id)
+    }
+
+    /// Get all sequence names in the index.
+    pub fn sequence_names(&self) -> Vec<&str>
+    {
+        self.fai_index.sequence_names().collect()
+    }
+
+    /// Fetch a sequence by its ID.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(FastARecord)` - The fetched sequence record
+    /// * `Err(io::Error)` - If the sequence is not found or reading fails
+    pub async fn fetch(&self, seq_id: &str) -> io::Result<FastARecord>
+    {
+        let entry = self.entry(seq_id)?;
+        let (span_start, span_end) = record_span(entry);
+        record_from_span(entry, span_start, &self.read_uncompressed(span_start, span_end).await?)
+    }
+
+    /// Fetch the bases in `[start, end)` of a sequence, `end` clamped to its length.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<u8>)` - The sequence data for the requested region
+    /// * `Err(io::Error)` - If the sequence is not found or reading fails
+    pub async fn fetch_range(&self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
+    {
+        let entry = self.entry(seq_id)?;
+        let (span_start, span_end, bases) = range_span(entry, start, end)?;
+        span_bases(self.read_uncompressed(span_start, span_end).await?, bases)
+    }
+
+    /// Fetch the bases of a [`Region`].
+    pub async fn fetch_region(&self, region: &Region) -> io::Result<Vec<u8>>
+    {
+        self.fetch_range(&region.name, region.start, region.end_or_max()).await
+    }
+
+    /// Read the uncompressed bytes `[start, end)` of the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<u8>)` - The bytes, fewer if the file ends before `end`
+    /// * `Err(io::Error)` - If reading or decompressing fails
+    pub async fn read_uncompressed(&self, start: u64, end: u64) -> io::Result<Vec<u8>>
+    {
+        if start >= end
+        {
+            return Ok(Vec::new());
+        }
+        let gzi_index = match &self.gzi_index
+        {
+            Some(gzi_index) => gzi_index,
+            None => return self.source.read_range(start, Some(end)).await,
+        };
+
+        // Fetch all blocks of the span at once, then inflate them in memory
+        let (offset, block_start) = block_containing(gzi_index, start);
+        let (_, compressed_end) = gzi_index.compressed_range(start, end);
+        let bytes = self.source.read_range(offset, compressed_end).await?;
+        let limit = offset + bytes.len() as u64;
+        collect_blocks(offset, block_start, start, end, limit, |at| {
+            Ok(Arc::new(block_from_bytes(&bytes[(at - offset) as usize..])?))
+        })
+    }
+
+    /// Look up the FAI entry of a sequence.
+    fn entry(&self, seq_id: &str) -> io::Result<&crate::fai::FaiEntry>
+    {
+        self.fai_index.get(seq_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Sequence '{}' not found in index", seq_id),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::bgzf::TestReference;
+    use crate::remote::RetryPolicy;
+    use crate::test_server::{Fault, StubServer};
+    use crate::FastX::{fasta_iter, fastq_iter, FastQRead};
+    use tokio::io::{AsyncReadExt, BufReader};
+
+    #[tokio::test]
+    async fn test_record_streams()
+    {
+        // Tiny buffers split records, headers and '>' characters across chunks
+        let fasta = b">seq1 a>b\nACGT\nAC\n>seq2\n\nGGG\n>seq3 empty\n>seq4\nTT".to_vec();
+        for capacity in [1, 3, 7, 64]
+        {
+            let mut records = fasta_stream(BufReader::with_capacity(capacity, &fasta[..]));
+            let mut expected = fasta_iter(std::io::BufReader::new(&fasta[..]));
+            loop
+            {
+                match (records.next_record().await, expected.next())
+                {
+                    (Some(record), Some(want)) =>
+                    {
+                        let (record, want) = (record.unwrap(), want.unwrap());
+                        assert_eq!((record.name(), record.seq()), (want.name(), want.seq()));
+                    }
+                    (None, None) => break,
+                    (got, want) => panic!("{:?} vs {:?}", got.is_some(), want.is_some()),
+                }
+            }
+        }
+
+        let fastq = b"@r1 x\nACGT\n+\nIIII\n@r2\nGG\n+r2\n!!\n".to_vec();
+        let mut records = fastq_stream(BufReader::with_capacity(5, &fastq[..]));
+        let mut count = 0;
+        for want in fastq_iter(std::io::BufReader::new(&fastq[..]))
+        {
+            let (record, want) = (records.next_record().await.unwrap().unwrap(), want.unwrap());
+            assert_eq!(
+                (record.name(), record.seq(), record.qual()),
+                (want.name(), want.seq(), want.qual())
+            );
+            count += 1;
+        }
+        assert_eq!(count, 2);
+        assert!(records.next_record().await.is_none());
+
+        let truncated = b"@r1\nACGT\n+\n".to_vec();
+        let mut records = fastq_stream(&truncated[..]);
+        let error = records.next_record().await.unwrap().err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(records.next_record().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_async_bgzf()
+    {
+        let text: Vec<u8> =
+            (0..400).flat_map(|i| format!(">s{}\nACGTTGCA{}\n", i, i % 7).into_bytes()).collect();
+        let (data, _) = crate::bgzf::compress_for_tests(&text, 500);
+
+        let mut decoded = Vec::new();
+        AsyncBgzfReader::new(&data[..]).read_to_end(&mut decoded).await.unwrap();
+        assert_eq!(decoded, text);
+
+        // Concatenated files read as one
+        let twice = [data.clone(), data.clone()].concat();
+        let mut decoded = Vec::new();
+        AsyncBgzfReader::new(&twice[..]).read_to_end(&mut decoded).await.unwrap();
+        assert_eq!(decoded.len(), 2 * text.len());
+
+        let mut records = fasta_stream(AsyncBgzfReader::new(&data[..]));
+        let mut ids = Vec::new();
+        while let Some(record) = records.next_record().await
+        {
+            ids.push(record.unwrap().id().to_string());
+        }
+        assert_eq!(ids.len(), 400);
+        assert_eq!(ids[399], "s399");
+
+        // Without the 28 byte EOF marker the data is only reported as incomplete if asked
+        let truncated = &data[..data.len() - 28];
+        let mut decoded = Vec::new();
+        AsyncBgzfReader::new(truncated).read_to_end(&mut decoded).await.unwrap();
+        assert_eq!(decoded.len(), text.len());
+        let mut strict = AsyncBgzfReader::new(truncated).with_strict_eof(true);
+        let result = strict.read_to_end(&mut Vec::new()).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut corrupt = data.clone();
+        corrupt[30] ^= 0xff;
+        let result = AsyncBgzfReader::new(&corrupt[..]).read_to_end(&mut Vec::new()).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_async_indexed_reader()
+    {
+        let seqs: Vec<(String, Vec<u8>)> = (0..3)
+            .map(|i| {
+                let seq = (0..2000 + i * 777).map(|j| b"ACGT"[(j * 7 + i) % 4]).collect();
+                (format!("chr{}", i + 1), seq)
+            })
+            .collect();
+        let reference = TestReference::new(&seqs, 700);
+        let dir = reference.write_to_temp_dir("fastx-async");
+        std::fs::write(dir.join("plain.fa"), &reference.text).unwrap();
+        std::fs::write(dir.join("plain.fa.fai"), &reference.fai).unwrap();
+
+        let server = StubServer::with_files(vec![
+            ("/ref.fa.gz", reference.data.clone()),
+            ("/ref.fa.gz.fai", reference.fai.clone().into_bytes()),
+            ("/ref.fa.gz.gzi", reference.gzi.clone()),
+        ]);
+        // A transient failure is retried
+        server.push_fault(Fault::Status(503, Some(0)));
+        let remote = AsyncIndexedReader::from_url(
+            &server.url_for("/ref.fa.gz"),
+            &server.url_for("/ref.fa.gz.fai"),
+            Some(&server.url_for("/ref.fa.gz.gzi")),
+            &RemoteConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        let readers = [
+            AsyncIndexedReader::from_path(&dir.join("ref.fa.gz")).await.unwrap(),
+            AsyncIndexedReader::from_path(&dir.join("plain.fa")).await.unwrap(),
+            remote,
+        ];
+        assert!(readers[0].gzi_index().is_some() && readers[1].gzi_index().is_none());
+        for reader in &readers
+        {
+            for (name, seq) in &seqs
+            {
+                for (start, end) in [(0, 1), (59, 61), (100, 1500), (1990, 10_000)]
+                {
+                    let expected = &seq[start..end.min(seq.len())];
+                    let bases = reader.fetch_range(name, start as u64, end as u64).await.unwrap();
+                    assert_eq!(bases, expected);
+                }
+                let record = reader.fetch(name).await.unwrap();
+                assert_eq!((record.id(), record.seq()), (name.as_str(), seq.clone()));
+            }
+            let region: Region = "chr2:11-20".parse().unwrap();
+            assert_eq!(reader.fetch_region(&region).await.unwrap(), &seqs[1].1[10..20]);
+            let missing = reader.fetch("chrX").await.err().unwrap();
+            assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+            assert_eq!(reader.read_uncompressed(0, u64::MAX).await.unwrap(), reference.text);
+        }
+
+        // Each fetch is a single range request
+        let before = server.request_count();
+        readers[2].fetch_range("chr3", 100, 2600).await.unwrap();
+        assert_eq!(server.request_count(), before + 1);
+
+        // Concurrent fetches through clones
+        let tasks: Vec<_> = (0..8u64)
+            .map(|i| {
+                let reader = readers[2].clone();
+                tokio::spawn(async move {
+                    reader.fetch_range("chr1", i * 200, i * 200 + 150).await
+                })
+            })
+            .collect();
+        for (i, task) in tasks.into_iter().enumerate()
+        {
+            assert_eq!(task.await.unwrap().unwrap(), &seqs[0].1[i * 200..i * 200 + 150]);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_range_sources()
+    {
+        let data: Vec<u8> = (0..5000u32).map(|i| (i % 239) as u8).collect();
+
+        let path = std::env::temp_dir().join(format!("fastx-async-src-{}", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let file = AsyncFileSource::open(&path).await.unwrap();
+        assert_eq!(file.read_range(1000, Some(1100)).await.unwrap(), &data[1000..1100]);
+        assert_eq!(file.read_range(4900, Some(6000)).await.unwrap(), &data[4900..]);
+        assert_eq!(file.read_range(4000, None).await.unwrap(), &data[4000..]);
+        assert!(file.read_range(6000, None).await.unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+
+        // Ignored ranges: the first 200 response is spilled and serves all reads
+        let server = StubServer::new(data.clone());
+        server.ignore_ranges();
+        let http = AsyncHttpSource::new(&server.url, &RemoteConfig::default()).unwrap();
+        assert_eq!(http.read_range(1000, Some(1100)).await.unwrap(), &data[1000..1100]);
+        let requests = server.request_count();
+        assert_eq!(http.read_range(4000, Some(6000)).await.unwrap(), &data[4000..]);
+        assert_eq!(http.read_range(0, Some(10)).await.unwrap(), &data[..10]);
+        assert_eq!(server.request_count(), requests);
+
+        // Without a spill directory such servers are reported clearly
+        let config = RemoteConfig::default().with_spill_dir(None);
+        let http = AsyncHttpSource::new(&server.url, &config).unwrap();
+        let err = http.read_range(1000, Some(1100)).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(err.to_string().contains("does not support range requests"));
+        assert_eq!(http.read_range(0, None).await.unwrap(), data);
+
+        // Bodies cut short are retried, whether or not their length was announced
+        let config = RemoteConfig::default().with_retry_policy(
+            RetryPolicy::default()
+                .with_max_retries(1)
+                .with_backoff(Duration::from_millis(1), Duration::from_millis(1)),
+        );
+        let server = StubServer::new(data.clone());
+        let http = AsyncHttpSource::new(&server.url, &config).unwrap();
+        server.push_fault(Fault::Truncate);
+        assert_eq!(http.read_range(1000, Some(3000)).await.unwrap(), &data[1000..3000]);
+        server.omit_content_length();
+        server.push_fault(Fault::Truncate);
+        assert_eq!(http.read_range(1000, Some(3000)).await.unwrap(), &data[1000..3000]);
+        assert_eq!(server.request_count(), 4);
+        let server = StubServer::new(data.clone());
+        server.ignore_ranges();
+        server.push_fault(Fault::Truncate);
+        let http = AsyncHttpSource::new(&server.url, &config).unwrap();
+        assert_eq!(http.read_range(1000, Some(3000)).await.unwrap(), &data[1000..3000]);
+        assert_eq!(server.request_count(), 2);
+
+        // Client errors are not retried
+        let server = StubServer::new(data.clone());
+        server.push_fault(Fault::Status(404, None));
+        let http = AsyncHttpSource::new(&server.url, &RemoteConfig::default()).unwrap();
+        let err = http.read_range(0, Some(10)).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(server.request_count(), 1);
+    }
+}
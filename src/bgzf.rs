@@ -3,9 +3,11 @@
 //! BGZF is a variant of gzip that uses independent blocks for random access.
 //! Each block is a valid gzip member, allowing decompression from any block boundary.
 
+use crate::cache::{CacheStats, LruCache};
 use crate::gzi::GziIndex;
-use flate2::Decompress;
+use flate2::{Crc, Decompress, FlushDecompress, Status};
 use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::sync::Arc;
 
 /// BGZF magic numbers and constants
 const GZIP_ID1: u8 = 0x1f;
@@ -17,12 +19,78 @@ const GZIP_OS_UNKNOWN: u8 = 255;
 const BGZF_EXTRA_ID: u8 = 66; // 'B'
 const BGZF_EXTRA_SUBFIELD: u8 = 67; // 'C'
 const BGZF_MAX_BLOCK_SIZE: usize = 64 * 1024;
+/// Length of the empty block that ends a BGZF file.
+const EOF_MARKER_LEN: u64 = 28;
+
+/// Bits of a virtual offset that hold the offset within the uncompressed block.
+const VIRTUAL_OFFSET_BITS: u32 = 16;
+
+/// Default upper bound for cached decompressed blocks (8MB).
+pub const DEFAULT_CACHE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// A decompressed BGZF block.
+#[derive(Default)]
+pub(crate) struct Block
+{
+    pub(crate) data: Vec<u8>,
+    /// Length of the compressed block, to find the next one
+    pub(crate) compressed_len: u64,
+}
+
+/// The result of [`BgzfReader::verify`].
+#[derive(Debug)]
+pub struct VerifyReport
+{
+    /// Number of valid blocks, including the EOF marker
+    pub blocks: u64,
+    /// Total uncompressed size of the valid blocks
+    pub uncompressed_size: u64,
+    /// Whether the file ends with the BGZF EOF marker block
+    pub eof_marker: bool,
+    /// The first corrupt block, if any
+    pub corrupt_block: Option<CorruptBlock>,
+}
+
+impl VerifyReport
+{
+    /// True if no block is corrupt and the file ends with the EOF marker.
+    pub fn is_ok(&self) -> bool
+    {
+        self.corrupt_block.is_none() && self.eof_marker
+    }
+}
+
+/// A block that failed to decompress or to match its trailer.
+#[derive(Debug)]
+pub struct CorruptBlock
+{
+    /// Compressed offset of the block
+    pub offset: u64,
+    /// What is wrong with it
+    pub error: io::Error,
+}
 
 /// A BGZF reader with optional index for seeking.
 ///
 /// This reader can decompress BGZF-compressed data and, when provided with
 /// a .gzi index, can seek to arbitrary positions in the uncompressed stream.
 ///
+/// Each block's CRC32 and ISIZE are checked against its inflated data (see
+/// [`with_checksums`](BgzfReader::with_checksums)). Truncated files are told
+/// apart from complete ones by the BGZF EOF marker block at the end: a missing
+/// marker is reported by [`verify`](BgzfReader::verify), or fails reading with
+/// [`with_strict_eof`](BgzfReader::with_strict_eof).
+///
+/// Positions can be saved and restored as htslib-style virtual offsets with
+/// [`virtual_position`](BgzfReader::virtual_position) and
+/// [`seek_virtual`](BgzfReader::seek_virtual), which need no index. With a .gzi
+/// index, the reader also implements [`Seek`] on uncompressed positions.
+///
+/// Decompressed blocks are kept in an LRU cache keyed by compressed offset
+/// (see [`with_cache_size`](BgzfReader::with_cache_size)), so seeking back to
+/// a recently read block does not inflate it again. Seeking within the
+/// current block only moves the read position.
+///
 /// # Type Parameters
 ///
 /// * `R` - The underlying reader type (must implement Read and Seek)
@@ -45,12 +113,23 @@ pub struct BgzfReader<R: Read + Seek>
     inner: R,
     /// Optional .gzi index for seeking
     gzi_index: Option<GziIndex>,
-    /// Decompression buffer
-    decompressed_buf: Vec<u8>,
-    /// Current position in decompressed buffer
+    /// The current decompressed block
+    block: Arc<Block>,
+    /// Current position in the decompressed block
     buf_pos: usize,
-    /// Current uncompressed position (for tracking)
-    current_uncompressed_pos: u64,
+    /// Compressed offset of the block after the current one
+    next_offset: u64,
+    /// Compressed offset the inner reader is at, None if unknown
+    inner_pos: Option<u64>,
+    /// Recently decompressed blocks by compressed offset
+    cache: LruCache<u64, Arc<Block>>,
+    /// Check the CRC32 of each block
+    verify_checksums: bool,
+    /// Fail at the end of a file without the EOF marker
+    strict_eof: bool,
+    /// Uncompressed offset of the end of the current block, None if unknown after
+    /// seeking to a virtual offset without a .gzi entry
+    current_uncompressed_pos: Option<u64>,
     /// End of stream flag
     eof: bool,
 }
@@ -65,9 +144,14 @@ impl<R: Read + Seek> BgzfReader<R>
         Self {
             inner,
             gzi_index: None,
-            decompressed_buf: Vec::new(),
+            block: Arc::default(),
             buf_pos: 0,
-            current_uncompressed_pos: 0,
+            next_offset: 0,
+            inner_pos: Some(0),
+            cache: LruCache::new(DEFAULT_CACHE_SIZE),
+            verify_checksums: true,
+            strict_eof: false,
+            current_uncompressed_pos: Some(0),
             eof: false,
         }
     }
@@ -90,13 +174,123 @@ impl<R: Read + Seek> BgzfReader<R>
         Ok(Self {
             inner,
             gzi_index: Some(gzi_index),
-            decompressed_buf: Vec::new(),
+            block: Arc::default(),
             buf_pos: 0,
-            current_uncompressed_pos: 0,
+            next_offset: 0,
+            inner_pos: Some(0),
+            cache: LruCache::new(DEFAULT_CACHE_SIZE),
+            verify_checksums: true,
+            strict_eof: false,
+            current_uncompressed_pos: Some(0),
             eof: false,
         })
     }
 
+    /// Set the maximum number of bytes of decompressed blocks kept in the cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Cache capacity in bytes; 0 disables caching
+    pub fn with_cache_size(mut self, bytes: u64) -> Self
+    {
+        self.cache.set_capacity(bytes);
+        self
+    }
+
+    /// Enable or disable the CRC32 check of each block (enabled by default).
+    ///
+    /// Block sizes (ISIZE) are always checked, as that costs nothing.
+    pub fn with_checksums(mut self, verify: bool) -> Self
+    {
+        self.verify_checksums = verify;
+        self
+    }
+
+    /// Fail reads that reach the end of a file without the BGZF EOF marker.
+    ///
+    /// Off by default, so a file missing the marker reads like a complete one and
+    /// only [`verify`](BgzfReader::verify) reports it. Enable this to treat
+    /// truncated downloads as errors while reading.
+    pub fn with_strict_eof(mut self, strict: bool) -> Self
+    {
+        self.strict_eof = strict;
+        self
+    }
+
+    /// Get the block cache counters.
+    pub fn cache_stats(&self) -> CacheStats
+    {
+        self.cache.stats()
+    }
+
+    /// Check the integrity of the whole file.
+    ///
+    /// Every block from the start of the stream is read, inflated and checked
+    /// against its CRC32 and ISIZE, even if checksums are disabled for reading.
+    /// The scan stops at the first corrupt block. The reader is left at the start
+    /// of the stream.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(report)` - The result of the scan; see [`VerifyReport::is_ok`]
+    /// * `Err(io::Error)` - If reading the underlying file fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::bgzf::BgzfReader;
+    /// use std::fs::File;
+    ///
+    /// let mut reader = BgzfReader::new(File::open("data.fasta.gz").unwrap());
+    /// let report = reader.verify().unwrap();
+    /// if let Some(corrupt) = &report.corrupt_block
+    /// {
+    ///     eprintln!("Corrupt block at offset {}: {}", corrupt.offset, corrupt.error);
+    /// }
+    /// else if !report.eof_marker
+    /// {
+    ///     eprintln!("File is truncated");
+    /// }
+    /// ```
+    pub fn verify(&mut self) -> io::Result<VerifyReport>
+    {
+        let mut report = VerifyReport {
+            blocks: 0,
+            uncompressed_size: 0,
+            eof_marker: false,
+            corrupt_block: None,
+        };
+        let mut offset = 0;
+        loop
+        {
+            match self.read_block(offset, true)
+            {
+                Ok(Some(block)) =>
+                {
+                    report.blocks += 1;
+                    report.uncompressed_size += block.data.len() as u64;
+                    report.eof_marker = block.data.is_empty();
+                    offset += block.compressed_len;
+                }
+                Ok(None) => break,
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+                    ) =>
+                {
+                    report.eof_marker = false;
+                    report.corrupt_block = Some(CorruptBlock { offset, error });
+                    break;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        self.reset(0, Some(0));
+        Ok(report)
+    }
+
     /// Seek to an uncompressed position using the .gzi index.
     ///
     /// This method uses the .gzi index to find the compressed offset
@@ -131,16 +325,23 @@ impl<R: Read + Seek> BgzfReader<R>
             )
         })?;
 
-        // Seek to the compressed offset
-        self.inner.seek(SeekFrom::Start(compressed_offset))?;
+        // Fast path: the target is in the block already in memory
+        if let Some(block_end) = self.current_uncompressed_pos
+        {
+            let block_start = block_end - self.block.data.len() as u64;
+            if (block_start..block_end).contains(&uncompressed_pos)
+            {
+                self.buf_pos = (uncompressed_pos - block_start) as usize;
+                return Ok(uncompressed_pos);
+            }
+        }
 
-        // Reset decompression state
-        self.decompressed_buf.clear();
-        self.buf_pos = 0;
-        self.current_uncompressed_pos = block_start_uncompressed;  // Important: start from GZI entry's position
+        // Start from the GZI entry's block
+        self.reset(compressed_offset, Some(block_start_uncompressed));
 
         // Read and decompress blocks until we reach the target position
-        while self.current_uncompressed_pos < uncompressed_pos
+        let mut block_end = block_start_uncompressed;
+        while block_end < uncompressed_pos
         {
             if !self.read_next_block()?
             {
@@ -149,42 +350,251 @@ impl<R: Read + Seek> BgzfReader<R>
                     "Reached end of file before target position",
                 ));
             }
+            block_end += self.block.data.len() as u64;
         }
 
         // Now we're at or past the target position
         // Set buf_pos to the correct offset within the current block
-        let offset_in_block = (uncompressed_pos
-            - (self.current_uncompressed_pos - self.decompressed_buf.len() as u64))
-            as usize;
-        self.buf_pos = offset_in_block;
+        self.buf_pos = (uncompressed_pos - (block_end - self.block.data.len() as u64)) as usize;
 
         Ok(uncompressed_pos)
     }
 
     /// Get the current uncompressed position.
+    ///
+    /// After [`seek_virtual`](BgzfReader::seek_virtual) to a block without a .gzi
+    /// entry the position is unknown until the next
+    /// [`seek_uncompressed`](BgzfReader::seek_uncompressed), and this is the offset
+    /// within the current block.
     pub fn current_position(&self) -> u64
     {
-        if self.decompressed_buf.is_empty()
+        match self.current_uncompressed_pos
+        {
+            Some(block_end) => block_end - self.block.data.len() as u64 + self.buf_pos as u64,
+            None => self.buf_pos as u64,
+        }
+    }
+
+    /// Get the current virtual offset.
+    ///
+    /// A virtual offset is the compressed offset of a block shifted left by 16 bits,
+    /// or-ed with the offset within the uncompressed block, as used by BAI, CSI and
+    /// TBI indexes. At the end of a block it points to the start of the next one.
+    pub fn virtual_position(&self) -> u64
+    {
+        if self.buf_pos < self.block.data.len()
         {
-            self.current_uncompressed_pos
+            let block_offset = self.next_offset - self.block.compressed_len;
+            (block_offset << VIRTUAL_OFFSET_BITS) | self.buf_pos as u64
         }
         else
         {
-            self.current_uncompressed_pos - self.decompressed_buf.len() as u64 + self.buf_pos as u64
+            self.next_offset << VIRTUAL_OFFSET_BITS
         }
     }
 
+    /// Seek to a virtual offset.
+    ///
+    /// No index is needed, as a virtual offset names the block directly. If the
+    /// .gzi index has an entry for the block, the uncompressed position is kept up
+    /// to date; otherwise it is unknown until the next
+    /// [`seek_uncompressed`](BgzfReader::seek_uncompressed), and
+    /// [`stream_position`](Seek::stream_position) fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `voffset` - Compressed block offset << 16 | offset within the block
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The next read starts at `voffset`
+    /// * `Err(io::Error)` - If the block cannot be read or is shorter than the in-block offset
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::bgzf::BgzfReader;
+    /// use std::fs::File;
+    /// use std::io::BufRead;
+    ///
+    /// let mut reader = BgzfReader::new(File::open("data.fasta.gz").unwrap());
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line).unwrap();
+    /// let second_line = reader.virtual_position();
+    ///
+    /// // ... later
+    /// reader.seek_virtual(second_line).unwrap();
+    /// ```
+    pub fn seek_virtual(&mut self, voffset: u64) -> io::Result<()>
+    {
+        let compressed_offset = voffset >> VIRTUAL_OFFSET_BITS;
+        let offset_in_block = (voffset & ((1 << VIRTUAL_OFFSET_BITS) - 1)) as usize;
+
+        // Fast path: the target is in the block already in memory
+        let in_current_block = self.block.compressed_len > 0
+            && self.next_offset - self.block.compressed_len == compressed_offset;
+        if !in_current_block
+        {
+            let uncompressed_pos =
+                block_start_uncompressed(self.gzi_index.as_ref(), compressed_offset);
+            self.reset(compressed_offset, uncompressed_pos);
+            if offset_in_block == 0
+            {
+                // Read the block lazily, it may be the end of the file
+                return Ok(());
+            }
+            self.read_next_block()?;
+        }
+
+        if offset_in_block > self.block.data.len()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Virtual offset {} is beyond the end of its {} byte block",
+                    voffset,
+                    self.block.data.len()
+                ),
+            ));
+        }
+        self.buf_pos = offset_in_block;
+        Ok(())
+    }
+
     /// Get a reference to the GZI index, if available.
     pub fn gzi_index(&self) -> Option<&GziIndex>
     {
         self.gzi_index.as_ref()
     }
 
-    /// Read the next BGZF block.
+    /// Get a reference to the underlying compressed reader.
+    pub fn get_ref(&self) -> &R
+    {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying compressed reader.
+    ///
+    /// Reading from or seeking it directly is safe: the next block is always
+    /// read from its own compressed offset.
+    pub fn get_mut(&mut self) -> &mut R
+    {
+        self.inner_pos = None;
+        &mut self.inner
+    }
+
+    /// Clear the decompression state so the next block is read from `compressed_offset`,
+    /// which starts at `uncompressed_pos` if known.
+    fn reset(&mut self, compressed_offset: u64, uncompressed_pos: Option<u64>)
+    {
+        self.next_offset = compressed_offset;
+        self.block = Arc::default();
+        self.buf_pos = 0;
+        self.eof = false;
+        self.current_uncompressed_pos = uncompressed_pos;
+    }
+
+    /// Total uncompressed size, found by reading the blocks after the last .gzi entry.
+    fn uncompressed_size(&mut self) -> io::Result<u64>
+    {
+        let gzi = self.gzi_index.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "No .gzi index available for seeking")
+        })?;
+        let (compressed_offset, uncompressed_pos) = gzi.entries().last().copied().unwrap_or((0, 0));
+
+        self.reset(compressed_offset, Some(uncompressed_pos));
+        let mut size = uncompressed_pos;
+        while self.read_next_block()?
+        {
+            size += self.block.data.len() as u64;
+        }
+        Ok(size)
+    }
+
+    /// Move on to the next BGZF block, taking it from the cache if possible.
     ///
     /// Returns true if a block was read, false on EOF.
     fn read_next_block(&mut self) -> io::Result<bool>
     {
+        let offset = self.next_offset;
+        let block = match self.cache.get(&offset)
+        {
+            Some(block) => Arc::clone(block),
+            None =>
+            {
+                let block = self.read_block(offset, self.verify_checksums).map_err(|e| {
+                    io::Error::new(e.kind(), format!("{} (BGZF block at offset {})", e, offset))
+                })?;
+                let block = match block
+                {
+                    Some(block) => Arc::new(block),
+                    None =>
+                    {
+                        // A complete file ends with the empty EOF marker block
+                        if self.strict_eof && offset > 0 && !self.follows_eof_marker(offset)?
+                        {
+                            return Err(io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                format!("BGZF EOF marker missing at offset {}", offset),
+                            ));
+                        }
+                        self.eof = true;
+                        return Ok(false);
+                    }
+                };
+                if !block.data.is_empty()
+                {
+                    self.cache.insert(offset, Arc::clone(&block), block.data.len() as u64);
+                }
+                block
+            }
+        };
+
+        self.next_offset = offset + block.compressed_len;
+        self.buf_pos = 0;
+        self.current_uncompressed_pos =
+            self.current_uncompressed_pos.map(|pos| pos + block.data.len() as u64);
+        self.block = block;
+        Ok(true)
+    }
+
+    /// Check whether the block ending at compressed offset `end` is the EOF marker.
+    fn follows_eof_marker(&mut self, end: u64) -> io::Result<bool>
+    {
+        if self.block.compressed_len > 0
+        {
+            return Ok(self.block.data.is_empty());
+        }
+        // After a seek the previous block is not in memory
+        if end < EOF_MARKER_LEN
+        {
+            return Ok(false);
+        }
+        match self.read_block(end - EOF_MARKER_LEN, true)
+        {
+            Ok(Some(block)) => Ok(block.data.is_empty() && block.compressed_len == EOF_MARKER_LEN),
+            Ok(None) => Ok(false),
+            Err(e)
+                if matches!(e.kind(), io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read, decompress and check the block at a compressed offset.
+    ///
+    /// Returns None at the end of the file.
+    fn read_block(&mut self, offset: u64, verify_crc: bool) -> io::Result<Option<Block>>
+    {
+        if self.inner_pos != Some(offset)
+        {
+            self.inner.seek(SeekFrom::Start(offset))?;
+        }
+        // Unknown until the whole block has been read
+        self.inner_pos = None;
+
         // Read and verify BGZF header (first 12 bytes: ID1, ID2, CM, FLG, MTIME, XFL, OS, XLEN)
         let mut header = [0u8; 12];
         let mut total_read = 0;
@@ -200,115 +610,21 @@ impl<R: Read + Seek> BgzfReader<R>
 
         if total_read == 0
         {
-            self.eof = true;
-            return Ok(false);
+            self.inner_pos = Some(offset);
+            return Ok(None);
         }
         if total_read < 12
         {
             return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Incomplete BGZF header"));
         }
 
-        // Verify gzip magic
-        if header[0] != GZIP_ID1 || header[1] != GZIP_ID2
-        {
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid gzip magic number"));
-        }
-
-        // Verify deflate compression method
-        if header[2] != GZIP_CM_DEFLATE
-        {
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not deflate compression"));
-        }
-
-        let flg = header[3];
-
-        // Check for extra field (BGZF stores block size here)
-        let xlen = if flg & GZIP_FLG_FEXTRA != 0
-        {
-            u16::from_le_bytes([header[10], header[11]]) as usize
-        }
-        else
-        {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "BGZF requires extra field (FEXTRA flag not set)",
-            ));
-        };
+        let xlen = extra_field_len(&header)?;
 
         // Read extra field
         let mut extra = vec![0u8; xlen];
         self.inner.read_exact(&mut extra)?;
-
-        // Parse BGZF subfield to get block size
-        let mut remaining_xlen = xlen;
-        let mut block_size = None;
-
-        while remaining_xlen >= 4
-        {
-            let si1 = extra[xlen - remaining_xlen];
-            let si2 = extra[xlen - remaining_xlen + 1];
-            let sublen = u16::from_le_bytes([
-                extra[xlen - remaining_xlen + 2],
-                extra[xlen - remaining_xlen + 3],
-            ]) as usize;
-
-            if si1 == BGZF_EXTRA_ID && si2 == BGZF_EXTRA_SUBFIELD && sublen >= 2
-            {
-                // Block size is stored as a 16-bit little-endian value
-                // It includes the 1-byte SI1, 1-byte SI2, 2-byte sublen, and block_size itself
-                // So the actual compressed data size is block_size - 1 - 1 - 2 - 2 = block_size - 6
-                let bsize = u16::from_le_bytes([
-                    extra[xlen - remaining_xlen + 4],
-                    extra[xlen - remaining_xlen + 5],
-                ]);
-                block_size = Some(bsize as usize);
-                break;
-            }
-
-            // Prevent underflow: ensure we have enough bytes for SI1, SI2, SUBLEN, and the data
-            if sublen > remaining_xlen.saturating_sub(4)
-            {
-                break;
-            }
-            remaining_xlen -= 4 + sublen;
-        }
-
-        let block_size = block_size.ok_or_else(|| {
-            io::Error::new(io::ErrorKind::InvalidData, "BC subfield not found in BGZF extra field")
-        })?;
-
-        // MTIME (4 bytes), XFL (1 byte), OS (1 byte) = already read in header
-        // Already read: 18 bytes (header) + xlen bytes (extra)
-        // Remaining before compressed data: 6 bytes (MTIME + XFL + OS are in header, then we need to account for what we've read)
-        // Actually, the header is:
-        // ID1(1) ID2(1) CM(1) FLG(1) MTIME(4) XFL(1) OS(1) = 10 bytes
-        // Extra length(2) + extra(xlen)
-        // We've read 12 bytes (10 + 2) + xlen
-
-        // Skip remaining header fields we haven't accounted for
-        // Wait, we read 18 bytes: that's ID1, ID2, CM, FLG, MTIME(4), XFL, OS, XLEN(2)
-        // Then we read xlen bytes of extra
-        // So now we're at the start of compressed data
-
-        // Calculate remaining compressed data size
-        // According to BGZF spec, BSIZE is "the size of the BGZF block minus one"
-        // So actual block size = BSIZE + 1
-        // Structure:
-        //   - Header: ID1(1) + ID2(1) + CM(1) + FLG(1) + MTIME(4) + XFL(1) + OS(1) + XLEN(2) = 12 bytes
-        //   - Extra field (XLEN bytes)
-        //   - Compressed data (variable)
-        //   - Trailer: CRC32(4) + ISIZE(4) = 8 bytes
-        // Actual block size = BSIZE + 1 = header(12) + extra_field(XLEN) + compressed_data + trailer(8)
-        // Compressed data size = (BSIZE + 1) - header(12) - extra_field(XLEN) - trailer(8)
-        let compressed_size = (block_size as isize + 1) - 12 - xlen as isize - 8;
-        if compressed_size <= 0
-        {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Invalid BGZF block size: {}, xlen: {}", block_size, xlen),
-            ));
-        }
-        let compressed_size = compressed_size as usize;
+        let block_len = block_len_from_extra(&extra)?;
+        let compressed_size = compressed_data_len(block_len, xlen)?;
 
         // Read compressed data
         let mut compressed_data = vec![0u8; compressed_size];
@@ -318,38 +634,25 @@ impl<R: Read + Seek> BgzfReader<R>
         let mut trailer = [0u8; 8];
         self.inner.read_exact(&mut trailer)?;
 
-        // Decompress the block
-        // Set capacity but keep length at 0 so decompress_vec appends to empty buffer
-        self.decompressed_buf.clear();
-        self.decompressed_buf.reserve(BGZF_MAX_BLOCK_SIZE);
-
-        let mut decompress = Decompress::new(false);
-        decompress.decompress_vec(
-            &compressed_data,
-            &mut self.decompressed_buf,
-            flate2::FlushDecompress::Finish,
-        )?;
-
-        self.buf_pos = 0;
-        self.current_uncompressed_pos += self.decompressed_buf.len() as u64;
-        Ok(true)
+        let mut data = Vec::new();
+        inflate_block(&compressed_data, &mut data)?;
+        check_trailer(&trailer, &data, verify_crc)?;
+        self.inner_pos = Some(offset + block_len as u64);
+        Ok(Some(Block { data, compressed_len: block_len as u64 }))
     }
 
     /// Ensure there's data available in the buffer.
     fn fill_buf(&mut self) -> io::Result<&[u8]>
     {
-        if self.buf_pos >= self.decompressed_buf.len()
+        // Empty blocks, such as EOF markers of concatenated files, are skipped
+        while self.buf_pos >= self.block.data.len()
         {
-            if self.eof
-            {
-                return Ok(&[]);
-            }
-            if !self.read_next_block()?
+            if self.eof || !self.read_next_block()?
             {
                 return Ok(&[]);
             }
         }
-        Ok(&self.decompressed_buf[self.buf_pos..])
+        Ok(&self.block.data[self.buf_pos..])
     }
 }
 
@@ -374,6 +677,39 @@ impl<R: Read + Seek> Read for BgzfReader<R>
     }
 }
 
+impl<R: Read + Seek> Seek for BgzfReader<R>
+{
+    /// Seek to an uncompressed position; requires a .gzi index.
+    ///
+    /// Seeking from the end reads the blocks after the last index entry to find
+    /// the uncompressed size.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64>
+    {
+        let (base, offset) = match pos
+        {
+            SeekFrom::Start(n) => return self.seek_uncompressed(n),
+            SeekFrom::Current(offset) => (self.stream_position()?, offset),
+            SeekFrom::End(offset) => (self.uncompressed_size()?, offset),
+        };
+        let target = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Seek before start of stream")
+        })?;
+        self.seek_uncompressed(target)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64>
+    {
+        match self.current_uncompressed_pos
+        {
+            Some(_) => Ok(self.current_position()),
+            None => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Uncompressed position unknown after seeking to a block without a .gzi entry",
+            )),
+        }
+    }
+}
+
 impl<R: Read + Seek> BufRead for BgzfReader<R>
 {
     fn fill_buf(&mut self) -> io::Result<&[u8]>
@@ -384,10 +720,288 @@ impl<R: Read + Seek> BufRead for BgzfReader<R>
     fn consume(&mut self, amt: usize)
     {
         self.buf_pos += amt;
-        if self.buf_pos > self.decompressed_buf.len()
+        if self.buf_pos > self.block.data.len()
+        {
+            self.buf_pos = self.block.data.len();
+        }
+    }
+}
+
+/// Length of the fixed gzip header up to and including XLEN.
+pub(crate) const BLOCK_HEADER_LEN: usize = 12;
+
+/// Verify the fixed part of a BGZF block header and return the length of its
+/// extra field (XLEN).
+///
+/// The header is ID1(1) ID2(1) CM(1) FLG(1) MTIME(4) XFL(1) OS(1) XLEN(2).
+pub(crate) fn extra_field_len(header: &[u8; BLOCK_HEADER_LEN]) -> io::Result<usize>
+{
+    // Verify gzip magic
+    if header[0] != GZIP_ID1 || header[1] != GZIP_ID2
+    {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid gzip magic number"));
+    }
+
+    // Verify deflate compression method
+    if header[2] != GZIP_CM_DEFLATE
+    {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not deflate compression"));
+    }
+
+    // BGZF stores the block size in the extra field
+    if header[3] & GZIP_FLG_FEXTRA == 0
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "BGZF requires extra field (FEXTRA flag not set)",
+        ));
+    }
+    Ok(u16::from_le_bytes([header[10], header[11]]) as usize)
+}
+
+/// Find the total length of a block (BSIZE + 1) in its extra field.
+pub(crate) fn block_len_from_extra(extra: &[u8]) -> io::Result<usize>
+{
+    let xlen = extra.len();
+    let mut remaining_xlen = xlen;
+
+    while remaining_xlen >= 4
+    {
+        let si1 = extra[xlen - remaining_xlen];
+        let si2 = extra[xlen - remaining_xlen + 1];
+        let sublen = u16::from_le_bytes([
+            extra[xlen - remaining_xlen + 2],
+            extra[xlen - remaining_xlen + 3],
+        ]) as usize;
+
+        if si1 == BGZF_EXTRA_ID && si2 == BGZF_EXTRA_SUBFIELD && sublen >= 2
+        {
+            // BSIZE is "the size of the BGZF block minus one", 16-bit little-endian
+            let bsize = u16::from_le_bytes([
+                extra[xlen - remaining_xlen + 4],
+                extra[xlen - remaining_xlen + 5],
+            ]);
+            return Ok(bsize as usize + 1);
+        }
+
+        // Prevent underflow: ensure we have enough bytes for SI1, SI2, SUBLEN, and the data
+        if sublen > remaining_xlen.saturating_sub(4)
+        {
+            break;
+        }
+        remaining_xlen -= 4 + sublen;
+    }
+
+    Err(io::Error::new(io::ErrorKind::InvalidData, "BC subfield not found in BGZF extra field"))
+}
+
+/// Size of the deflate data in a block of `block_len` bytes with an extra field
+/// of `xlen` bytes.
+///
+/// A block is the header (12 bytes), the extra field, the compressed data and
+/// the trailer CRC32(4) + ISIZE(4).
+fn compressed_data_len(block_len: usize, xlen: usize) -> io::Result<usize>
+{
+    block_len.checked_sub(BLOCK_HEADER_LEN + xlen + 8).filter(|&n| n > 0).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid BGZF block size: {}, xlen: {}", block_len - 1, xlen),
+        )
+    })
+}
+
+/// Inflate the deflate data of a block into `out`, replacing its contents.
+fn inflate_block(compressed_data: &[u8], out: &mut Vec<u8>) -> io::Result<()>
+{
+    // Set capacity but keep length at 0 so decompress_vec appends to empty buffer
+    out.clear();
+    out.reserve(BGZF_MAX_BLOCK_SIZE);
+
+    let mut decompress = Decompress::new(false);
+    let status = decompress
+        .decompress_vec(compressed_data, out, FlushDecompress::Finish)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if status != Status::StreamEnd
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Incomplete deflate data in BGZF block",
+        ));
+    }
+    Ok(())
+}
+
+/// The uncompressed offset of the block starting at `compressed_offset`, if it is
+/// the first block or the index has an entry for it.
+fn block_start_uncompressed(gzi: Option<&GziIndex>, compressed_offset: u64) -> Option<u64>
+{
+    if compressed_offset == 0
+    {
+        return Some(0);
+    }
+    let entries = gzi?.entries();
+    entries.binary_search_by_key(&compressed_offset, |&(c, _)| c).ok().map(|i| entries[i].1)
+}
+
+/// Check a block's trailer, CRC32(4) + ISIZE(4), against its inflated data.
+///
+/// The CRC32 is only computed if `verify_crc` is set.
+fn check_trailer(trailer: &[u8; 8], data: &[u8], verify_crc: bool) -> io::Result<()>
+{
+    let isize = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if isize as usize != data.len()
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("BGZF block size mismatch: ISIZE is {}, inflated {} bytes", isize, data.len()),
+        ));
+    }
+    if verify_crc
+    {
+        let expected = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let mut crc = Crc::new();
+        crc.update(data);
+        let actual = crc.sum();
+        if actual != expected
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("BGZF block CRC32 mismatch: expected {:08x}, got {:08x}", expected, actual),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Decompress a complete BGZF block held in memory.
+pub(crate) fn decompress_block(block: &[u8]) -> io::Result<Vec<u8>>
+{
+    let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated BGZF block");
+    let header: &[u8; BLOCK_HEADER_LEN] =
+        block.get(..BLOCK_HEADER_LEN).and_then(|h| h.try_into().ok()).ok_or_else(truncated)?;
+    let xlen = extra_field_len(header)?;
+    let extra = block.get(BLOCK_HEADER_LEN..BLOCK_HEADER_LEN + xlen).ok_or_else(truncated)?;
+    let block_len = block_len_from_extra(extra)?;
+    let compressed_size = compressed_data_len(block_len, xlen)?;
+    let data_start = BLOCK_HEADER_LEN + xlen;
+    let data_end = data_start + compressed_size;
+    let compressed_data = block.get(data_start..data_end).ok_or_else(truncated)?;
+    let trailer: &[u8; 8] =
+        block.get(data_end..data_end + 8).and_then(|t| t.try_into().ok()).ok_or_else(truncated)?;
+
+    let mut out = Vec::new();
+    inflate_block(compressed_data, &mut out)?;
+    check_trailer(trailer, &out, true)?;
+    Ok(out)
+}
+
+/// Decompress the block at the start of `bytes`, which may hold further blocks.
+#[cfg(feature = "async")]
+pub(crate) fn block_from_bytes(bytes: &[u8]) -> io::Result<Block>
+{
+    let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated BGZF block");
+    let header: &[u8; BLOCK_HEADER_LEN] =
+        bytes.get(..BLOCK_HEADER_LEN).and_then(|h| h.try_into().ok()).ok_or_else(truncated)?;
+    let xlen = extra_field_len(header)?;
+    let extra = bytes.get(BLOCK_HEADER_LEN..BLOCK_HEADER_LEN + xlen).ok_or_else(truncated)?;
+    let block_len = block_len_from_extra(extra)?;
+    let raw = bytes.get(..block_len).ok_or_else(truncated)?;
+    Ok(Block { data: decompress_block(raw)?, compressed_len: block_len as u64 })
+}
+
+/// Compress `data` into BGZF blocks of `block_len` uncompressed bytes, followed by
+/// the EOF marker. Returns the compressed bytes and the matching .gzi file contents.
+#[cfg(test)]
+pub(crate) fn compress_for_tests(data: &[u8], block_len: usize) -> (Vec<u8>, Vec<u8>)
+{
+    use flate2::write::DeflateEncoder;
+    use flate2::{Compression, Crc};
+    use std::io::Write;
+
+    let mut out = Vec::new();
+    let mut gzi = Vec::new();
+    let mut entries = 0u64;
+    let mut uncompressed = 0u64;
+    let chunks = data.chunks(block_len.max(1)).chain(std::iter::once(&[][..]));
+    for chunk in chunks
+    {
+        if uncompressed > 0 && !chunk.is_empty()
         {
-            self.buf_pos = self.decompressed_buf.len();
+            gzi.extend_from_slice(&(out.len() as u64).to_le_bytes());
+            gzi.extend_from_slice(&uncompressed.to_le_bytes());
+            entries += 1;
         }
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(chunk).unwrap();
+        let deflated = encoder.finish().unwrap();
+        let mut crc = Crc::new();
+        crc.update(chunk);
+
+        let block_size = 18 + deflated.len() + 8;
+        out.extend_from_slice(&[GZIP_ID1, GZIP_ID2, GZIP_CM_DEFLATE, GZIP_FLG_FEXTRA]);
+        out.extend_from_slice(&[0, 0, 0, 0, 0, GZIP_OS_UNKNOWN, 6, 0]);
+        out.extend_from_slice(&[BGZF_EXTRA_ID, BGZF_EXTRA_SUBFIELD, 2, 0]);
+        out.extend_from_slice(&((block_size - 1) as u16).to_le_bytes());
+        out.extend_from_slice(&deflated);
+        out.extend_from_slice(&crc.sum().to_le_bytes());
+        out.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        uncompressed += chunk.len() as u64;
+    }
+    let mut gzi_file = entries.to_le_bytes().to_vec();
+    gzi_file.extend_from_slice(&gzi);
+    (out, gzi_file)
+}
+
+/// A FASTA file with 60 bases per line, compressed into BGZF blocks, with its
+/// indexes.
+#[cfg(test)]
+pub(crate) struct TestReference
+{
+    /// The uncompressed FASTA text
+    pub(crate) text: Vec<u8>,
+    /// The .fai index of `text`
+    pub(crate) fai: String,
+    /// `text` in BGZF blocks
+    pub(crate) data: Vec<u8>,
+    /// The .gzi index of `data`
+    pub(crate) gzi: Vec<u8>,
+}
+
+#[cfg(test)]
+impl TestReference
+{
+    /// Build the FASTA file of named sequences, in BGZF blocks of `block_len` bytes.
+    pub(crate) fn new<N, S>(seqs: &[(N, S)], block_len: usize) -> Self
+    where
+        N: AsRef<str>,
+        S: AsRef<[u8]>,
+    {
+        let mut text = Vec::new();
+        let mut fai = String::new();
+        for (name, seq) in seqs
+        {
+            let (name, seq) = (name.as_ref(), seq.as_ref());
+            text.extend_from_slice(format!(">{}\n", name).as_bytes());
+            fai.push_str(&format!("{}\t{}\t{}\t60\t61\n", name, seq.len(), text.len()));
+            for line in seq.chunks(60)
+            {
+                text.extend_from_slice(line);
+                text.push(b'\n');
+            }
+        }
+        let (data, gzi) = compress_for_tests(&text, block_len);
+        Self { text, fai, data, gzi }
+    }
+
+    /// Write `ref.fa.gz` and its .fai and .gzi to a new temporary directory.
+    pub(crate) fn write_to_temp_dir(&self, name: &str) -> std::path::PathBuf
+    {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("ref.fa.gz"), &self.data).unwrap();
+        std::fs::write(dir.join("ref.fa.gz.fai"), &self.fai).unwrap();
+        std::fs::write(dir.join("ref.fa.gz.gzi"), &self.gzi).unwrap();
+        dir
     }
 }
 
@@ -421,4 +1035,262 @@ mod tests
         assert!(reader.gzi_index.is_none());
         assert_eq!(reader.current_position(), 0);
     }
+
+    #[test]
+    fn test_read_and_seek_blocks()
+    {
+        let data: Vec<u8> = (0..10_000u32).map(|i| b"ACGT\n"[(i % 5) as usize]).collect();
+        let (compressed, gzi) = compress_for_tests(&data, 1000);
+        let gzi = GziIndex::from_bytes(&gzi).unwrap();
+        let mut reader = BgzfReader::with_index(Cursor::new(compressed), gzi).unwrap();
+
+        reader.seek_uncompressed(4321).unwrap();
+        let mut buf = vec![0u8; 1500];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[4321..5821]);
+
+        reader.seek_uncompressed(0).unwrap();
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        assert_eq!(all, data);
+    }
+
+    #[test]
+    fn test_virtual_offsets_and_seek()
+    {
+        let data: Vec<u8> = (0..10_000u32).map(|i| b"ACGTN"[(i * 3 % 5) as usize]).collect();
+        let (compressed, gzi) = compress_for_tests(&data, 1000);
+        let gzi = GziIndex::from_bytes(&gzi).unwrap();
+        let mut buf = vec![0u8; 100];
+
+        // Virtual offsets work without an index
+        let mut reader = BgzfReader::new(Cursor::new(compressed.clone()));
+        let mut skipped = vec![0u8; 1234];
+        reader.read_exact(&mut skipped).unwrap();
+        let voffset = reader.virtual_position();
+        assert_eq!(voffset >> 16, gzi.entries()[0].0);
+        assert_eq!(voffset & 0xffff, 234);
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[1234..1334]);
+
+        reader.seek_virtual(0).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[..100]);
+        reader.seek_virtual(voffset).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[1234..1334]);
+        assert!(reader.seek_virtual(gzi.entries()[0].0 << 16 | 1001).is_err());
+
+        // At a block boundary the virtual offset names the next block
+        reader.seek_virtual(voffset).unwrap();
+        let mut rest_of_block = vec![0u8; 766];
+        reader.read_exact(&mut rest_of_block).unwrap();
+        assert_eq!(reader.virtual_position(), gzi.entries()[1].0 << 16);
+
+        // With an index, Seek works on uncompressed positions
+        let mut reader =
+            BgzfReader::with_index(Cursor::new(compressed.clone()), gzi.clone()).unwrap();
+        assert_eq!(reader.seek(SeekFrom::End(-100)).unwrap(), 9900);
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[9900..]);
+        assert_eq!(reader.seek(SeekFrom::Current(-5000)).unwrap(), 5000);
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[5000..5100]);
+        assert_eq!(reader.stream_position().unwrap(), 5100);
+        assert!(reader.seek(SeekFrom::Current(-6000)).is_err());
+
+        // and virtual offsets keep the uncompressed position
+        reader.seek_virtual(voffset).unwrap();
+        assert_eq!(reader.stream_position().unwrap(), 1234);
+
+        // A block without an index entry leaves the position unknown, and seeking by
+        // uncompressed position starts over instead of trusting the current block
+        let entries = gzi.entries();
+        let mut partial = 1u64.to_le_bytes().to_vec();
+        partial.extend_from_slice(&entries[3].0.to_le_bytes());
+        partial.extend_from_slice(&entries[3].1.to_le_bytes());
+        let partial = GziIndex::from_bytes(&partial).unwrap();
+        let mut reader = BgzfReader::with_index(Cursor::new(compressed), partial).unwrap();
+        reader.seek_virtual(entries[1].0 << 16 | 10).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[2010..2110]);
+        assert!(reader.stream_position().is_err());
+        assert!(reader.seek(SeekFrom::Current(-5)).is_err());
+        reader.seek_uncompressed(10).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[10..110]);
+        assert_eq!(reader.stream_position().unwrap(), 110);
+        reader.seek_virtual(entries[3].0 << 16).unwrap();
+        assert_eq!(reader.stream_position().unwrap(), entries[3].1);
+    }
+
+    #[test]
+    fn test_seek_virtual_to_end()
+    {
+        let data: Vec<u8> = (0..5000u32).map(|i| b"ACGTN"[(i * 3 % 5) as usize]).collect();
+        let (compressed, _) = compress_for_tests(&data, 1000);
+        let open = |bytes: &[u8], strict: bool| {
+            BgzfReader::new(Cursor::new(bytes.to_vec())).with_strict_eof(strict)
+        };
+        for strict in [false, true]
+        {
+            let mut reader = open(&compressed, strict);
+            let mut all = Vec::new();
+            reader.read_to_end(&mut all).unwrap();
+            assert_eq!(all, data);
+
+            let end = reader.virtual_position();
+            assert_eq!(end, (compressed.len() as u64) << 16);
+            reader.seek_virtual(0).unwrap();
+            reader.seek_virtual(end).unwrap();
+            assert_eq!(reader.read(&mut [0u8; 10]).unwrap(), 0);
+
+            // A fresh reader has not seen the EOF marker block before the end
+            let mut reader = open(&compressed, strict);
+            reader.seek_virtual(end).unwrap();
+            assert_eq!(reader.read(&mut [0u8; 10]).unwrap(), 0);
+        }
+
+        // Strict reads still notice a missing marker after seeking
+        let truncated = &compressed[..compressed.len() - 28];
+        let mut reader = open(truncated, true);
+        reader.seek_virtual((truncated.len() as u64) << 16).unwrap();
+        assert_eq!(reader.read(&mut [0u8; 10]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    /// Counts the bytes read from the compressed stream.
+    struct CountingReader
+    {
+        inner: Cursor<Vec<u8>>,
+        bytes_read: usize,
+    }
+
+    impl Read for CountingReader
+    {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+        {
+            let n = self.inner.read(buf)?;
+            self.bytes_read += n;
+            Ok(n)
+        }
+    }
+
+    impl Seek for CountingReader
+    {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64>
+        {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_block_cache()
+    {
+        let data: Vec<u8> = (0..10_000u32).map(|i| b"ACGTN"[(i * 7 % 5) as usize]).collect();
+        let (compressed, gzi) = compress_for_tests(&data, 1000);
+        let gzi = GziIndex::from_bytes(&gzi).unwrap();
+        let inner = CountingReader { inner: Cursor::new(compressed), bytes_read: 0 };
+        let mut reader = BgzfReader::with_index(inner, gzi).unwrap();
+        let mut buf = vec![0u8; 100];
+
+        reader.seek_uncompressed(2500).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[2500..2600]);
+        let bytes_read = reader.get_ref().bytes_read;
+        let stats = reader.cache_stats();
+
+        // Within the current block: no lookup, no read
+        reader.seek_uncompressed(2050).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[2050..2150]);
+        assert_eq!(reader.cache_stats(), stats);
+
+        assert_eq!(reader.get_ref().bytes_read, bytes_read);
+
+        // Back to a cached block without reading it again
+        reader.seek_uncompressed(5200).unwrap();
+        let bytes_read = reader.get_ref().bytes_read;
+        reader.seek_uncompressed(2900).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[2900..3000]);
+        assert_eq!(reader.cache_stats().hits, stats.hits + 1);
+        assert_eq!(reader.get_ref().bytes_read, bytes_read);
+
+        // The next block was never read and comes from its own offset
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[3000..3100]);
+        assert!(reader.get_ref().bytes_read > bytes_read);
+
+        // Without a cache every revisit reads the block again
+        let compressed = reader.get_ref().inner.get_ref().clone();
+        let inner = CountingReader { inner: Cursor::new(compressed), bytes_read: 0 };
+        let gzi = reader.gzi_index().unwrap().clone();
+        let mut reader = BgzfReader::with_index(inner, gzi).unwrap().with_cache_size(0);
+        reader.seek_uncompressed(2500).unwrap();
+        reader.seek_uncompressed(5200).unwrap();
+        let bytes_read = reader.get_ref().bytes_read;
+        reader.seek_uncompressed(2900).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[2900..3000]);
+        assert!(reader.get_ref().bytes_read > bytes_read);
+        assert_eq!(reader.cache_stats().hits, 0);
+    }
+
+    #[test]
+    fn test_integrity_checks()
+    {
+        let data: Vec<u8> = (0..5000u32).map(|i| b"ACGT\n"[(i % 5) as usize]).collect();
+        let (compressed, gzi) = compress_for_tests(&data, 1000);
+        let gzi = GziIndex::from_bytes(&gzi).unwrap();
+        let read_all = |bytes: Vec<u8>, checksums: bool| {
+            let mut all = Vec::new();
+            BgzfReader::new(Cursor::new(bytes))
+                .with_checksums(checksums)
+                .with_strict_eof(true)
+                .read_to_end(&mut all)
+                .map(|_| all)
+        };
+
+        let report = BgzfReader::new(Cursor::new(compressed.clone())).verify().unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.blocks, 6);
+        assert_eq!(report.uncompressed_size, 5000);
+
+        // Flip a CRC32 byte in the trailer of the third block
+        let (third, _) = gzi.entries()[1];
+        let (fourth, _) = gzi.entries()[2];
+        let mut corrupt = compressed.clone();
+        corrupt[fourth as usize - 8] ^= 0xff;
+        let err = read_all(corrupt.clone(), true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("CRC32"), "{}", err);
+        assert_eq!(read_all(corrupt.clone(), false).unwrap(), data);
+        let mut reader = BgzfReader::new(Cursor::new(corrupt)).with_checksums(false);
+        let report = reader.verify().unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.corrupt_block.unwrap().offset, third);
+        assert_eq!(report.blocks, 2);
+
+        // Without the EOF marker the file looks truncated, which fails strict reads
+        let truncated = compressed[..compressed.len() - 28].to_vec();
+        let err = read_all(truncated.clone(), true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let mut all = Vec::new();
+        BgzfReader::new(Cursor::new(truncated.clone())).read_to_end(&mut all).unwrap();
+        assert_eq!(all, data);
+        let report = BgzfReader::new(Cursor::new(truncated)).verify().unwrap();
+        assert!(report.corrupt_block.is_none());
+        assert!(!report.eof_marker);
+
+        // Cut inside the last data block
+        let cut = compressed[..compressed.len() - 40].to_vec();
+        assert!(read_all(cut.clone(), true).is_err());
+        let report = BgzfReader::new(Cursor::new(cut)).verify().unwrap();
+        assert_eq!(report.corrupt_block.unwrap().offset, gzi.entries()[3].0);
+
+        // Concatenated files read through the inner EOF marker
+        let mut twice = compressed.clone();
+        twice.extend_from_slice(&compressed);
+        assert_eq!(read_all(twice, true).unwrap(), [data.clone(), data].concat());
+    }
 }
@@ -0,0 +1,228 @@
+//! Size-bounded LRU cache used for fetched and decompressed blocks.
+//!
+//! Entries are weighted by their size in bytes; inserting beyond the capacity
+//! evicts the least recently used entries first. Hit, miss and eviction
+//! counters are exposed as [`CacheStats`] so cache sizes can be tuned.
+
+// Some operations are only needed by the remote reader
+#![cfg_attr(not(feature = "url"), allow(dead_code))]
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Counters describing the effectiveness of a block cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats
+{
+    /// Lookups served from the cache
+    pub hits: u64,
+    /// Lookups that had to fetch or decode the block
+    pub misses: u64,
+    /// Entries dropped to stay within the capacity
+    pub evictions: u64,
+    /// Number of cached entries
+    pub entries: usize,
+    /// Total size of cached entries in bytes
+    pub bytes: u64,
+    /// Capacity in bytes
+    pub capacity: u64,
+}
+
+impl CacheStats
+{
+    /// Fraction of lookups served from the cache, 0 if there were none.
+    pub fn hit_rate(&self) -> f64
+    {
+        let lookups = self.hits + self.misses;
+        if lookups == 0
+        {
+            0.0
+        }
+        else
+        {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+struct Entry<V>
+{
+    value: V,
+    size: u64,
+    tick: u64,
+}
+
+/// A least-recently-used cache bounded by the total size of its entries.
+pub(crate) struct LruCache<K, V>
+{
+    entries: HashMap<K, Entry<V>>,
+    /// Recency order: tick of last use -> key
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    bytes: u64,
+    capacity: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl<K: Hash + Eq + Copy, V> LruCache<K, V>
+{
+    /// Create an empty cache holding at most `capacity` bytes.
+    pub(crate) fn new(capacity: u64) -> Self
+    {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            bytes: 0,
+            capacity,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Look up an entry, marking it as most recently used.
+    pub(crate) fn get(&mut self, key: &K) -> Option<&V>
+    {
+        self.tick += 1;
+        match self.entries.get_mut(key)
+        {
+            Some(entry) =>
+            {
+                self.hits += 1;
+                self.order.remove(&entry.tick);
+                entry.tick = self.tick;
+                self.order.insert(self.tick, *key);
+                Some(&entry.value)
+            }
+            None =>
+            {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Check for an entry without touching recency or counters.
+    pub(crate) fn contains(&self, key: &K) -> bool
+    {
+        self.entries.contains_key(key)
+    }
+
+    /// Insert an entry of `size` bytes, evicting old entries as needed.
+    ///
+    /// Entries larger than the whole capacity are not cached.
+    pub(crate) fn insert(&mut self, key: K, value: V, size: u64)
+    {
+        self.remove(&key);
+        if size > self.capacity
+        {
+            return;
+        }
+        while self.bytes + size > self.capacity
+        {
+            let (_, oldest) = match self.order.pop_first()
+            {
+                Some(item) => item,
+                None => break,
+            };
+            if let Some(entry) = self.entries.remove(&oldest)
+            {
+                self.bytes -= entry.size;
+                self.evictions += 1;
+            }
+        }
+        self.tick += 1;
+        self.order.insert(self.tick, key);
+        self.entries.insert(key, Entry { value, size, tick: self.tick });
+        self.bytes += size;
+    }
+
+    /// Remove an entry, returning its value.
+    pub(crate) fn remove(&mut self, key: &K) -> Option<V>
+    {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.tick);
+        self.bytes -= entry.size;
+        Some(entry.value)
+    }
+
+    /// Change the capacity, evicting entries if it shrinks.
+    pub(crate) fn set_capacity(&mut self, capacity: u64)
+    {
+        self.capacity = capacity;
+        while self.bytes > self.capacity
+        {
+            let (_, oldest) = match self.order.pop_first()
+            {
+                Some(item) => item,
+                None => break,
+            };
+            if let Some(entry) = self.entries.remove(&oldest)
+            {
+                self.bytes -= entry.size;
+                self.evictions += 1;
+            }
+        }
+    }
+
+    /// Drop all entries, keeping the counters.
+    pub(crate) fn clear(&mut self)
+    {
+        self.entries.clear();
+        self.order.clear();
+        self.bytes = 0;
+    }
+
+    /// Snapshot of the counters.
+    pub(crate) fn stats(&self) -> CacheStats
+    {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            entries: self.entries.len(),
+            bytes: self.bytes,
+            capacity: self.capacity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction_order()
+    {
+        let mut cache: LruCache<u64, Vec<u8>> = LruCache::new(30);
+        cache.insert(0, vec![0; 10], 10);
+        cache.insert(1, vec![1; 10], 10);
+        cache.insert(2, vec![2; 10], 10);
+
+        // Touch 0 so that 1 becomes the least recently used
+        assert_eq!(cache.get(&0), Some(&vec![0; 10]));
+        cache.insert(3, vec![3; 10], 10);
+        assert!(cache.get(&1).is_none());
+        assert!(cache.get(&0).is_some());
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.bytes, 30);
+        assert!((stats.hit_rate() - 2.0 / 3.0).abs() < 1e-9);
+
+        // Oversized entries are skipped, shrinking keeps the most recent
+        cache.insert(4, vec![4; 40], 40);
+        assert!(cache.get(&4).is_none());
+        cache.set_capacity(10);
+        assert_eq!(cache.stats().entries, 1);
+        assert_eq!(cache.stats().evictions, 3);
+        assert!(cache.get(&0).is_some());
+    }
+}
@@ -0,0 +1,637 @@
+//! Sequence dictionaries (.dict) and refget/MD5 sequence checksums.
+//!
+//! A sequence dictionary lists the name, length and checksum of every sequence in a
+//! reference as tab-separated SAM-style `@SQ` lines, such as
+//! `@SQ SN:chr1 LN:248956422 M5:6aef897c3d6ff0c78aff06ac189178dd UR:file:/ref/hg38.fa`.
+//!
+//! GATK and Picard require one next to every FASTA, and BAM/CRAM headers carry the
+//! same lines. Checksums are computed over the normalized sequence: whitespace is
+//! removed and the bases are uppercased. `M5` is the MD5 of that sequence as used by
+//! SAM, and the GA4GH refget identifier `SQ.<sha512t24u>` is the URL-safe base64
+//! encoding of the first 24 bytes of its SHA-512.
+
+use crate::digest::{base64, hex, Md5, Sha512, BASE64_URL_SAFE};
+use crate::indexed::{IndexedFastXReader, SequenceFetch};
+use crate::FastX::{FastARecord, FastXRead};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, Write};
+use std::path::Path;
+
+/// Bases fetched per request when hashing sequences from a [`SequenceFetch`].
+const FETCH_CHUNK: u64 = 1 << 20;
+
+/// Checksums of a single normalized sequence.
+///
+/// # Example
+///
+/// ```
+/// use fastx::dict::SequenceChecksums;
+///
+/// let checksums = SequenceChecksums::of(b"acgt\nACGT");
+/// assert_eq!(checksums.length, 8);
+/// assert_eq!(checksums, SequenceChecksums::of(b"ACGTACGT"));
+/// assert!(checksums.refget_id().starts_with("SQ."));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceChecksums
+{
+    /// Number of bases
+    pub length: u64,
+    /// Lowercase hexadecimal MD5, as in the `M5` tag
+    pub md5: String,
+    /// GA4GH `sha512t24u` digest (without the `SQ.` prefix)
+    pub sha512t24u: String,
+}
+
+impl SequenceChecksums
+{
+    /// Compute the checksums of a sequence in one go.
+    pub fn of(seq: &[u8]) -> Self
+    {
+        let mut hasher = SequenceHasher::new();
+        hasher.update(seq);
+        hasher.finish()
+    }
+
+    /// The refget sequence identifier, `SQ.<sha512t24u>`.
+    pub fn refget_id(&self) -> String
+    {
+        format!("SQ.{}", self.sha512t24u)
+    }
+}
+
+/// Streaming MD5 and `sha512t24u` computation over a sequence given in pieces.
+///
+/// Whitespace is skipped and bases are uppercased before hashing, so raw
+/// multi-line FASTA data can be fed in directly.
+///
+/// # Example
+///
+/// ```
+/// use fastx::dict::{SequenceChecksums, SequenceHasher};
+///
+/// let mut hasher = SequenceHasher::new();
+/// hasher.update(b"ACGT\n");
+/// hasher.update(b"acgt\n");
+/// assert_eq!(hasher.length(), 8);
+/// assert_eq!(hasher.finish(), SequenceChecksums::of(b"ACGTACGT"));
+/// ```
+pub struct SequenceHasher
+{
+    md5: Md5,
+    sha512: Sha512,
+    length: u64,
+}
+
+impl Default for SequenceHasher
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl SequenceHasher
+{
+    /// Create a hasher for an empty sequence.
+    pub fn new() -> Self
+    {
+        Self { md5: Md5::new(), sha512: Sha512::new(), length: 0 }
+    }
+
+    /// Add the next piece of the sequence.
+    pub fn update(&mut self, seq: &[u8])
+    {
+        let mut normalized = [0u8; 4096];
+        for chunk in seq.chunks(normalized.len())
+        {
+            let mut n = 0;
+            for &base in chunk.iter().filter(|b| !b.is_ascii_whitespace())
+            {
+                normalized[n] = base.to_ascii_uppercase();
+                n += 1;
+            }
+            self.md5.update(&normalized[..n]);
+            self.sha512.update(&normalized[..n]);
+            self.length += n as u64;
+        }
+    }
+
+    /// Number of bases hashed so far.
+    pub fn length(&self) -> u64
+    {
+        self.length
+    }
+
+    /// Finish hashing and return the checksums.
+    pub fn finish(self) -> SequenceChecksums
+    {
+        SequenceChecksums {
+            length: self.length,
+            md5: hex(&self.md5.finish()),
+            sha512t24u: base64(&self.sha512.finish()[..24], BASE64_URL_SAFE, false),
+        }
+    }
+}
+
+/// A single `@SQ` line of a sequence dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DictEntry
+{
+    /// Sequence name (`SN`)
+    pub name: String,
+    /// Sequence length (`LN`)
+    pub length: u64,
+    /// Hexadecimal MD5 of the normalized sequence (`M5`)
+    pub md5: Option<String>,
+    /// Refget `sha512t24u` digest, not part of the .dict format
+    pub sha512t24u: Option<String>,
+    /// URI of the reference the sequence was taken from (`UR`)
+    pub uri: Option<String>,
+}
+
+impl DictEntry
+{
+    /// Create an entry from computed checksums.
+    pub fn new(name: impl Into<String>, checksums: SequenceChecksums) -> Self
+    {
+        Self {
+            name: name.into(),
+            length: checksums.length,
+            md5: Some(checksums.md5),
+            sha512t24u: Some(checksums.sha512t24u),
+            uri: None,
+        }
+    }
+
+    /// Check if `digest` names this sequence.
+    ///
+    /// Accepts a hexadecimal MD5 (optionally prefixed with `md5:`), a refget
+    /// identifier (`SQ.…` or `ga4gh:SQ.…`) or a bare `sha512t24u` digest.
+    pub fn matches_digest(&self, digest: &str) -> bool
+    {
+        let digest = digest.trim();
+        let md5 = digest.strip_prefix("md5:").unwrap_or(digest);
+        let refget = digest.strip_prefix("ga4gh:").unwrap_or(digest);
+        let refget = refget.strip_prefix("SQ.").unwrap_or(refget);
+        self.md5.as_deref().is_some_and(|m| m.eq_ignore_ascii_case(md5))
+            || self.sha512t24u.as_deref() == Some(refget)
+    }
+}
+
+/// A difference between a claimed sequence dictionary and a reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DictMismatch
+{
+    /// No sequence of the reference has the claimed name or checksum
+    Missing
+    {
+        name: String
+    },
+    /// The sequence has a different length than claimed
+    Length
+    {
+        name: String, expected: u64, found: u64
+    },
+    /// The sequence has the claimed name and length but a different checksum
+    Checksum
+    {
+        name: String, expected: String, found: String
+    },
+}
+
+impl fmt::Display for DictMismatch
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            DictMismatch::Missing { name } => write!(f, "sequence '{}' not in reference", name),
+            DictMismatch::Length { name, expected, found } =>
+            {
+                write!(f, "sequence '{}' has length {}, expected {}", name, found, expected)
+            }
+            DictMismatch::Checksum { name, expected, found } =>
+            {
+                write!(f, "sequence '{}' has MD5 {}, expected {}", name, found, expected)
+            }
+        }
+    }
+}
+
+/// An ordered sequence dictionary.
+///
+/// # Example
+///
+/// ```
+/// use fastx::dict::SequenceDictionary;
+/// use std::io::Cursor;
+///
+/// let fasta = b">chr1\nACGT\nAC\n>chr2\nGGGG\n";
+/// let dict = SequenceDictionary::from_fasta(Cursor::new(&fasta[..])).unwrap();
+/// assert_eq!(dict.len(), 2);
+/// assert_eq!(dict.get("chr1").unwrap().length, 6);
+///
+/// let mut out = Vec::new();
+/// dict.write(&mut out).unwrap();
+/// let parsed = SequenceDictionary::from_reader(Cursor::new(out)).unwrap();
+/// assert!(parsed.mismatches(&dict).is_empty());
+///
+/// let md5 = dict.get("chr2").unwrap().md5.clone().unwrap();
+/// assert_eq!(dict.find_by_digest(&md5).unwrap().name, "chr2");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SequenceDictionary
+{
+    entries: Vec<DictEntry>,
+}
+
+impl SequenceDictionary
+{
+    /// Create a dictionary from entries in order.
+    pub fn new(entries: Vec<DictEntry>) -> Self
+    {
+        Self { entries }
+    }
+
+    /// Compute the dictionary of a FASTA file by reading it from start to end.
+    ///
+    /// Sequences are named by their ID, the header up to the first whitespace.
+    pub fn from_fasta<R: BufRead>(mut reader: R) -> io::Result<Self>
+    {
+        let mut entries = Vec::new();
+        let mut record = FastARecord::default();
+        while record.read(&mut reader)? > 0
+        {
+            entries.push(DictEntry::new(record.id(), SequenceChecksums::of(&record.raw_seq)));
+        }
+        Ok(Self { entries })
+    }
+
+    /// Compute the dictionary of the named sequences of an indexed reference.
+    ///
+    /// Sequences are fetched in chunks, so whole chromosomes are never held in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - An indexed FASTA or .2bit reader
+    /// * `names` - The sequences to include, in dictionary order
+    pub fn from_fetch<F: SequenceFetch + ?Sized>(reader: &mut F, names: &[&str]) -> io::Result<Self>
+    {
+        let mut entries = Vec::with_capacity(names.len());
+        for name in names
+        {
+            let length = reader.sequence_length(name)?;
+            let mut hasher = SequenceHasher::new();
+            let mut start = 0;
+            while start < length
+            {
+                let end = (start + FETCH_CHUNK).min(length);
+                hasher.update(&reader.fetch_range(name, start, end)?);
+                start = end;
+            }
+            entries.push(DictEntry::new(*name, hasher.finish()));
+        }
+        Ok(Self { entries })
+    }
+
+    /// Compute the dictionary of all sequences of an indexed FASTA file.
+    ///
+    /// Sequences appear in file order, as recorded by the .fai offsets.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::dict::SequenceDictionary;
+    /// use fastx::indexed::IndexedFastXReader;
+    /// use std::fs::File;
+    /// use std::path::Path;
+    ///
+    /// let mut reader = IndexedFastXReader::from_path(Path::new("ref.fa.gz")).unwrap();
+    /// let dict = SequenceDictionary::from_indexed(&mut reader)
+    ///     .unwrap()
+    ///     .with_uri("file:/data/ref.fa.gz");
+    /// dict.write(File::create("ref.dict").unwrap()).unwrap();
+    /// ```
+    pub fn from_indexed<R: Read + Seek>(reader: &mut IndexedFastXReader<R>) -> io::Result<Self>
+    {
+        let mut entries: Vec<_> = reader.index().entries().collect();
+        entries.sort_by_key(|entry| entry.offset);
+        let names: Vec<String> = entries.into_iter().map(|entry| entry.name.clone()).collect();
+        let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+        Self::from_fetch(reader, &names)
+    }
+
+    /// Parse the `@SQ` lines of a .dict file or SAM header.
+    ///
+    /// Other header lines are ignored, and parsing stops at the first alignment line.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self>
+    {
+        let mut entries = Vec::new();
+        for (number, line) in reader.lines().enumerate()
+        {
+            let line = line?;
+            if !line.starts_with('@')
+            {
+                if line.trim().is_empty()
+                {
+                    continue;
+                }
+                break;
+            }
+            if !line.starts_with("@SQ\t")
+            {
+                continue;
+            }
+
+            let (mut name, mut length, mut md5, mut uri) = (None, None, None, None);
+            for field in line.split('\t').skip(1)
+            {
+                match field.split_at_checked(3)
+                {
+                    Some(("SN:", value)) => name = Some(value.to_string()),
+                    Some(("LN:", value)) => length = value.parse::<u64>().ok(),
+                    Some(("M5:", value)) => md5 = Some(value.to_ascii_lowercase()),
+                    Some(("UR:", value)) => uri = Some(value.to_string()),
+                    _ => (),
+                }
+            }
+            let (Some(name), Some(length)) = (name, length)
+            else
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid @SQ line {}: missing SN or LN", number + 1),
+                ));
+            };
+            entries.push(DictEntry { name, length, md5, sha512t24u: None, uri });
+        }
+        Ok(Self { entries })
+    }
+
+    /// Load a .dict file.
+    pub fn from_path(path: &Path) -> io::Result<Self>
+    {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Set the `UR` field of every entry.
+    pub fn with_uri(mut self, uri: &str) -> Self
+    {
+        for entry in &mut self.entries
+        {
+            entry.uri = Some(uri.to_string());
+        }
+        self
+    }
+
+    /// Write the dictionary in .dict format.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()>
+    {
+        writeln!(writer, "@HD\tVN:1.0\tSO:unsorted")?;
+        for entry in &self.entries
+        {
+            write!(writer, "@SQ\tSN:{}\tLN:{}", entry.name, entry.length)?;
+            if let Some(md5) = &entry.md5
+            {
+                write!(writer, "\tM5:{}", md5)?;
+            }
+            if let Some(uri) = &entry.uri
+            {
+                write!(writer, "\tUR:{}", uri)?;
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+
+    /// Get all entries in order.
+    pub fn entries(&self) -> &[DictEntry]
+    {
+        &self.entries
+    }
+
+    /// Get the number of sequences.
+    pub fn len(&self) -> usize
+    {
+        self.entries.len()
+    }
+
+    /// Check if the dictionary is empty.
+    pub fn is_empty(&self) -> bool
+    {
+        self.entries.is_empty()
+    }
+
+    /// Get the entry of a sequence by name.
+    pub fn get(&self, name: &str) -> Option<&DictEntry>
+    {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// Find a sequence by checksum.
+    ///
+    /// See [`DictEntry::matches_digest`] for the accepted digest notations.
+    pub fn find_by_digest(&self, digest: &str) -> Option<&DictEntry>
+    {
+        self.entries.iter().find(|entry| entry.matches_digest(digest))
+    }
+
+    /// Compare a claimed dictionary, such as the `@SQ` lines of a BAM header, with
+    /// this one computed from the reference.
+    ///
+    /// Claimed sequences with an `M5` are looked up by checksum first, so renamed
+    /// sequences (`1` vs `chr1`) still match; the rest are looked up by name.
+    ///
+    /// # Returns
+    ///
+    /// The differences found, empty if the reference matches the claim.
+    pub fn mismatches(&self, claimed: &SequenceDictionary) -> Vec<DictMismatch>
+    {
+        let mut mismatches = Vec::new();
+        for expected in &claimed.entries
+        {
+            let by_digest = expected.md5.as_deref().and_then(|md5| self.find_by_digest(md5));
+            let Some(found) = by_digest.or_else(|| self.get(&expected.name))
+            else
+            {
+                mismatches.push(DictMismatch::Missing { name: expected.name.clone() });
+                continue;
+            };
+
+            if found.length != expected.length
+            {
+                mismatches.push(DictMismatch::Length {
+                    name: expected.name.clone(),
+                    expected: expected.length,
+                    found: found.length,
+                });
+            }
+            else if let (Some(expected_md5), Some(found_md5)) = (&expected.md5, &found.md5)
+            {
+                if !expected_md5.eq_ignore_ascii_case(found_md5)
+                {
+                    mismatches.push(DictMismatch::Checksum {
+                        name: expected.name.clone(),
+                        expected: expected_md5.clone(),
+                        found: found_md5.clone(),
+                    });
+                }
+            }
+        }
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::io::Cursor;
+
+    /// In-memory references for testing [`SequenceDictionary::from_fetch`].
+    struct Sequences(Vec<(&'static str, Vec<u8>)>);
+
+    impl SequenceFetch for Sequences
+    {
+        fn fetch(&mut self, seq_id: &str) -> io::Result<FastARecord>
+        {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Whole-record fetch of {} is not needed by these tests", seq_id),
+            ))
+        }
+
+        fn fetch_range(&mut self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
+        {
+            let seq = &self.0.iter().find(|(name, _)| *name == seq_id).unwrap().1;
+            Ok(seq[start as usize..end as usize].to_vec())
+        }
+
+        fn sequence_length(&mut self, seq_id: &str) -> io::Result<u64>
+        {
+            Ok(self.0.iter().find(|(name, _)| *name == seq_id).unwrap().1.len() as u64)
+        }
+
+        fn contains(&self, seq_id: &str) -> bool
+        {
+            self.0.iter().any(|(name, _)| *name == seq_id)
+        }
+
+        fn sequence_names(&self) -> Vec<&str>
+        {
+            self.0.iter().map(|(name, _)| *name).collect()
+        }
+    }
+
+    #[test]
+    fn test_checksums()
+    {
+        let checksums = SequenceChecksums::of(b"ACGT");
+        assert_eq!(checksums.md5, "f1f8f4bf413b16ad135722aa4591043e");
+        assert_eq!(checksums.sha512t24u, "aKF498dAxcJAqme6QYQ7EZ07-fiw8Kw2");
+        assert_eq!(checksums.refget_id(), "SQ.aKF498dAxcJAqme6QYQ7EZ07-fiw8Kw2");
+
+        // Case and line breaks do not change the checksum
+        assert_eq!(SequenceChecksums::of(b"ac\r\ngt\n"), checksums);
+
+        // Sequences longer than the normalization buffer
+        let long = vec![b'a'; 10_000];
+        let mut hasher = SequenceHasher::new();
+        for piece in long.chunks(333)
+        {
+            hasher.update(piece);
+        }
+        assert_eq!(hasher.finish(), SequenceChecksums::of(&vec![b'A'; 10_000]));
+    }
+
+    #[test]
+    fn test_from_fetch_matches_fasta()
+    {
+        let chr1 = b"ACGTNNacgt".repeat(200_000);
+        let mut fasta = b">chr1 first\n".to_vec();
+        for line in chr1.chunks(60)
+        {
+            fasta.extend_from_slice(line);
+            fasta.push(b'\n');
+        }
+        fasta.extend_from_slice(b">chrM\nGATC\n");
+
+        let from_fasta = SequenceDictionary::from_fasta(Cursor::new(fasta)).unwrap();
+        let mut sequences = Sequences(vec![("chr1", chr1), ("chrM", b"GATC".to_vec())]);
+        let from_fetch = SequenceDictionary::from_fetch(&mut sequences, &["chr1", "chrM"]).unwrap();
+        assert_eq!(from_fasta, from_fetch);
+        assert_eq!(from_fetch.entries()[0].length, 2_000_000);
+        assert_eq!(from_fetch.entries()[1].name, "chrM");
+    }
+
+    #[test]
+    fn test_write_and_parse()
+    {
+        let dict = SequenceDictionary::from_fasta(Cursor::new(&b">chr1\nACGT\n>chr2\nGG\n"[..]))
+            .unwrap()
+            .with_uri("file:/ref.fa");
+        let mut out = Vec::new();
+        dict.write(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "@HD\tVN:1.0\tSO:unsorted\n\
+             @SQ\tSN:chr1\tLN:4\tM5:f1f8f4bf413b16ad135722aa4591043e\tUR:file:/ref.fa\n\
+             @SQ\tSN:chr2\tLN:2\tM5:86d8d92aba9ecf9bbf89f69cb3e49588\tUR:file:/ref.fa\n"
+        );
+
+        let parsed = SequenceDictionary::from_reader(Cursor::new(out)).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed.get("chr2").unwrap().uri.as_deref(), Some("file:/ref.fa"));
+        assert_eq!(parsed.get("chr2").unwrap().sha512t24u, None);
+
+        // SAM headers stop at the first alignment
+        let sam = "@HD\tVN:1.6\n@SQ\tSN:1\tLN:4\n@PG\tID:bwa\nread1\t0\t1\t1\n@SQ\tSN:2\tLN:2\n";
+        let parsed = SequenceDictionary::from_reader(Cursor::new(sam)).unwrap();
+        assert_eq!(parsed.len(), 1);
+
+        let invalid = SequenceDictionary::from_reader(Cursor::new("@SQ\tSN:chr1\n"));
+        assert_eq!(invalid.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_find_by_digest_and_mismatches()
+    {
+        let dict =
+            SequenceDictionary::from_fasta(Cursor::new(&b">chr1\nACGT\n>chr2\nGG\n"[..])).unwrap();
+        for digest in [
+            "f1f8f4bf413b16ad135722aa4591043e",
+            "md5:F1F8F4BF413B16AD135722AA4591043E",
+            "SQ.aKF498dAxcJAqme6QYQ7EZ07-fiw8Kw2",
+            "ga4gh:SQ.aKF498dAxcJAqme6QYQ7EZ07-fiw8Kw2",
+            "aKF498dAxcJAqme6QYQ7EZ07-fiw8Kw2",
+        ]
+        {
+            assert_eq!(dict.find_by_digest(digest).unwrap().name, "chr1", "{}", digest);
+        }
+        assert!(dict.find_by_digest("00000000000000000000000000000000").is_none());
+
+        // Renamed but identical sequences match by checksum
+        let header = "@SQ\tSN:1\tLN:4\tM5:f1f8f4bf413b16ad135722aa4591043e\n\
+                      @SQ\tSN:chr2\tLN:3\n\
+                      @SQ\tSN:chrX\tLN:10\n";
+        let claimed = SequenceDictionary::from_reader(Cursor::new(header)).unwrap();
+        let mismatches = dict.mismatches(&claimed);
+        assert_eq!(
+            mismatches,
+            vec![
+                DictMismatch::Length { name: "chr2".to_string(), expected: 3, found: 2 },
+                DictMismatch::Missing { name: "chrX".to_string() },
+            ]
+        );
+        assert_eq!(mismatches[0].to_string(), "sequence 'chr2' has length 2, expected 3");
+
+        let header = "@SQ\tSN:chr2\tLN:2\tM5:00000000000000000000000000000000\n";
+        let claimed = SequenceDictionary::from_reader(Cursor::new(header)).unwrap();
+        assert!(matches!(
+            dict.mismatches(&claimed)[..],
+            [DictMismatch::Checksum { ref name, .. }] if name == "chr2"
+        ));
+    }
+}
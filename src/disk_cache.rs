@@ -0,0 +1,329 @@
+//! Persistent on-disk cache for remote file blocks and index downloads.
+//!
+//! Entries are keyed by URL and by a validator built from the `ETag`,
+//! `Last-Modified` and `Content-Length` response headers, so a changed remote
+//! file is never served from stale cache data.
+//!
+//! # Layout
+//!
+//! One directory per URL and validator, named `<url hash>-<validator hash>`,
+//! holding a `meta` file (URL and validator) and one file per cached block or
+//! download. Files are written to a temporary name and renamed into place, so
+//! several processes can share a cache directory: readers either see a complete
+//! file or none. When the cache grows beyond its maximum size, the least
+//! recently used files are removed.
+
+#![cfg(feature = "url")]
+
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Name of the file recording URL and validator of an entry.
+const META_FILE: &str = "meta";
+/// Prefix of files being written.
+const TEMP_PREFIX: &str = ".tmp-";
+/// Temporary files older than this are left over from crashed writers.
+const STALE_TEMP_AGE: Duration = Duration::from_secs(3600);
+
+/// A directory of cached remote data, shared by all readers using it.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::disk_cache::DiskCache;
+/// use fastx::indexed::IndexedFastXReader;
+/// use fastx::remote::RemoteConfig;
+///
+/// let cache = DiskCache::open("/var/cache/fastx", 10 * 1024 * 1024 * 1024).unwrap();
+/// let config = RemoteConfig::default().with_disk_cache(cache);
+/// let mut reader = IndexedFastXReader::from_url_with_config(
+///     "https://example.com/ref.fa.gz",
+///     "https://example.com/ref.fa.gz.fai",
+///     "https://example.com/ref.fa.gz.gzi",
+///     &config,
+/// ).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct DiskCache
+{
+    dir: PathBuf,
+    max_size: u64,
+    /// Bytes on disk as last counted plus bytes written since
+    size: Mutex<u64>,
+}
+
+/// The directory of one cached URL and validator.
+#[derive(Debug, Clone)]
+pub(crate) struct CacheEntry
+{
+    dir: PathBuf,
+}
+
+impl DiskCache
+{
+    /// Open or create a cache directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The cache directory, created if missing
+    /// * `max_size` - Maximum total size of cached files in bytes
+    pub fn open(dir: impl Into<PathBuf>, max_size: u64) -> io::Result<Self>
+    {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let size = scan(&dir)?.iter().map(|file| file.size).sum();
+        Ok(Self { dir, max_size, size: Mutex::new(size) })
+    }
+
+    /// Get the cache directory.
+    pub fn dir(&self) -> &Path
+    {
+        &self.dir
+    }
+
+    /// Get the maximum total size in bytes.
+    pub fn max_size(&self) -> u64
+    {
+        self.max_size
+    }
+
+    /// Count the bytes currently cached, including entries of other processes.
+    pub fn size_on_disk(&self) -> io::Result<u64>
+    {
+        Ok(scan(&self.dir)?.iter().map(|file| file.size).sum())
+    }
+
+    /// Remove all cached entries.
+    pub fn clear(&self) -> io::Result<()>
+    {
+        for entry in fs::read_dir(&self.dir)?
+        {
+            let path = entry?.path();
+            if path.is_dir()
+            {
+                remove_dir(&path);
+            }
+        }
+        *self.size.lock().map_err(|_| io::Error::other("Cache lock poisoned"))? = 0;
+        Ok(())
+    }
+
+    /// Get the entry for a URL whose current version is described by `validator`.
+    ///
+    /// Entries of other versions of the same URL are removed.
+    pub(crate) fn entry(&self, url: &str, validator: &str) -> io::Result<CacheEntry>
+    {
+        let url_key = format!("{:016x}", fnv1a(url.as_bytes()));
+        let name = format!("{}-{:016x}", url_key, fnv1a(validator.as_bytes()));
+        let dir = self.dir.join(&name);
+        let meta = format!("{}\n{}\n", url, validator);
+
+        match fs::read_to_string(dir.join(META_FILE))
+        {
+            Ok(existing) if existing == meta => return Ok(CacheEntry { dir }),
+            Ok(_) =>
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("Cache key collision for {}", url),
+                ))
+            }
+            Err(_) =>
+            {}
+        }
+
+        // Drop outdated versions of this URL
+        for other in fs::read_dir(&self.dir)?.flatten()
+        {
+            let other_name = other.file_name();
+            let other_name = other_name.to_string_lossy();
+            if other_name.starts_with(&url_key) && other_name != name
+            {
+                remove_dir(&other.path());
+            }
+        }
+
+        let entry = CacheEntry { dir };
+        self.write(&entry, META_FILE, meta.as_bytes())?;
+        Ok(entry)
+    }
+
+    /// Read a cached file, marking it as recently used.
+    pub(crate) fn read(&self, entry: &CacheEntry, name: &str) -> Option<Vec<u8>>
+    {
+        let path = entry.dir.join(name);
+        let data = fs::read(&path).ok()?;
+        if let Ok(file) = File::options().write(true).open(&path)
+        {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(data)
+    }
+
+    /// Check whether a file is cached.
+    pub(crate) fn contains(&self, entry: &CacheEntry, name: &str) -> bool
+    {
+        entry.dir.join(name).is_file()
+    }
+
+    /// Store a file atomically, evicting old files if the cache is full.
+    pub(crate) fn write(&self, entry: &CacheEntry, name: &str, data: &[u8]) -> io::Result<()>
+    {
+        if data.len() as u64 > self.max_size
+        {
+            return Ok(());
+        }
+        // The directory may have been evicted by another process
+        fs::create_dir_all(&entry.dir)?;
+        let temp = entry.dir.join(temp_name());
+        fs::write(&temp, data)?;
+        if let Err(e) = fs::rename(&temp, entry.dir.join(name))
+        {
+            let _ = fs::remove_file(&temp);
+            return Err(e);
+        }
+
+        let mut size = self.size.lock().map_err(|_| io::Error::other("Cache lock poisoned"))?;
+        *size += data.len() as u64;
+        if *size > self.max_size
+        {
+            *size = self.evict()?;
+        }
+        Ok(())
+    }
+
+    /// Remove least recently used files until the cache is below 90% of its
+    /// maximum size. Returns the remaining size.
+    fn evict(&self) -> io::Result<u64>
+    {
+        let mut files = scan(&self.dir)?;
+        let mut size: u64 = files.iter().map(|file| file.size).sum();
+        let target = self.max_size / 10 * 9;
+        files.sort_by_key(|file| file.modified);
+        for file in files
+        {
+            if size <= target
+            {
+                break;
+            }
+            if fs::remove_file(&file.path).is_ok()
+            {
+                size -= file.size;
+            }
+        }
+        Ok(size)
+    }
+}
+
+/// A cached file found while scanning.
+struct CachedFile
+{
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// List all cached files, removing stale temporary files on the way.
+fn scan(dir: &Path) -> io::Result<Vec<CachedFile>>
+{
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)?.flatten()
+    {
+        if !entry.path().is_dir()
+        {
+            continue;
+        }
+        for file in fs::read_dir(entry.path())?.flatten()
+        {
+            let metadata = match file.metadata()
+            {
+                Ok(metadata) if metadata.is_file() => metadata,
+                _ => continue,
+            };
+            let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+            if file.file_name().to_string_lossy().starts_with(TEMP_PREFIX)
+            {
+                let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+                if age > STALE_TEMP_AGE
+                {
+                    let _ = fs::remove_file(file.path());
+                }
+                continue;
+            }
+            files.push(CachedFile { path: file.path(), size: metadata.len(), modified });
+        }
+    }
+    Ok(files)
+}
+
+/// Remove a directory, tolerating concurrent removal by other processes.
+fn remove_dir(path: &Path)
+{
+    // Renaming first makes the removal appear atomic to other processes
+    let doomed = path.with_file_name(temp_name());
+    let target = if fs::rename(path, &doomed).is_ok() { doomed } else { path.to_path_buf() };
+    let _ = fs::remove_dir_all(target);
+}
+
+/// A file name unique across processes and threads.
+fn temp_name() -> String
+{
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    format!(
+        "{}{}-{}-{}",
+        TEMP_PREFIX,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        nanos
+    )
+}
+
+/// 64-bit FNV-1a hash, stable across platforms and releases.
+fn fnv1a(data: &[u8]) -> u64
+{
+    data.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_entries_and_eviction()
+    {
+        let dir = std::env::temp_dir().join(format!("fastx-disk-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let cache = DiskCache::open(&dir, 1000).unwrap();
+
+        let v1 = cache.entry("http://host/a", "length:10").unwrap();
+        cache.write(&v1, "block", b"0123456789").unwrap();
+        assert_eq!(cache.read(&v1, "block").unwrap(), b"0123456789");
+        assert!(cache.contains(&v1, "block"));
+
+        // A new version replaces the old one
+        let v2 = cache.entry("http://host/a", "length:11").unwrap();
+        assert!(!cache.contains(&v2, "block"));
+        assert!(!v1.dir.exists());
+
+        // Writing beyond the maximum evicts the oldest files
+        let other = cache.entry("http://host/b", "length:400").unwrap();
+        for i in 0..5
+        {
+            cache.write(&other, &format!("block{}", i), &[b'x'; 300]).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(cache.size_on_disk().unwrap() <= 1000);
+        assert!(!cache.contains(&other, "block0"));
+        assert!(cache.contains(&other, "block4"));
+
+        cache.clear().unwrap();
+        assert_eq!(cache.size_on_disk().unwrap(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
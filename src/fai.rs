@@ -12,6 +12,8 @@
 //! - LINEBASES: Number of bases per line
 //! - LINEWIDTH: Total bytes per line (including newline)
 
+use crate::alias::ChromAliases;
+use crate::region::Region;
 use std::collections::HashMap;
 use std::io;
 use std::io::BufRead;
@@ -124,6 +126,8 @@ impl FaiEntry
 pub struct FaiIndex
 {
     pub entries: HashMap<String, FaiEntry>,
+    /// Alternative names to look sequences up by
+    pub(crate) aliases: Option<ChromAliases>,
 }
 
 impl FaiIndex
@@ -233,19 +237,89 @@ impl FaiIndex
             entries.insert(entry.name.clone(), entry);
         }
 
-        Ok(FaiIndex { entries })
+        Ok(FaiIndex { entries, aliases: None })
     }
 
-    /// Get an entry by sequence name.
+    /// Look sequences up by alternative names too.
+    ///
+    /// [`get`](Self::get), [`contains`](Self::contains) and the readers using this
+    /// index then accept e.g. `chr1` for a sequence named `1` in the .fai.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastx::alias::ChromAliases;
+    /// use fastx::fai::FaiIndex;
+    /// use std::io::Cursor;
+    ///
+    /// let fai = "1\t1000\t3\t60\t61\nMT\t100\t1024\t60\t61\n";
+    /// let index = FaiIndex::from_reader(Cursor::new(fai))
+    ///     .unwrap()
+    ///     .with_aliases(ChromAliases::new());
+    /// assert_eq!(index.get("chr1").unwrap().name, "1");
+    /// assert!(index.contains("chrM"));
+    /// assert!(!index.contains("chr2"));
+    /// ```
+    pub fn with_aliases(mut self, aliases: ChromAliases) -> Self
+    {
+        self.aliases = Some(aliases);
+        self
+    }
+
+    /// Get the aliases used for lookups, if any.
+    pub fn aliases(&self) -> Option<&ChromAliases>
+    {
+        self.aliases.as_ref()
+    }
+
+    /// Get an entry by sequence name or alias.
     pub fn get(&self, name: &str) -> Option<&FaiEntry>
     {
-        self.entries.get(name)
+        self.entries.get(name).or_else(|| {
+            let aliases = self.aliases.as_ref()?;
+            aliases.find_candidate(name, |alias| self.entries.get(alias))
+        })
     }
 
-    /// Check if a sequence exists in the index.
+    /// Check if a sequence exists in the index, by name or alias.
     pub fn contains(&self, name: &str) -> bool
     {
-        self.entries.contains_key(name)
+        self.get(name).is_some()
+    }
+
+    /// Get the name a sequence has in the index.
+    pub fn resolve_name(&self, name: &str) -> Option<&str>
+    {
+        self.get(name).map(|entry| entry.name.as_str())
+    }
+
+    /// Parse a region such as `chr1:1,001-2,000` and resolve its sequence name.
+    ///
+    /// Unlike [`Region::from_str`](std::str::FromStr), a string naming a sequence of
+    /// the index is always taken as the whole sequence, so names ending in `:` and a
+    /// number need no explicit range.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Region)` - The region with the sequence name used in the index
+    /// * `Err(io::Error)` - If the region is invalid or the sequence not found
+    pub fn parse_region(&self, text: &str) -> io::Result<Region>
+    {
+        if let Some(name) = self.resolve_name(text)
+        {
+            return Ok(Region::whole(name));
+        }
+        let mut region: Region = text.parse()?;
+        region.name = self
+            .resolve_name(&region.name)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Sequence '{}' not found in index", region.name),
+                )
+            })?
+            .to_string();
+        Ok(region)
     }
 
     /// Get the number of sequences in the index.
@@ -345,4 +419,30 @@ mod tests
         let index = FaiIndex::from_reader(io::BufReader::new(data.as_bytes())).unwrap();
         assert_eq!(index.len(), 1);
     }
+
+    #[test]
+    fn test_aliases_and_regions()
+    {
+        let data = "1\t1000\t3\t60\t61\n\
+                    MT\t100\t1024\t60\t61\n\
+                    HLA-A*01:01\t50\t2000\t60\t61\n";
+        let index = FaiIndex::from_reader(io::BufReader::new(data.as_bytes())).unwrap();
+        assert!(!index.contains("chr1"));
+        assert_eq!(index.parse_region("chr1:1-10").unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(index.parse_region("HLA-A*01:01").unwrap(), Region::whole("HLA-A*01:01"));
+
+        let mut aliases = ChromAliases::new();
+        aliases.add(&["chr1", "NC_000001.11"]);
+        let index = index.with_aliases(aliases);
+        assert_eq!(index.resolve_name("chr1"), Some("1"));
+        assert_eq!(index.resolve_name("NC_000001.11"), Some("1"));
+        assert_eq!(index.resolve_name("chrM"), Some("MT"));
+        assert_eq!(index.resolve_name("chr2"), None);
+        assert_eq!(
+            index.parse_region("NC_000001.11:1,001-1,100").unwrap(),
+            Region::new("1", 1000, 1100)
+        );
+        assert_eq!(index.parse_region("chrM").unwrap(), Region::whole("MT"));
+        assert!(index.parse_region("chr1:20-10").is_err());
+    }
 }
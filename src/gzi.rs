@@ -154,7 +154,8 @@ impl GziIndex
         match result
         {
             Ok(i) => Some(self.entries[i].0),
-            Err(0) => Some(self.entries[0].0),
+            // The first block is implicit in .gzi files written by bgzip
+            Err(0) => Some(0),
             Err(i) if i >= self.entries.len() => Some(self.entries.last()?.0),
             Err(i) => Some(self.entries[i - 1].0),
         }
@@ -175,12 +176,27 @@ impl GziIndex
         match result
         {
             Ok(i) => Some(self.entries[i].1),
-            Err(0) => None,
+            Err(0) => Some(0),
             Err(i) if i >= self.entries.len() => Some(self.entries.last()?.1),
             Err(i) => Some(self.entries[i - 1].1),
         }
     }
 
+    /// Get the compressed byte range holding the uncompressed range `[start, end)`.
+    ///
+    /// # Returns
+    ///
+    /// `(compressed_start, compressed_end)`, where `compressed_end` is None if the
+    /// range reaches into the last indexed block and thus extends to the end of the file.
+    pub fn compressed_range(&self, start: u64, end: u64) -> (u64, Option<u64>)
+    {
+        let compressed_start = self.get_compressed_offset(start).unwrap_or(0);
+        // Entries are sorted by uncompressed offset
+        let next = self.entries.partition_point(|&(_, uncompressed)| uncompressed < end);
+        let compressed_end = self.entries.get(next).map(|&(compressed, _)| compressed);
+        (compressed_start, compressed_end)
+    }
+
     /// Get the number of index entries.
     pub fn len(&self) -> usize
     {
@@ -217,4 +233,18 @@ mod tests
         assert_eq!(index.len(), 1);
         assert_eq!(index.get_compressed_offset(0), Some(100));
     }
+
+    #[test]
+    fn test_implicit_first_block()
+    {
+        let index = GziIndex { entries: vec![(100, 1000), (250, 2000)] };
+        assert_eq!(index.get_compressed_offset(10), Some(0));
+        assert_eq!(index.get_uncompressed_offset(0), Some(0));
+        assert_eq!(index.get_compressed_offset(1500), Some(100));
+        assert_eq!(index.get_uncompressed_offset(250), Some(2000));
+        assert_eq!(index.compressed_range(10, 20), (0, Some(100)));
+        assert_eq!(index.compressed_range(500, 1000), (0, Some(100)));
+        assert_eq!(index.compressed_range(500, 1001), (0, Some(250)));
+        assert_eq!(index.compressed_range(1200, 2500), (100, None));
+    }
 }
@@ -1,16 +1,68 @@
 //! Indexed FASTA/FASTQ reader for random access by sequence ID.
 //!
 //! This module provides `IndexedFastXReader` which enables efficient random access
-//! to bgzip-compressed FASTA files using .fai and .gzi indexes.
+//! to bgzip-compressed FASTA files using .fai and .gzi indexes. Ordinary gzip
+//! files are supported through a [`ZranIndex`] checkpoint index.
 
+use crate::alias::ChromAliases;
 use crate::bgzf::BgzfReader;
 use crate::fai::{FaiEntry, FaiIndex};
 use crate::gzi::GziIndex;
+use crate::region::Region;
+use crate::shared::record_span;
+use crate::zran::{ZranIndex, ZranReader};
 use crate::FastX::{FastARecord, FastXRead};
 use std::fs::File;
-use std::io::{self, Read, Seek};
+use std::io::{self, BufRead, Read, Seek};
 use std::path::Path;
 
+/// Random access to sequences by ID, shared by the indexed reader types.
+///
+/// Implemented by [`IndexedFastXReader`] and [`TwoBitReader`](crate::twobit::TwoBitReader),
+/// so region-based code can be written once for both FASTA and .2bit references.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::indexed::{IndexedFastXReader, SequenceFetch};
+/// use fastx::twobit::TwoBitReader;
+/// use std::path::Path;
+///
+/// fn gc_content(reader: &mut dyn SequenceFetch, seq_id: &str, start: u64, end: u64) -> f64
+/// {
+///     let seq = reader.fetch_range(seq_id, start, end).unwrap();
+///     let gc = seq.iter().filter(|b| matches!(b, b'G' | b'C' | b'g' | b'c')).count();
+///     gc as f64 / seq.len() as f64
+/// }
+///
+/// let mut fasta = IndexedFastXReader::from_path(Path::new("ref.fa.gz")).unwrap();
+/// let mut twobit = TwoBitReader::from_path(Path::new("ref.2bit")).unwrap();
+/// assert_eq!(gc_content(&mut fasta, "chr1", 0, 1000), gc_content(&mut twobit, "chr1", 0, 1000));
+/// ```
+pub trait SequenceFetch
+{
+    /// Fetch a whole sequence by its ID.
+    fn fetch(&mut self, seq_id: &str) -> io::Result<FastARecord>;
+
+    /// Fetch the bases in `[start, end)` of a sequence, `end` clamped to its length.
+    fn fetch_range(&mut self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>;
+
+    /// Fetch the bases of a region, such as one parsed from `"chr1:1001-2000"`.
+    fn fetch_region(&mut self, region: &Region) -> io::Result<Vec<u8>>
+    {
+        self.fetch_range(&region.name, region.start, region.end_or_max())
+    }
+
+    /// Get the length of a sequence.
+    fn sequence_length(&mut self, seq_id: &str) -> io::Result<u64>;
+
+    /// Check if a sequence exists.
+    fn contains(&self, seq_id: &str) -> bool;
+
+    /// Get all sequence names.
+    fn sequence_names(&self) -> Vec<&str>;
+}
+
 /// An indexed FASTA/FASTQ reader supporting random access by sequence ID.
 ///
 /// This reader uses both .fai (for sequence metadata) and .gzi (for gzip seeking)
@@ -50,10 +102,92 @@ use std::path::Path;
 /// ```
 pub struct IndexedFastXReader<R: Read + Seek>
 {
-    /// The BGZF reader for decompression
-    reader: BgzfReader<R>,
+    /// The decompressing reader
+    reader: Decoder<R>,
     /// The FASTA index for sequence lookup
     fai_index: FaiIndex,
+    /// Hook to fetch a compressed byte span ahead of reading it
+    prefetch: Option<PrefetchFn<R>>,
+}
+
+/// Fetches the compressed bytes `[start, end)` of the underlying reader ahead of use.
+type PrefetchFn<R> = fn(&mut R, u64, u64) -> io::Result<()>;
+
+/// Seekable decompression backends for [`IndexedFastXReader`].
+enum Decoder<R: Read + Seek>
+{
+    Bgzf(BgzfReader<R>),
+    Gzip(ZranReader<R>),
+}
+
+impl<R: Read + Seek> Decoder<R>
+{
+    fn seek_uncompressed(&mut self, uncompressed_pos: u64) -> io::Result<u64>
+    {
+        match self
+        {
+            Decoder::Bgzf(reader) => reader.seek_uncompressed(uncompressed_pos),
+            Decoder::Gzip(reader) => reader.seek_uncompressed(uncompressed_pos),
+        }
+    }
+
+    /// Let the underlying reader fetch the compressed bytes of the uncompressed
+    /// span `[start, end)` at once, if it supports that.
+    fn prefetch_span(
+        &mut self,
+        prefetch: Option<PrefetchFn<R>>,
+        start: u64,
+        end: u64,
+    ) -> io::Result<()>
+    {
+        let prefetch = match prefetch
+        {
+            Some(prefetch) => prefetch,
+            None => return Ok(()),
+        };
+        if let Decoder::Bgzf(reader) = self
+        {
+            if let Some(gzi) = reader.gzi_index()
+            {
+                let (compressed_start, compressed_end) = gzi.compressed_range(start, end);
+                prefetch(reader.get_mut(), compressed_start, compressed_end.unwrap_or(u64::MAX))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Read for Decoder<R>
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+        match self
+        {
+            Decoder::Bgzf(reader) => reader.read(buf),
+            Decoder::Gzip(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl<R: Read + Seek> BufRead for Decoder<R>
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]>
+    {
+        match self
+        {
+            Decoder::Bgzf(reader) => reader.fill_buf(),
+            Decoder::Gzip(reader) => reader.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize)
+    {
+        match self
+        {
+            Decoder::Bgzf(reader) => reader.consume(amt),
+            Decoder::Gzip(reader) => reader.consume(amt),
+        }
+    }
 }
 
 impl<R: Read + Seek> IndexedFastXReader<R>
@@ -66,7 +200,18 @@ impl<R: Read + Seek> IndexedFastXReader<R>
     /// * `fai_index` - A parsed FASTA index
     pub fn new(reader: BgzfReader<R>, fai_index: FaiIndex) -> Self
     {
-        Self { reader, fai_index }
+        Self { reader: Decoder::Bgzf(reader), fai_index, prefetch: None }
+    }
+
+    /// Create a new indexed reader for an ordinary gzip file.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A gzip reader with its checkpoint index
+    /// * `fai_index` - A parsed FASTA index
+    pub fn with_zran(reader: ZranReader<R>, fai_index: FaiIndex) -> Self
+    {
+        Self { reader: Decoder::Gzip(reader), fai_index, prefetch: None }
     }
 }
 
@@ -93,7 +238,11 @@ impl IndexedFastXReader<File>
     ///
     /// For a file like `data.fasta.gz`:
     /// - `data.fasta.gz.fai` or `data.fasta.fai` - Required FASTA index
-    /// - `data.fasta.gz.gzi` or `data.fasta.gzi` - Required gzip index for compressed files
+    /// - `data.fasta.gz.gzi` or `data.fasta.gzi` - Required gzip index for BGZF files
+    /// - `data.fasta.gz.zran` - Checkpoint index for ordinary gzip files
+    ///
+    /// Ordinary (non-BGZF) gzip files need a `.zran` index, which is built once with
+    /// [`ZranIndex::build`] and saved with [`ZranIndex::write_to_path`].
     ///
     /// # Example
     ///
@@ -123,7 +272,7 @@ impl IndexedFastXReader<File>
         // Check if file is gzip compressed and look for .gzi
         let is_gzip = path.extension().map(|e| e == "gz").unwrap_or(false);
 
-        let file = File::open(path)?;
+        let mut file = File::open(path)?;
 
         let reader = if is_gzip
         {
@@ -131,9 +280,17 @@ impl IndexedFastXReader<File>
             if let Some(gzi_path) = find_index_file(path, "gzi")
             {
                 let gzi_index = GziIndex::from_path(&gzi_path)?;
-                BgzfReader::with_index(file, gzi_index)?
+                Decoder::Bgzf(BgzfReader::with_index(file, gzi_index)?)
             }
-            else
+            else if let Some(zran_path) = find_index_file(path, "zran")
+            {
+                let zran_index = ZranIndex::from_path(&zran_path)?;
+                zran_index.check_source(&mut file).map_err(|e| {
+                    io::Error::new(e.kind(), format!("{}: {}", zran_path.display(), e))
+                })?;
+                Decoder::Gzip(ZranReader::new(file, zran_index))
+            }
+            else if is_bgzf(path)?
             {
                 return Err(io::Error::new(
                     io::ErrorKind::NotFound,
@@ -144,21 +301,35 @@ impl IndexedFastXReader<File>
                     ),
                 ));
             }
+            else
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "Index not found for gzip file {} (expected {}.zran, create it with \
+                         ZranIndex::build and write_to_path, or recompress with bgzip)",
+                        path.display(),
+                        path.display()
+                    ),
+                ));
+            }
         }
         else
         {
             return Err(io::Error::new(
                 io::ErrorKind::Unsupported,
-                "Uncompressed files not yet supported, please use bgzip-compressed files",
+                "Uncompressed files not yet supported, please use gzip-compressed files",
             ));
         };
 
-        Ok(Self { reader, fai_index })
+        Ok(Self { reader, fai_index, prefetch: None })
     }
 
-    /// Open an indexed FASTA file from HTTP/HTTPS URLs.
+    /// Open an indexed FASTA file from URLs.
     ///
-    /// This requires the `url` feature to be enabled.
+    /// `file://`, `http://`, `https://` and `s3://` URLs are supported, see
+    /// [`open_url`](crate::source::open_url). This requires the `url` feature
+    /// to be enabled.
     ///
     /// # Arguments
     ///
@@ -190,83 +361,832 @@ impl IndexedFastXReader<File>
         data_url: impl Into<String>,
         fai_url: impl Into<String>,
         gzi_url: impl Into<String>,
-    ) -> io::Result<IndexedFastXReader<crate::remote::RemoteReader>>
+    ) -> io::Result<IndexedFastXReader<crate::source::SourceReader>>
+    {
+        Self::from_url_with_config(
+            data_url,
+            fai_url,
+            gzi_url,
+            &crate::remote::RemoteConfig::default(),
+        )
+    }
+
+    /// Open an indexed FASTA file from URLs with custom connection settings.
+    ///
+    /// The data file and both indexes are fetched through one shared agent, so
+    /// authentication headers, proxy and TLS settings apply to all of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_url` - URL to the FASTA data file (.fasta.gz)
+    /// * `fai_url` - URL to the .fai index file
+    /// * `gzi_url` - URL to the .gzi index file
+    /// * `config` - Connection settings, see [`RemoteConfig`](crate::remote::RemoteConfig)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::indexed::IndexedFastXReader;
+    /// use fastx::remote::RemoteConfig;
+    ///
+    /// let config = RemoteConfig::default().with_bearer_token("secret-token");
+    /// let mut reader = IndexedFastXReader::from_url_with_config(
+    ///     "https://portal.example.com/data.fasta.gz",
+    ///     "https://portal.example.com/data.fasta.gz.fai",
+    ///     "https://portal.example.com/data.fasta.gz.gzi",
+    ///     &config,
+    /// ).unwrap();
+    /// ```
+    #[cfg(feature = "url")]
+    pub fn from_url_with_config(
+        data_url: impl Into<String>,
+        fai_url: impl Into<String>,
+        gzi_url: impl Into<String>,
+        config: &crate::remote::RemoteConfig,
+    ) -> io::Result<IndexedFastXReader<crate::source::SourceReader>>
+    {
+        let agent = config.build_agent()?;
+        Self::from_url_with_agent(data_url.into(), &fai_url.into(), &gzi_url.into(), agent, config)
+    }
+
+    /// Open an indexed FASTA file from a URL, discovering its index files.
+    ///
+    /// Index URLs are probed (with HEAD requests over HTTP) following the same conventions
+    /// as [`from_path`](IndexedFastXReader::from_path):
+    /// - `data.fasta.gz.fai`, then `data.fasta.fai`
+    /// - `data.fasta.gz.gzi`, then `data.fasta.gzi`
+    ///
+    /// Index locations can also be given explicitly with the htslib convention of
+    /// appending `##idx##` and the index URL to the data URL. Either index may be
+    /// given that way; the other one is still discovered.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_url` - URL to the FASTA data file, optionally with `##idx##` suffixes
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(reader)` - The indexed reader ready for use
+    /// * `Err(io::Error)` - `NotFound` listing the tried URLs if an index is missing
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::indexed::IndexedFastXReader;
+    ///
+    /// let url = "https://example.com/data.fasta.gz";
+    /// let mut reader = IndexedFastXReader::from_url_auto(url).unwrap();
+    ///
+    /// // Index stored elsewhere
+    /// let mut reader = IndexedFastXReader::from_url_auto(
+    ///     "https://example.com/data.fasta.gz##idx##https://indexes.example.com/data.fasta.gz.fai",
+    /// ).unwrap();
+    /// ```
+    #[cfg(feature = "url")]
+    pub fn from_url_auto(
+        data_url: impl Into<String>,
+    ) -> io::Result<IndexedFastXReader<crate::source::SourceReader>>
     {
-        use crate::remote::RemoteReader;
+        Self::from_url_auto_with_config(data_url, &crate::remote::RemoteConfig::default())
+    }
+
+    /// Open an indexed FASTA file from a URL with custom connection settings,
+    /// discovering its index files as [`from_url_auto`](IndexedFastXReader::from_url_auto) does.
+    #[cfg(feature = "url")]
+    pub fn from_url_auto_with_config(
+        data_url: impl Into<String>,
+        config: &crate::remote::RemoteConfig,
+    ) -> io::Result<IndexedFastXReader<crate::source::SourceReader>>
+    {
+        let data_url = data_url.into();
+        let mut parts = data_url.split("##idx##");
+        let data = parts.next().unwrap_or_default().to_string();
+        let explicit: Vec<&str> = parts.collect();
+        let explicit_for = |ext: &str| {
+            explicit
+                .iter()
+                .find(|url| url_path(url).ends_with(&format!(".{}", ext)))
+                .map(|url| url.to_string())
+        };
+
+        let agent = config.build_agent()?;
+        let discover = |ext: &str| match explicit_for(ext)
+        {
+            Some(url) => Ok(url),
+            None => find_index_url(&agent, config, &data, ext),
+        };
+        let fai_url = discover("fai")?;
+        let gzi_url = discover("gzi")?;
+        Self::from_url_with_agent(data, &fai_url, &gzi_url, agent, config)
+    }
+
+    #[cfg(feature = "url")]
+    fn from_url_with_agent(
+        data_url: String,
+        fai_url: &str,
+        gzi_url: &str,
+        agent: ureq::Agent,
+        config: &crate::remote::RemoteConfig,
+    ) -> io::Result<IndexedFastXReader<crate::source::SourceReader>>
+    {
+        use crate::source::{open_url_with_agent, read_url, SourceReader};
 
         // Fetch and parse the FAI index
-        let fai_url = fai_url.into();
-        let fai_data = fetch_url(&fai_url)?;
+        let fai_data = read_url(&agent, config, fai_url)?;
         let fai_index = parse_fai_from_bytes(&fai_data)?;
 
         // Fetch and parse the GZI index
-        let gzi_url = gzi_url.into();
-        let gzi_data = fetch_url(&gzi_url)?;
+        let gzi_data = read_url(&agent, config, gzi_url)?;
         let gzi_index = parse_gzi_from_bytes(&gzi_data)?;
 
-        // Create the remote reader
-        let remote_reader = RemoteReader::new(data_url)?;
-        let reader = BgzfReader::with_index(remote_reader, gzi_index)?;
+        // Open the data file with the backend for its scheme
+        let source = open_url_with_agent(&data_url, Some(agent), config)?;
+        let reader = BgzfReader::with_index(source, gzi_index)?;
 
-        Ok(IndexedFastXReader { reader, fai_index })
+        // Fetch the compressed span of each request in one go
+        let mut reader = IndexedFastXReader::new(reader, fai_index);
+        reader.prefetch = Some(|source: &mut SourceReader, start, end| source.prefetch(start, end));
+        Ok(reader)
+    }
+}
+
+impl<R: Read + Seek> IndexedFastXReader<R>
+{
+    /// Fetch a sequence by its ID.
+    ///
+    /// Reads the entire sequence from the file using the index.
+    ///
+    /// # Arguments
+    ///
+    /// * `seq_id` - The sequence identifier (e.g., "chr1", "gene123")
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(FastARecord)` - The fetched sequence record
+    /// * `Err(io::Error)` - If the sequence is not found or reading fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::indexed::IndexedFastXReader;
+    /// use fastx::FastX::FastXRead;
+    /// use std::path::Path;
+    ///
+    /// let mut reader = IndexedFastXReader::from_path(Path::new("data.fasta.gz")).unwrap();
+    ///
+    /// match reader.fetch("chr1") {
+    ///     Ok(record) => println!("Got sequence {}: {} bp", record.id(), record.seq_len()),
+    ///     Err(e) => eprintln!("Error: {}", e),
+    /// }
+    /// ```
+    pub fn fetch(&mut self, seq_id: &str) -> io::Result<FastARecord>
+    {
+        let mut record = FastARecord::default();
+        self.fetch_into(seq_id, &mut record)?;
+        Ok(record)
+    }
+
+    /// Fetch a sequence by its ID into an existing record.
+    ///
+    /// Like [`fetch`](Self::fetch), but reuses the record's name and sequence
+    /// storage, so fetching many sequences does not allocate for each.
+    ///
+    /// # Arguments
+    ///
+    /// * `seq_id` - The sequence identifier
+    /// * `record` - The record to overwrite
+    pub fn fetch_into(&mut self, seq_id: &str, record: &mut FastARecord) -> io::Result<()>
+    {
+        let entry = self.fai_index.get(seq_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Sequence '{}' not found in index", seq_id),
+            )
+        })?;
+        Self::read_entry(&mut self.reader, self.prefetch, entry, record)
+    }
+
+    /// Fetch a specific region of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `seq_id` - The sequence identifier
+    /// * `start` - 0-based start position
+    /// * `end` - End position (exclusive)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<u8>)` - The sequence data for the requested region
+    /// * `Err(io::Error)` - If the sequence is not found or reading fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::indexed::IndexedFastXReader;
+    /// use std::path::Path;
+    ///
+    /// let mut reader = IndexedFastXReader::from_path(Path::new("data.fasta.gz")).unwrap();
+    ///
+    /// // Fetch bases 1000-2000 of chr1
+    /// let region = reader.fetch_range("chr1", 1000, 2000).unwrap();
+    /// println!("Region length: {} bp", region.len());
+    /// ```
+    pub fn fetch_range(&mut self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
+    {
+        let mut seq_data = Vec::new();
+        self.fetch_range_into(seq_id, start, end, &mut seq_data)?;
+        Ok(seq_data)
+    }
+
+    /// Fetch a specific region of a sequence into an existing buffer.
+    ///
+    /// Like [`fetch_range`](Self::fetch_range), but `buf` is cleared and refilled,
+    /// reusing its capacity. The bases are copied line by line straight out of the
+    /// decompressed block, so repeated fetches of short regions do not allocate.
+    ///
+    /// # Arguments
+    ///
+    /// * `seq_id` - The sequence identifier
+    /// * `start` - 0-based start position
+    /// * `end` - End position (exclusive)
+    /// * `buf` - The buffer to fill with the bases
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::indexed::IndexedFastXReader;
+    /// use std::path::Path;
+    ///
+    /// let mut reader = IndexedFastXReader::from_path(Path::new("data.fasta.gz")).unwrap();
+    /// let mut buf = Vec::new();
+    /// for start in (0..1_000_000).step_by(1000) {
+    ///     reader.fetch_range_into("chr1", start, start + 100, &mut buf).unwrap();
+    ///     assert_eq!(buf.len(), 100);
+    /// }
+    /// ```
+    pub fn fetch_range_into(
+        &mut self,
+        seq_id: &str,
+        start: u64,
+        end: u64,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()>
+    {
+        buf.clear();
+        let mut region = self.open_region(seq_id, start, end, true)?;
+        buf.reserve(region.remaining() as usize);
+        loop
+        {
+            let bases = region.fill_buf()?;
+            if bases.is_empty()
+            {
+                return Ok(());
+            }
+            buf.extend_from_slice(bases);
+            let n = bases.len();
+            region.consume(n);
+        }
+    }
+
+    /// Stream the bases of a region instead of fetching them at once.
+    ///
+    /// The returned reader yields the bases in `[start, end)` (`end` clamped to the
+    /// sequence length) with line terminators removed. Compressed blocks are
+    /// decompressed as they are read, so whole chromosomes can be processed in
+    /// constant memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `seq_id` - The sequence identifier
+    /// * `start` - 0-based start position
+    /// * `end` - End position (exclusive)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RegionReader)` - A `Read`/`BufRead` adapter over the region's bases
+    /// * `Err(io::Error)` - If the sequence is not found or seeking fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::indexed::IndexedFastXReader;
+    /// use std::io::BufRead;
+    /// use std::path::Path;
+    ///
+    /// let mut reader = IndexedFastXReader::from_path(Path::new("data.fasta.gz")).unwrap();
+    ///
+    /// // Count GC over all of chr1 without holding it in memory
+    /// let mut region = reader.region_reader("chr1", 0, u64::MAX).unwrap();
+    /// let mut gc = 0;
+    /// loop {
+    ///     let bases = region.fill_buf().unwrap();
+    ///     if bases.is_empty() {
+    ///         break;
+    ///     }
+    ///     gc += bases.iter().filter(|b| matches!(b, b'G' | b'C' | b'g' | b'c')).count();
+    ///     let n = bases.len();
+    ///     region.consume(n);
+    /// }
+    /// ```
+    pub fn region_reader(
+        &mut self,
+        seq_id: &str,
+        start: u64,
+        end: u64,
+    ) -> io::Result<RegionReader<'_, R>>
+    {
+        self.open_region(seq_id, start, end, false)
+    }
+
+    /// Iterate over windows of `size` bases every `step` bases of all sequences.
+    ///
+    /// Sequences are visited in file order and each is read once from start to end.
+    /// Windows overlap if `step < size` and leave gaps if `step > size`. By default the
+    /// last window of a sequence is truncated at its end; see [`Windows::with_partial`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Windows)` - The window iterator
+    /// * `Err(io::Error)` - If `size` or `step` is zero
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::indexed::IndexedFastXReader;
+    /// use std::path::Path;
+    ///
+    /// let mut reader = IndexedFastXReader::from_path(Path::new("data.fasta.gz")).unwrap();
+    ///
+    /// // GC content in 1 kb windows every 500 bp, as a bedGraph
+    /// let mut windows = reader.windows(1000, 500).unwrap();
+    /// while let Some(window) = windows.next_window() {
+    ///     let window = window.unwrap();
+    ///     let gc = window.seq.iter().filter(|b| matches!(b, b'G' | b'C')).count();
+    ///     let fraction = gc as f64 / window.seq.len() as f64;
+    ///     println!("{}\t{}\t{}\t{:.3}", window.seq_id, window.start, window.end, fraction);
+    /// }
+    /// ```
+    pub fn windows(&mut self, size: u64, step: u64) -> io::Result<Windows<'_, R>>
+    {
+        let mut entries: Vec<FaiEntry> = self.fai_index.entries().cloned().collect();
+        entries.sort_by_key(|entry| entry.offset);
+        Windows::new(&mut self.reader, entries, size, step)
+    }
+
+    /// Iterate over windows of `size` bases every `step` bases of one sequence.
+    ///
+    /// See [`windows`](Self::windows).
+    pub fn sequence_windows(
+        &mut self,
+        seq_id: &str,
+        size: u64,
+        step: u64,
+    ) -> io::Result<Windows<'_, R>>
+    {
+        let entry = self.fai_index.get(seq_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Sequence '{}' not found in index", seq_id),
+            )
+        })?;
+        let entries = vec![entry.clone()];
+        Windows::new(&mut self.reader, entries, size, step)
+    }
+
+    /// Seek to the start of a region and return a reader over its bases.
+    ///
+    /// With `prefetch`, the compressed bytes of the whole region are requested at once.
+    fn open_region(
+        &mut self,
+        seq_id: &str,
+        start: u64,
+        end: u64,
+        prefetch: bool,
+    ) -> io::Result<RegionReader<'_, R>>
+    {
+        let entry = self.fai_index.get(seq_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Sequence '{}' not found in index", seq_id),
+            )
+        })?;
+
+        if start >= entry.length
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Start position {} beyond sequence length {}", start, entry.length),
+            ));
+        }
+
+        let clamped_end = end.min(entry.length);
+
+        if prefetch
+        {
+            let start_offset = entry.offset_for_position(start);
+            let end_offset = entry.offset_for_position(clamped_end - 1) + 1;
+            self.reader.prefetch_span(self.prefetch, start_offset, end_offset)?;
+        }
+
+        RegionReader::new(&mut self.reader, entry, start, clamped_end)
+    }
+
+    /// Read the record of a FAI entry into `record`.
+    fn read_entry(
+        reader: &mut Decoder<R>,
+        prefetch: Option<PrefetchFn<R>>,
+        entry: &FaiEntry,
+        record: &mut FastARecord,
+    ) -> io::Result<()>
+    {
+        // The FAI offset points to the sequence data (after the header line), so
+        // the header is searched for backwards from there
+        let (header_offset, end_offset) = record_span(entry);
+        reader.prefetch_span(prefetch, header_offset, end_offset)?;
+
+        // Seek to where the header might start
+        reader.seek_uncompressed(header_offset)?;
+
+        // Scan the decompressed data up to entry.offset for the last '>' (the header start)
+        let mut header_start = None;
+        let mut pos = header_offset;
+        while pos < entry.offset
+        {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty()
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("Unexpected end of file before sequence '{}'", entry.name),
+                ));
+            }
+            let n = buf.len().min((entry.offset - pos) as usize);
+            if let Some(i) = memchr::memrchr(b'>', &buf[..n])
+            {
+                header_start = Some(pos + i as u64);
+            }
+            reader.consume(n);
+            pos += n as u64;
+        }
+        let Some(header_start) = header_start
+        else
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Could not find FASTA header for sequence '{}'", entry.name),
+            ));
+        };
+
+        // Seek to the header start and parse the record
+        reader.seek_uncompressed(header_start)?;
+        record.read(reader)?;
+
+        Ok(())
+    }
+
+    /// Look sequences up by alternative names too.
+    ///
+    /// `fetch`, `fetch_range`, `contains` and [`parse_region`](Self::parse_region)
+    /// then accept e.g. `chr1` on a reference naming it `1`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::alias::ChromAliases;
+    /// use fastx::indexed::{IndexedFastXReader, SequenceFetch};
+    /// use std::path::Path;
+    ///
+    /// let aliases = ChromAliases::from_path(Path::new("hg38.chromAlias.txt")).unwrap();
+    /// let mut reader = IndexedFastXReader::from_path(Path::new("Homo_sapiens.GRCh38.fa.gz"))
+    ///     .unwrap()
+    ///     .with_aliases(aliases);
+    /// let region = reader.parse_region("NC_000001.11:10,001-10,100").unwrap();
+    /// assert_eq!(region.name, "1");
+    /// let bases = reader.fetch_region(&region).unwrap();
+    /// ```
+    pub fn with_aliases(mut self, aliases: ChromAliases) -> Self
+    {
+        self.fai_index = self.fai_index.with_aliases(aliases);
+        self
+    }
+
+    /// Parse a region such as `chr1:1,001-2,000`, resolving aliases of its sequence name.
+    ///
+    /// See [`FaiIndex::parse_region`].
+    pub fn parse_region(&self, text: &str) -> io::Result<Region>
+    {
+        self.fai_index.parse_region(text)
+    }
+
+    /// Get a reference to the FAI index.
+    pub fn index(&self) -> &FaiIndex
+    {
+        &self.fai_index
+    }
+
+    /// Get a reference to the GZI index, if available.
+    pub fn gzi_index(&self) -> Option<&GziIndex>
+    {
+        match &self.reader
+        {
+            Decoder::Bgzf(reader) => reader.gzi_index(),
+            Decoder::Gzip(_) => None,
+        }
+    }
+
+    /// Get a reference to the checkpoint index, if reading an ordinary gzip file.
+    pub fn zran_index(&self) -> Option<&ZranIndex>
+    {
+        match &self.reader
+        {
+            Decoder::Bgzf(_) => None,
+            Decoder::Gzip(reader) => Some(reader.index()),
+        }
+    }
+
+    /// Check if a sequence exists in the index.
+    ///
+    /// # Arguments
+    ///
+    /// * `seq_id` - The sequence identifier to check
+    pub fn contains(&self, seq_id: &str) -> bool
+    {
+        self.fai_index.contains(seq_id)
+    }
+
+    /// Get all sequence names in the index.
+    pub fn sequence_names(&self) -> Vec<&str>
+    {
+        self.fai_index.sequence_names().collect()
+    }
+}
+
+/// Streams the bases of a region, returned by [`IndexedFastXReader::region_reader`].
+///
+/// Line terminators are skipped using the `line_bases`/`line_width` of the .fai entry.
+pub struct RegionReader<'a, R: Read + Seek>
+{
+    reader: &'a mut Decoder<R>,
+    /// Bases per line
+    line_bases: u64,
+    /// Line terminator bytes per line
+    padding: u64,
+    /// Column of the next base
+    col: u64,
+    /// Bases left in the region
+    remaining: u64,
+}
+
+impl<'a, R: Read + Seek> RegionReader<'a, R>
+{
+    /// Seek to `start` of a sequence and read up to `end`, both within its length.
+    fn new(reader: &'a mut Decoder<R>, entry: &FaiEntry, start: u64, end: u64) -> io::Result<Self>
+    {
+        reader.seek_uncompressed(entry.offset_for_position(start))?;
+        Ok(Self {
+            reader,
+            line_bases: entry.line_bases,
+            padding: entry.line_width - entry.line_bases,
+            col: start % entry.line_bases,
+            remaining: end - start,
+        })
+    }
+
+    /// Get the number of bases not read yet.
+    pub fn remaining(&self) -> u64
+    {
+        self.remaining
+    }
+}
+
+impl<R: Read + Seek> BufRead for RegionReader<'_, R>
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]>
+    {
+        if self.remaining == 0
+        {
+            return Ok(&[]);
+        }
+
+        // At the end of a line's bases, skip the line terminator
+        if self.col >= self.line_bases
+        {
+            let mut padding = self.padding;
+            while padding > 0
+            {
+                let available = self.reader.fill_buf()?.len() as u64;
+                if available == 0
+                {
+                    break;
+                }
+                let n = available.min(padding);
+                self.reader.consume(n as usize);
+                padding -= n;
+            }
+            self.col = 0;
+        }
+
+        let in_line = self.remaining.min(self.line_bases - self.col);
+        let buf = self.reader.fill_buf()?;
+        if buf.is_empty()
+        {
+            return Err(truncated_sequence());
+        }
+        let n = (buf.len() as u64).min(in_line) as usize;
+        Ok(&buf[..n])
+    }
+
+    fn consume(&mut self, amt: usize)
+    {
+        self.reader.consume(amt);
+        self.col += amt as u64;
+        self.remaining -= amt as u64;
+    }
+}
+
+impl<R: Read + Seek> Read for RegionReader<'_, R>
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+/// How [`Windows`] treat the last window of a sequence if it is shorter than the window size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PartialWindow
+{
+    /// Yield the last window truncated at the end of the sequence
+    #[default]
+    Keep,
+    /// Yield only windows of the full size
+    Skip,
+}
+
+/// A window of bases yielded by [`Windows`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window<'a>
+{
+    /// Sequence name
+    pub seq_id: &'a str,
+    /// 0-based start
+    pub start: u64,
+    /// Exclusive end
+    pub end: u64,
+    /// The bases in `[start, end)`
+    pub seq: &'a [u8],
+}
+
+/// An owned copy of a [`Window`], yielded by [`OwnedWindows`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedWindow
+{
+    /// Sequence name
+    pub seq_id: String,
+    /// 0-based start
+    pub start: u64,
+    /// Exclusive end
+    pub end: u64,
+    /// The bases in `[start, end)`
+    pub seq: Vec<u8>,
+}
+
+/// Sliding windows over indexed sequences, created by [`IndexedFastXReader::windows`]
+/// and [`IndexedFastXReader::sequence_windows`].
+///
+/// Each sequence is streamed once; only the bases of the current window are kept in
+/// memory. `Windows` is a lending iterator, not an `Iterator`: each [`Window`]
+/// borrows this buffer, so it must be dropped before [`next_window`](Self::next_window)
+/// is called again. Use [`into_owned`](Self::into_owned) for an `Iterator` that
+/// copies every window instead.
+///
+/// Iteration stops after the first error: the call returning `Some(Err(_))` is
+/// followed by `None`.
+pub struct Windows<'a, R: Read + Seek>
+{
+    /// The current sequence, None between sequences
+    region: Option<RegionReader<'a, R>>,
+    /// The decoder while no sequence is open
+    reader: Option<&'a mut Decoder<R>>,
+    /// Sequences still to visit
+    entries: std::vec::IntoIter<FaiEntry>,
+    size: u64,
+    step: u64,
+    partial: PartialWindow,
+    /// Name and length of the current sequence
+    seq_id: String,
+    length: u64,
+    /// Start of the next window, `length` once the sequence is done
+    pos: u64,
+    /// Bases read from the current sequence, starting at `buf_start`
+    buf: Vec<u8>,
+    buf_start: u64,
+    /// Set after an error, ending the iteration
+    failed: bool,
+}
+
+impl<'a, R: Read + Seek> Windows<'a, R>
+{
+    fn new(
+        reader: &'a mut Decoder<R>,
+        entries: Vec<FaiEntry>,
+        size: u64,
+        step: u64,
+    ) -> io::Result<Self>
+    {
+        if size == 0 || step == 0
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid window size {} or step {}", size, step),
+            ));
+        }
+        Ok(Self {
+            region: None,
+            reader: Some(reader),
+            entries: entries.into_iter(),
+            size,
+            step,
+            partial: PartialWindow::default(),
+            seq_id: String::new(),
+            length: 0,
+            pos: 0,
+            buf: Vec::new(),
+            buf_start: 0,
+            failed: false,
+        })
     }
-}
 
-impl<R: Read + Seek> IndexedFastXReader<R>
-{
-    /// Fetch a sequence by its ID.
-    ///
-    /// Reads the entire sequence from the file using the index.
-    ///
-    /// # Arguments
+    /// Set how the last window of a sequence is treated if it is shorter than the
+    /// window size.
     ///
-    /// * `seq_id` - The sequence identifier (e.g., "chr1", "gene123")
+    /// With [`PartialWindow::Keep`], windows continue until one reaches the end of the
+    /// sequence, so every base is covered; with [`PartialWindow::Skip`], only windows
+    /// of the full size are returned.
+    pub fn with_partial(mut self, partial: PartialWindow) -> Self
+    {
+        self.partial = partial;
+        self
+    }
+
+    /// Read the next window.
     ///
     /// # Returns
     ///
-    /// * `Ok(FastARecord)` - The fetched sequence record
-    /// * `Err(io::Error)` - If the sequence is not found or reading fails
-    ///
-    /// # Example
-    ///
-    /// ```no_run
-    /// use fastx::indexed::IndexedFastXReader;
-    /// use fastx::FastX::FastXRead;
-    /// use std::path::Path;
-    ///
-    /// let mut reader = IndexedFastXReader::from_path(Path::new("data.fasta.gz")).unwrap();
-    ///
-    /// match reader.fetch("chr1") {
-    ///     Ok(record) => println!("Got sequence {}: {} bp", record.id(), record.seq_len()),
-    ///     Err(e) => eprintln!("Error: {}", e),
-    /// }
-    /// ```
-    pub fn fetch(&mut self, seq_id: &str) -> io::Result<FastARecord>
+    /// * `Some(Ok(window))` - The next window
+    /// * `Some(Err(e))` - If reading fails; later calls return None
+    /// * `None` - After the last window of the last sequence, or after an error
+    pub fn next_window(&mut self) -> Option<io::Result<Window<'_>>>
     {
-        let entry = self.fai_index.get(seq_id).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::NotFound,
-                format!("Sequence '{}' not found in index", seq_id),
-            )
-        })?;
+        if self.failed
+        {
+            return None;
+        }
+        loop
+        {
+            if self.pos < self.length
+            {
+                let end = (self.pos + self.size).min(self.length);
+                if end - self.pos == self.size || self.partial == PartialWindow::Keep
+                {
+                    break;
+                }
+            }
+            match self.next_sequence()
+            {
+                Ok(true) => (),
+                Ok(false) => return None,
+                Err(e) =>
+                {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
 
-        // Clone the entry to avoid borrowing issues
-        let entry = entry.clone();
-        self.fetch_entry(&entry)
+        let start = self.pos;
+        let end = (start + self.size).min(self.length);
+        if let Err(e) = self.fill(start, end)
+        {
+            self.failed = true;
+            return Some(Err(e));
+        }
+        self.pos = if end == self.length { self.length } else { start + self.step };
+
+        let offset = (start - self.buf_start) as usize;
+        Some(Ok(Window {
+            seq_id: &self.seq_id,
+            start,
+            end,
+            seq: &self.buf[offset..offset + (end - start) as usize],
+        }))
     }
 
-    /// Fetch a specific region of a sequence.
-    ///
-    /// # Arguments
-    ///
-    /// * `seq_id` - The sequence identifier
-    /// * `start` - 0-based start position
-    /// * `end` - End position (exclusive)
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(Vec<u8>)` - The sequence data for the requested region
-    /// * `Err(io::Error)` - If the sequence is not found or reading fails
+    /// Turn into an `Iterator` yielding a copy of every window.
     ///
     /// # Example
     ///
@@ -275,179 +1195,147 @@ impl<R: Read + Seek> IndexedFastXReader<R>
     /// use std::path::Path;
     ///
     /// let mut reader = IndexedFastXReader::from_path(Path::new("data.fasta.gz")).unwrap();
-    ///
-    /// // Fetch bases 1000-2000 of chr1
-    /// let region = reader.fetch_range("chr1", 1000, 2000).unwrap();
-    /// println!("Region length: {} bp", region.len());
+    /// let windows: Vec<_> =
+    ///     reader.windows(1000, 1000).unwrap().into_owned().collect::<Result<_, _>>().unwrap();
     /// ```
-    pub fn fetch_range(&mut self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
+    pub fn into_owned(self) -> OwnedWindows<'a, R>
     {
-        let entry = self.fai_index.get(seq_id).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::NotFound,
-                format!("Sequence '{}' not found in index", seq_id),
-            )
-        })?;
-
-        // Clone to avoid borrowing issues
-        let entry = entry.clone();
+        OwnedWindows { windows: self }
+    }
 
-        if start >= entry.length
+    /// Open the next non-empty sequence, returning false after the last one.
+    fn next_sequence(&mut self) -> io::Result<bool>
+    {
+        let Some(entry) = self.entries.by_ref().find(|entry| entry.length > 0)
+        else
         {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!("Start position {} beyond sequence length {}", start, entry.length),
-            ));
-        }
-
-        let clamped_end = end.min(entry.length);
-        let region_length = clamped_end - start;
-
-        // Calculate file offset for start position
-        let start_offset = entry.offset_for_position(start);
-
-        // Seek to the start position
-        self.reader.seek_uncompressed(start_offset)?;
+            return Ok(false);
+        };
+        let reader = match self.region.take()
+        {
+            Some(region) => region.reader,
+            None => self.reader.take().ok_or_else(|| {
+                io::Error::other("Window iteration stopped after a previous error")
+            })?,
+        };
+        self.region = Some(RegionReader::new(reader, &entry, 0, entry.length)?);
+        self.seq_id = entry.name;
+        self.length = entry.length;
+        self.pos = 0;
+        self.buf.clear();
+        self.buf_start = 0;
+        Ok(true)
+    }
 
-        // Read the sequence data, handling line wrapping
-        let mut seq_data = Vec::with_capacity(region_length as usize);
-        let mut remaining = region_length;
-        let mut col = start % entry.line_bases;
+    /// Make the buffer hold the bases in `[start, end)` of the current sequence.
+    fn fill(&mut self, start: u64, end: u64) -> io::Result<()>
+    {
+        let region = self.region.as_mut().ok_or_else(|| io::Error::other("No sequence open"))?;
 
-        while remaining > 0
+        // Drop bases before the window, skipping unread ones in a gap between windows
+        let buf_end = self.buf_start + self.buf.len() as u64;
+        if start >= buf_end
         {
-            // If we are at the end of a line's bases, skip the padding (newlines)
-            if col >= entry.line_bases
+            let mut skip = start - buf_end;
+            while skip > 0
             {
-                let padding = entry.line_width - entry.line_bases;
-                if padding > 0
+                let n = (region.fill_buf()?.len() as u64).min(skip);
+                if n == 0
                 {
-                    // Skip padding bytes (newlines)
-                    // We need to read and discard them because we can't easily seek relative
-                    // in the compressed stream without resetting the decompressor state
-                    let mut trash = vec![0u8; padding as usize];
-                    self.reader.read_exact(&mut trash)?;
+                    return Err(truncated_sequence());
                 }
-                col = 0;
+                region.consume(n as usize);
+                skip -= n;
             }
+            self.buf.clear();
+            self.buf_start = start;
+        }
+        else if (start - self.buf_start) as usize > self.buf.len() / 2
+        {
+            self.buf.drain(..(start - self.buf_start) as usize);
+            self.buf_start = start;
+        }
 
-            // Calculate how much we can read from the current line
-            let in_line = std::cmp::min(remaining, entry.line_bases - col);
-
-            // Read that many bytes
-            let mut buf = vec![0u8; in_line as usize];
-            let n = self.reader.read(&mut buf)?;
+        while self.buf_start + (self.buf.len() as u64) < end
+        {
+            let missing = end - self.buf_start - self.buf.len() as u64;
+            let available = region.fill_buf()?;
+            let n = (available.len() as u64).min(missing) as usize;
             if n == 0
             {
-                return Err(io::Error::new(
-                    io::ErrorKind::UnexpectedEof,
-                    "Unexpected end of file while reading sequence",
-                ));
+                return Err(truncated_sequence());
             }
-            seq_data.extend_from_slice(&buf[..n]);
-            remaining -= n as u64;
-            col += n as u64; // Advance column by actual bytes read
+            self.buf.extend_from_slice(&available[..n]);
+            region.consume(n);
         }
-
-        Ok(seq_data)
+        Ok(())
     }
+}
 
-    /// Fetch a sequence using its FAI entry directly.
-    fn fetch_entry(&mut self, entry: &FaiEntry) -> io::Result<FastARecord>
-    {
-        // The FAI offset points to the sequence data (after the header line).
-        // We need to find the header start by seeking backwards to find the '>' character.
-        // Search up to 4KB backwards which should be enough for any header.
-        const MAX_HEADER_SEARCH: u64 = 4096;
-
-        let header_offset = entry.offset.saturating_sub(MAX_HEADER_SEARCH);
-
-        // Seek to where the header might start
-        self.reader.seek_uncompressed(header_offset)?;
-
-        // Read data until we find '>' or reach entry.offset
-        let buffer_len = (entry.offset - header_offset) as usize;
-        let mut buffer = vec![0u8; buffer_len];
-        self.reader.read_exact(&mut buffer)?;
+/// An `Iterator` over copies of the windows of [`Windows`], created by
+/// [`Windows::into_owned`].
+pub struct OwnedWindows<'a, R: Read + Seek>
+{
+    windows: Windows<'a, R>,
+}
 
-        // Find the last '>' before entry.offset (the header start)
-        let header_start = match buffer.iter().rposition(|&b| b == b'>')
-        {
-            Some(pos) => header_offset + pos as u64,
-            None =>
-            {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("Could not find FASTA header for sequence '{}'", entry.name),
-                ));
-            }
-        };
+impl<R: Read + Seek> Iterator for OwnedWindows<'_, R>
+{
+    type Item = io::Result<OwnedWindow>;
 
-        // Seek to the header start and parse the record
-        self.reader.seek_uncompressed(header_start)?;
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        let window = self.windows.next_window()?;
+        Some(window.map(|window| OwnedWindow {
+            seq_id: window.seq_id.to_string(),
+            start: window.start,
+            end: window.end,
+            seq: window.seq.to_vec(),
+        }))
+    }
+}
 
-        let mut record = FastARecord::default();
-        record.read(&mut self.reader)?;
+fn truncated_sequence() -> io::Error
+{
+    io::Error::new(io::ErrorKind::UnexpectedEof, "Unexpected end of file while reading sequence")
+}
 
-        Ok(record)
+impl<R: Read + Seek> SequenceFetch for IndexedFastXReader<R>
+{
+    fn fetch(&mut self, seq_id: &str) -> io::Result<FastARecord>
+    {
+        IndexedFastXReader::fetch(self, seq_id)
     }
 
-    /// Get a reference to the FAI index.
-    pub fn index(&self) -> &FaiIndex
+    fn fetch_range(&mut self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
     {
-        &self.fai_index
+        IndexedFastXReader::fetch_range(self, seq_id, start, end)
     }
 
-    /// Get a reference to the GZI index, if available.
-    pub fn gzi_index(&self) -> Option<&GziIndex>
+    fn sequence_length(&mut self, seq_id: &str) -> io::Result<u64>
     {
-        self.reader.gzi_index()
+        self.fai_index.get(seq_id).map(|entry| entry.length).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Sequence '{}' not found in index", seq_id),
+            )
+        })
     }
 
-    /// Check if a sequence exists in the index.
-    ///
-    /// # Arguments
-    ///
-    /// * `seq_id` - The sequence identifier to check
-    pub fn contains(&self, seq_id: &str) -> bool
+    fn contains(&self, seq_id: &str) -> bool
     {
-        self.fai_index.contains(seq_id)
+        IndexedFastXReader::contains(self, seq_id)
     }
 
-    /// Get all sequence names in the index.
-    pub fn sequence_names(&self) -> Vec<&str>
+    fn sequence_names(&self) -> Vec<&str>
     {
-        self.fai_index.sequence_names().collect()
+        IndexedFastXReader::sequence_names(self)
     }
 }
 
-/// Fetch data from a URL (requires `url` feature).
-#[cfg(feature = "url")]
-#[allow(dead_code)]
-fn fetch_url(url: &str) -> io::Result<Vec<u8>>
-{
-    let agent = ureq::Agent::new_with_defaults();
-
-    let response = agent.get(url).call().map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::ConnectionRefused,
-            format!("HTTP GET request failed for {}: {}", url, e),
-        )
-    })?;
-
-    let data = response.into_body().read_to_vec().map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::ConnectionRefused,
-            format!("Failed to read response body: {}", e),
-        )
-    })?;
-
-    Ok(data)
-}
-
 /// Parse FAI index from bytes (for URL support).
 #[allow(dead_code)]
-fn parse_fai_from_bytes(data: &[u8]) -> io::Result<FaiIndex>
+pub(crate) fn parse_fai_from_bytes(data: &[u8]) -> io::Result<FaiIndex>
 {
     use crate::fai::FaiEntry;
     use std::collections::HashMap;
@@ -531,12 +1419,12 @@ fn parse_fai_from_bytes(data: &[u8]) -> io::Result<FaiIndex>
     }
 
     // Use internal constructor to create FaiIndex
-    Ok(FaiIndex { entries })
+    Ok(FaiIndex { entries, aliases: None })
 }
 
 /// Parse GZI index from bytes (for URL support).
 #[allow(dead_code)]
-fn parse_gzi_from_bytes(data: &[u8]) -> io::Result<GziIndex>
+pub(crate) fn parse_gzi_from_bytes(data: &[u8]) -> io::Result<GziIndex>
 {
     if data.len() < 8
     {
@@ -610,12 +1498,93 @@ fn parse_gzi_from_bytes(data: &[u8]) -> io::Result<GziIndex>
 
 use std::path::PathBuf;
 
+/// Check whether a gzip file is BGZF by looking for the `BC` extra subfield
+/// in its first member header.
+fn is_bgzf(path: &Path) -> io::Result<bool>
+{
+    let mut header = [0u8; 16];
+    let mut file = File::open(path)?;
+    let mut filled = 0;
+    while filled < header.len()
+    {
+        let n = file.read(&mut header[filled..])?;
+        if n == 0
+        {
+            return Ok(false);
+        }
+        filled += n;
+    }
+    // ID1 ID2 CM FLG(FEXTRA) MTIME(4) XFL OS XLEN(2) SI1 SI2
+    Ok(header[0] == 0x1f
+        && header[1] == 0x8b
+        && header[3] & 0x04 != 0
+        && header[12] == b'B'
+        && header[13] == b'C')
+}
+
+/// The path part of a URL, without query string or fragment.
+#[cfg(feature = "url")]
+fn url_path(url: &str) -> &str
+{
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    &url[..end]
+}
+
+/// Candidate index URLs for a data URL, in the order of [`find_index_file`].
+///
+/// - For `http://host/data.fasta.gz`: `data.fasta.gz.fai` then `data.fasta.fai`
+/// - A query string is kept after the changed path
+#[cfg(feature = "url")]
+fn index_url_candidates(data_url: &str, ext: &str) -> Vec<String>
+{
+    let path = url_path(data_url);
+    let rest = &data_url[path.len()..];
+    let mut candidates = vec![format!("{}.{}{}", path, ext, rest)];
+
+    let name_start = path.rfind('/').map(|i| i + 1).unwrap_or(0);
+    if let Some(dot) = path[name_start..].rfind('.').filter(|&dot| dot > 0)
+    {
+        let stem = &path[..name_start + dot];
+        candidates.push(format!("{}.{}{}", stem, ext, rest));
+    }
+    candidates
+}
+
+/// Probe candidate index URLs and return the first that exists.
+#[cfg(feature = "url")]
+fn find_index_url(
+    agent: &ureq::Agent,
+    config: &crate::remote::RemoteConfig,
+    data_url: &str,
+    ext: &str,
+) -> io::Result<String>
+{
+    let mut tried = Vec::new();
+    for candidate in index_url_candidates(data_url, ext)
+    {
+        match crate::source::url_exists(agent, config, &candidate)
+        {
+            Ok(()) => return Ok(candidate),
+            Err(e) => tried.push(format!("{} ({})", candidate, e)),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "{} index not found for {}; tried: {}",
+            ext.to_uppercase(),
+            data_url,
+            tried.join(", ")
+        ),
+    ))
+}
+
 /// Find an index file for a given data file.
 ///
 /// Tries multiple patterns:
 /// - For `data.fasta.gz`: tries `data.fasta.gz.fai` then `data.fasta.fai`
 /// - For `data.fasta`: tries `data.fasta.fai`
-fn find_index_file(path: &Path, ext: &str) -> Option<PathBuf>
+pub(crate) fn find_index_file(path: &Path, ext: &str) -> Option<PathBuf>
 {
     let stem = path.with_extension("");
 
@@ -640,6 +1609,8 @@ fn find_index_file(path: &Path, ext: &str) -> Option<PathBuf>
 mod tests
 {
     use super::*;
+    use crate::bgzf::TestReference;
+    use std::io::Cursor;
 
     #[test]
     fn test_find_index_file()
@@ -706,4 +1677,373 @@ mod tests
         assert_eq!(index.get_compressed_offset(0), Some(0));
         assert_eq!(index.get_compressed_offset(5000), Some(0));
     }
+
+    #[test]
+    fn test_plain_gzip_with_zran()
+    {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let fasta_path = Path::new("test_zran.fasta.gz");
+        let fai_path = Path::new("test_zran.fasta.gz.fai");
+        let zran_path = Path::new("test_zran.fasta.gz.zran");
+
+        // Two sequences of 60 bases per line
+        let chr1: Vec<u8> = (0..3000u32).map(|i| b"ACGT"[(i * 7 % 4) as usize]).collect();
+        let chr2: Vec<u8> = (0..500u32).map(|i| b"TTGCA"[(i % 5) as usize]).collect();
+        let reference = TestReference::new(&[("chr1", &chr1), ("chr2", &chr2)], 1000);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&reference.text).unwrap();
+        std::fs::write(fasta_path, encoder.finish().unwrap()).unwrap();
+        std::fs::write(fai_path, reference.fai).unwrap();
+
+        // Nothing is written next to the data without asking
+        let err = IndexedFastXReader::from_path(fasta_path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(!zran_path.exists());
+        let zran_index = ZranIndex::build(File::open(fasta_path).unwrap(), 1024).unwrap();
+        zran_index.write_to_path(zran_path).unwrap();
+
+        let mut reader = IndexedFastXReader::from_path(fasta_path).unwrap();
+        assert!(reader.gzi_index().is_none());
+        assert!(reader.zran_index().is_some());
+        assert_eq!(reader.fetch_range("chr1", 100, 250).unwrap(), &chr1[100..250]);
+        assert_eq!(reader.fetch_range("chr2", 10, 1000).unwrap(), &chr2[10..]);
+
+        // Reopen
+        let mut reader = IndexedFastXReader::from_path(fasta_path).unwrap();
+        let record = reader.fetch("chr2").unwrap();
+        assert_eq!(record.id(), "chr2");
+        assert_eq!(record.seq(), chr2);
+
+        std::fs::remove_file(fasta_path).unwrap();
+        std::fs::remove_file(fai_path).unwrap();
+        std::fs::remove_file(zran_path).unwrap();
+    }
+
+    /// Compress FASTA text into BGZF blocks of `block_len` bytes and open it with `fai`.
+    fn bgzf_reader(text: &[u8], fai: &str, block_len: usize) -> IndexedFastXReader<Cursor<Vec<u8>>>
+    {
+        let (compressed, gzi) = crate::bgzf::compress_for_tests(text, block_len);
+        let gzi_index = parse_gzi_from_bytes(&gzi).unwrap();
+        let bgzf = BgzfReader::with_index(Cursor::new(compressed), gzi_index).unwrap();
+        IndexedFastXReader::new(bgzf, parse_fai_from_bytes(fai.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn test_region_reader()
+    {
+        // Lines of 60 bases with CRLF terminators, spread over many small blocks
+        let chr1: Vec<u8> = (0..5000u32).map(|i| b"ACGTN"[(i * 7 % 5) as usize]).collect();
+        let mut text = b">chr1\r\n".to_vec();
+        let fai = format!("chr1\t{}\t{}\t60\t62\n", chr1.len(), text.len());
+        for line in chr1.chunks(60)
+        {
+            text.extend_from_slice(line);
+            text.extend_from_slice(b"\r\n");
+        }
+        let mut reader = bgzf_reader(&text, &fai, 700);
+
+        for (start, end) in [(0, 5000), (59, 61), (123, 4321), (4990, u64::MAX)]
+        {
+            let mut region = reader.region_reader("chr1", start, end).unwrap();
+            let expected = &chr1[start as usize..(end as usize).min(chr1.len())];
+            assert_eq!(region.remaining(), expected.len() as u64);
+
+            // Small reads cross line and block boundaries
+            let mut bases = Vec::new();
+            let mut buf = [0u8; 37];
+            loop
+            {
+                let n = region.read(&mut buf).unwrap();
+                if n == 0
+                {
+                    break;
+                }
+                bases.extend_from_slice(&buf[..n]);
+            }
+            assert_eq!(bases, expected);
+            assert_eq!(region.remaining(), 0);
+            assert_eq!(reader.fetch_range("chr1", start, end).unwrap(), expected);
+        }
+
+        let lines: Vec<String> =
+            reader.region_reader("chr1", 0, 120).unwrap().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines, vec![String::from_utf8(chr1[..120].to_vec()).unwrap()]);
+        assert!(reader.region_reader("chr1", 5000, 6000).is_err());
+        assert_eq!(
+            reader.region_reader("chr2", 0, 10).err().unwrap().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn test_windows()
+    {
+        let chr1: Vec<u8> = (0..1000u32).map(|i| b"ACGT"[(i * 7 % 4) as usize]).collect();
+        let chr2: Vec<u8> = (0..95u32).map(|i| b"TTGCA"[(i % 5) as usize]).collect();
+        let TestReference { text, fai, .. } =
+            TestReference::new(&[("chr2", &chr2), ("empty", &Vec::new()), ("chr1", &chr1)], 300);
+        let mut reader = bgzf_reader(&text, &fai, 300);
+        let seqs = [("chr1", &chr1), ("chr2", &chr2)];
+
+        let collect = |windows: &mut Windows<'_, Cursor<Vec<u8>>>| {
+            let mut spans = Vec::new();
+            while let Some(window) = windows.next_window()
+            {
+                let window = window.unwrap();
+                let seq = seqs.iter().find(|(name, _)| *name == window.seq_id).unwrap().1;
+                assert_eq!(window.seq, &seq[window.start as usize..window.end as usize]);
+                spans.push((window.seq_id.to_string(), window.start, window.end));
+            }
+            spans
+        };
+
+        // File order, overlapping windows, truncated last window
+        let spans = collect(&mut reader.windows(40, 30).unwrap());
+        assert_eq!(
+            spans[..4],
+            [
+                ("chr2".to_string(), 0, 40),
+                ("chr2".to_string(), 30, 70),
+                ("chr2".to_string(), 60, 95),
+                ("chr1".to_string(), 0, 40)
+            ]
+        );
+        assert_eq!(spans.last().unwrap(), &("chr1".to_string(), 960, 1000));
+        assert_eq!(spans.len(), 3 + 33);
+
+        // Gaps between windows, partial window skipped
+        let mut windows = reader.sequence_windows("chr2", 20, 50).unwrap();
+        assert_eq!(
+            collect(&mut windows),
+            [("chr2".to_string(), 0, 20), ("chr2".to_string(), 50, 70)]
+        );
+        let mut windows =
+            reader.sequence_windows("chr2", 30, 30).unwrap().with_partial(PartialWindow::Skip);
+        assert_eq!(collect(&mut windows).len(), 3);
+
+        // Windows longer than the sequence
+        let mut windows = reader.sequence_windows("chr2", 500, 1).unwrap();
+        assert_eq!(collect(&mut windows), [("chr2".to_string(), 0, 95)]);
+        let windows = reader.sequence_windows("chr2", 500, 1).unwrap();
+        assert!(windows.with_partial(PartialWindow::Skip).next_window().is_none());
+
+        // Owned windows match the borrowed ones
+        let owned: Vec<OwnedWindow> =
+            reader.windows(40, 30).unwrap().into_owned().collect::<io::Result<_>>().unwrap();
+        let spans: Vec<_> = owned.iter().map(|w| (w.seq_id.clone(), w.start, w.end)).collect();
+        assert_eq!(spans, collect(&mut reader.windows(40, 30).unwrap()));
+        assert_eq!(owned[1].seq, &chr2[30..70]);
+
+        // Iteration stops after an error, here a sequence shorter than indexed
+        let fai = fai.replace("chr1\t1000\t", "chr1\t1100\t");
+        let mut reader = bgzf_reader(&text, &fai, 300);
+        let mut windows = reader.sequence_windows("chr1", 1000, 1000).unwrap().into_owned();
+        assert_eq!(windows.next().unwrap().unwrap().seq, chr1);
+        assert!(windows.next().unwrap().is_err());
+        assert!(windows.next().is_none());
+
+        assert!(reader.windows(0, 10).is_err());
+        assert!(reader.sequence_windows("chrX", 10, 10).is_err());
+    }
+
+    #[test]
+    fn test_fetch_into()
+    {
+        // A long header so the search for '>' spans several blocks
+        let header = format!(">chr1 {}\n", "x".repeat(500));
+        let chr1: Vec<u8> = (0..2000u32).map(|i| b"ACGT"[(i * 3 % 4) as usize]).collect();
+        let mut text = b">chr0\nAC\n".to_vec();
+        text.extend_from_slice(header.as_bytes());
+        let fai = format!("chr0\t2\t6\t60\t61\nchr1\t{}\t{}\t60\t61\n", chr1.len(), text.len());
+        for line in chr1.chunks(60)
+        {
+            text.extend_from_slice(line);
+            text.push(b'\n');
+        }
+        let mut reader = bgzf_reader(&text, &fai, 128);
+
+        let mut buf = b"stale".to_vec();
+        reader.fetch_range_into("chr1", 55, 185, &mut buf).unwrap();
+        assert_eq!(buf, &chr1[55..185]);
+        let capacity = buf.capacity();
+        reader.fetch_range_into("chr1", 1990, 3000, &mut buf).unwrap();
+        assert_eq!(buf, &chr1[1990..]);
+        assert_eq!(buf.capacity(), capacity);
+        assert!(reader.fetch_range_into("chr2", 0, 10, &mut buf).is_err());
+
+        let mut record = FastARecord::default();
+        reader.fetch_into("chr1", &mut record).unwrap();
+        assert_eq!(record.id(), "chr1");
+        assert_eq!(record.seq(), chr1);
+        reader.fetch_into("chr0", &mut record).unwrap();
+        assert_eq!(record.name(), "chr0");
+        assert_eq!(record.seq(), b"AC");
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_from_url_with_config()
+    {
+        use crate::remote::RemoteConfig;
+        use crate::test_server::StubServer;
+
+        let seq: Vec<u8> = (0..5000u32).map(|i| b"ACGGT"[(i * 3 % 5) as usize]).collect();
+        let mut text = b">chr1\n".to_vec();
+        for line in seq.chunks(70)
+        {
+            text.extend_from_slice(line);
+            text.push(b'\n');
+        }
+        let (data, gzi) = crate::bgzf::compress_for_tests(&text, 1000);
+        let fai = format!("chr1\t{}\t6\t70\t71\n", seq.len()).into_bytes();
+        let server = StubServer::with_files(vec![
+            ("/ref.fa.gz", data),
+            ("/ref.fa.gz.fai", fai),
+            ("/ref.fa.gz.gzi", gzi),
+        ]);
+
+        let config = RemoteConfig::default()
+            .with_bearer_token("portal-token")
+            .with_block_size(256)
+            .with_read_ahead(0);
+        let mut reader = IndexedFastXReader::from_url_with_config(
+            server.url_for("/ref.fa.gz"),
+            server.url_for("/ref.fa.gz.fai"),
+            server.url_for("/ref.fa.gz.gzi"),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(reader.fetch_range("chr1", 1234, 2345).unwrap(), &seq[1234..2345]);
+        let range_requests = || {
+            let requests = server.requests();
+            requests
+                .iter()
+                .filter(|r| r.starts_with("GET /ref.fa.gz "))
+                .filter(|r| r.to_ascii_lowercase().contains("range:"))
+                .count()
+        };
+        // The compressed span of the region is fetched with a single range request
+        assert_eq!(range_requests(), 1);
+        assert_eq!(reader.fetch("chr1").unwrap().seq(), seq);
+        assert_eq!(range_requests(), 2);
+
+        let requests = server.requests();
+        assert!(requests.len() >= 4);
+        assert!(requests
+            .iter()
+            .all(|r| r.to_ascii_lowercase().contains("authorization: bearer portal-token")));
+
+        // Missing index files are reported as such
+        let err = IndexedFastXReader::from_url_with_config(
+            server.url_for("/ref.fa.gz"),
+            server.url_for("/missing.fai"),
+            server.url_for("/ref.fa.gz.gzi"),
+            &config,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_from_url_schemes()
+    {
+        use crate::remote::RemoteConfig;
+        use crate::s3::{S3Config, S3Credentials};
+        use crate::test_server::StubServer;
+
+        let seq: Vec<u8> = (0..3000u32).map(|i| b"ACGT"[(i * 5 % 4) as usize]).collect();
+        let reference = TestReference::new(&[("chr1", &seq)], 1000);
+
+        // Local files through file:// URLs
+        let dir = reference.write_to_temp_dir("fastx-schemes");
+        let base = format!("file://{}", dir.display());
+        let mut reader = IndexedFastXReader::from_url(
+            format!("{}/ref.fa.gz", base),
+            format!("{}/ref.fa.gz.fai", base),
+            format!("{}/ref.fa.gz.gzi", base),
+        )
+        .unwrap();
+        assert_eq!(reader.fetch_range("chr1", 500, 1500).unwrap(), &seq[500..1500]);
+        let reader = IndexedFastXReader::from_url_auto(format!("{}/ref.fa.gz", base)).unwrap();
+        assert_eq!(reader.fai_index.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // S3 objects on an S3-compatible server, with signed requests
+        let server = StubServer::with_files(vec![
+            ("/genomes/ref.fa.gz", reference.data),
+            ("/genomes/ref.fa.gz.fai", reference.fai.into_bytes()),
+            ("/genomes/ref.fa.gz.gzi", reference.gzi),
+        ]);
+        let s3 = S3Config::default()
+            .with_endpoint(server.url_for(""))
+            .with_credentials(S3Credentials::new("minio", "minio-secret"));
+        let config = RemoteConfig::default().with_s3(s3);
+        let mut reader =
+            IndexedFastXReader::from_url_auto_with_config("s3://genomes/ref.fa.gz", &config)
+                .unwrap();
+        assert_eq!(reader.fetch_range("chr1", 2000, 2100).unwrap(), &seq[2000..2100]);
+        let requests = server.requests();
+        assert!(requests.iter().all(|r| {
+            let r = r.to_ascii_lowercase();
+            r.contains("authorization: aws4-hmac-sha256 credential=minio/")
+                && r.contains("x-amz-date: ")
+        }));
+
+        let err = IndexedFastXReader::from_url("ftp://host/ref.fa.gz", "a.fai", "a.gzi")
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_index_url_candidates()
+    {
+        assert_eq!(
+            index_url_candidates("http://host/dir/ref.fa.gz?sig=abc", "fai"),
+            vec!["http://host/dir/ref.fa.gz.fai?sig=abc", "http://host/dir/ref.fa.fai?sig=abc"]
+        );
+        assert_eq!(index_url_candidates("http://host/ref", "gzi"), vec!["http://host/ref.gzi"]);
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_from_url_auto()
+    {
+        use crate::test_server::StubServer;
+
+        let seq: Vec<u8> = (0..3000u32).map(|i| b"ACGT"[(i * 7 % 4) as usize]).collect();
+        let TestReference { fai, data, gzi, .. } = TestReference::new(&[("chr1", &seq)], 1000);
+        let fai = fai.into_bytes();
+        let server = StubServer::with_files(vec![
+            ("/ref.fa.gz", data.clone()),
+            ("/ref.fa.fai", fai.clone()),
+            ("/ref.fa.gz.gzi", gzi.clone()),
+            ("/other.fa.gz", data),
+            ("/idx/other.fai", fai),
+        ]);
+
+        // The FAI is found under the stem name, the GZI under the full name
+        let mut reader = IndexedFastXReader::from_url_auto(server.url_for("/ref.fa.gz")).unwrap();
+        assert_eq!(reader.fetch_range("chr1", 100, 200).unwrap(), &seq[100..200]);
+
+        // An explicit ##idx## index is used as given
+        let url = format!(
+            "{}##idx##{}",
+            server.url_for("/other.fa.gz"),
+            server.url_for("/idx/other.fai")
+        );
+        let err = IndexedFastXReader::from_url_auto(url).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let message = err.to_string();
+        assert!(message.starts_with("GZI index not found"));
+        assert!(message.contains(&server.url_for("/other.fa.gz.gzi")));
+        assert!(message.contains(&server.url_for("/other.fa.gzi")));
+        assert!(!message.contains("fai"));
+    }
 }
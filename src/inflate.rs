@@ -0,0 +1,816 @@
+//! Minimal block-wise DEFLATE/gzip decoder with resumable state.
+//!
+//! flate2 cannot resume inflation at an arbitrary bit offset, which zran-style
+//! random access into ordinary gzip files requires. This decoder works one
+//! deflate block at a time, exposes the exact input bit position at every block
+//! boundary and can be restarted from such a position given the preceding 32 KiB
+//! of output.
+
+use flate2::Crc;
+use std::io::{self, Read};
+use std::sync::OnceLock;
+
+/// Size of the deflate back-reference window.
+pub(crate) const WINDOW_SIZE: usize = 32 * 1024;
+
+const INPUT_BUF_SIZE: usize = 64 * 1024;
+
+/// Longest Huffman code allowed by deflate.
+const MAX_CODE_BITS: u32 = 15;
+/// Code bits resolved by the first-level decoding table.
+const PRIMARY_BITS: u32 = 10;
+const PRIMARY_MASK: u32 = (1 << PRIMARY_BITS) - 1;
+/// Flag of first-level entries that point to a subtable.
+const SUBTABLE: u32 = 0x10;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] =
+    [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+fn invalid(msg: &str) -> io::Error
+{
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid deflate stream: {}", msg))
+}
+
+/// LSB-first bit reader that tracks its absolute bit position in the input.
+struct BitReader<R: Read>
+{
+    inner: R,
+    buf: Vec<u8>,
+    buf_pos: usize,
+    buf_len: usize,
+    bits: u64,
+    bit_count: u32,
+    /// Absolute input byte offset of `buf[buf_pos]`
+    byte_pos: u64,
+    eof: bool,
+}
+
+impl<R: Read> BitReader<R>
+{
+    fn new(inner: R, byte_pos: u64) -> Self
+    {
+        Self {
+            inner,
+            buf: vec![0u8; INPUT_BUF_SIZE],
+            buf_pos: 0,
+            buf_len: 0,
+            bits: 0,
+            bit_count: 0,
+            byte_pos,
+            eof: false,
+        }
+    }
+
+    /// Absolute position of the next unread bit.
+    fn bit_position(&self) -> u64
+    {
+        self.byte_pos * 8 - self.bit_count as u64
+    }
+
+    /// Read the next chunk of input into `buf`. Returns false at end of input.
+    fn refill(&mut self) -> io::Result<bool>
+    {
+        if self.eof
+        {
+            return Ok(false);
+        }
+        self.buf_len = loop
+        {
+            match self.inner.read(&mut self.buf)
+            {
+                Ok(n) => break n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        self.buf_pos = 0;
+        self.eof = self.buf_len == 0;
+        Ok(!self.eof)
+    }
+
+    /// Pull bytes into the bit buffer until it holds at least 57 bits or input ends.
+    fn fill(&mut self) -> io::Result<()>
+    {
+        while self.bit_count <= 56
+        {
+            if self.buf_pos == self.buf_len && !self.refill()?
+            {
+                return Ok(());
+            }
+            self.bits |= (self.buf[self.buf_pos] as u64) << self.bit_count;
+            self.buf_pos += 1;
+            self.byte_pos += 1;
+            self.bit_count += 8;
+        }
+        Ok(())
+    }
+
+    /// Peek at up to 32 bits; bits beyond the end of input read as zero.
+    fn peek(&mut self, n: u32) -> io::Result<u32>
+    {
+        if self.bit_count < n
+        {
+            self.fill()?;
+        }
+        Ok((self.bits & ((1u64 << n) - 1)) as u32)
+    }
+
+    fn consume(&mut self, n: u32) -> io::Result<()>
+    {
+        if n > self.bit_count
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Unexpected end of gzip stream",
+            ));
+        }
+        self.bits >>= n;
+        self.bit_count -= n;
+        Ok(())
+    }
+
+    fn read_bits(&mut self, n: u32) -> io::Result<u32>
+    {
+        if n == 0
+        {
+            return Ok(0);
+        }
+        let value = self.peek(n)?;
+        self.consume(n)?;
+        Ok(value)
+    }
+
+    fn into_inner(self) -> R
+    {
+        self.inner
+    }
+
+    fn align_to_byte(&mut self)
+    {
+        let drop = self.bit_count % 8;
+        self.bits >>= drop;
+        self.bit_count -= drop;
+    }
+
+    fn read_u8(&mut self) -> io::Result<u8>
+    {
+        Ok(self.read_bits(8)? as u8)
+    }
+
+    fn read_u16_le(&mut self) -> io::Result<u16>
+    {
+        Ok(self.read_bits(16)? as u16)
+    }
+
+    fn read_u32_le(&mut self) -> io::Result<u32>
+    {
+        self.read_bits(32)
+    }
+
+    /// Append `n` bytes of input to `out` (only valid when byte-aligned).
+    fn read_bytes(&mut self, mut n: usize, out: &mut Vec<u8>) -> io::Result<()>
+    {
+        out.reserve(n);
+        // Bytes already moved into the bit buffer come first
+        while n > 0 && self.bit_count >= 8
+        {
+            out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.bit_count -= 8;
+            n -= 1;
+        }
+        while n > 0
+        {
+            if self.buf_pos == self.buf_len && !self.refill()?
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Unexpected end of gzip stream",
+                ));
+            }
+            let take = n.min(self.buf_len - self.buf_pos);
+            out.extend_from_slice(&self.buf[self.buf_pos..self.buf_pos + take]);
+            self.buf_pos += take;
+            self.byte_pos += take as u64;
+            n -= take;
+        }
+        Ok(())
+    }
+
+    /// Check whether all input has been consumed (only valid when byte-aligned).
+    fn at_end(&mut self) -> io::Result<bool>
+    {
+        self.fill()?;
+        Ok(self.bit_count == 0)
+    }
+}
+
+/// A canonical Huffman decoding table indexed by bit-reversed codes.
+///
+/// Codes of up to [`PRIMARY_BITS`] bits are resolved by one lookup in the
+/// first-level table; longer codes continue in a subtable appended after it, so
+/// tables stay small even for 15-bit codes.
+struct Huffman
+{
+    /// First-level entries followed by the subtables. An entry holds the symbol
+    /// (or the subtable offset) in the upper 16 bits and the number of code bits
+    /// it resolves (or the subtable index bits) in the low 4 bits; 0 is invalid.
+    table: Vec<u32>,
+}
+
+impl Huffman
+{
+    fn new(lengths: &[u8]) -> io::Result<Self>
+    {
+        let mut count = [0u16; 16];
+        for &len in lengths
+        {
+            if len as u32 > MAX_CODE_BITS
+            {
+                return Err(invalid("Huffman code too long"));
+            }
+            count[len as usize] += 1;
+        }
+        count[0] = 0;
+
+        let mut next_code = [0u32; 16];
+        let mut code = 0u32;
+        for bits in 1..16
+        {
+            code = (code + count[bits - 1] as u32) << 1;
+            next_code[bits] = code;
+        }
+
+        // Bit-reversed code of every symbol, as the bit reader delivers them
+        let mut codes = vec![0u32; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate()
+        {
+            if len == 0
+            {
+                continue;
+            }
+            let code = next_code[len as usize];
+            next_code[len as usize] += 1;
+            if code >= (1 << len)
+            {
+                return Err(invalid("over-subscribed Huffman code"));
+            }
+            codes[symbol] = code.reverse_bits() >> (32 - len as u32);
+        }
+
+        // Size each subtable for the longest code sharing its first-level prefix
+        let mut sub_bits = vec![0u32; 1 << PRIMARY_BITS];
+        for (&code, &len) in codes.iter().zip(lengths)
+        {
+            let len = len as u32;
+            if len > PRIMARY_BITS
+            {
+                let prefix = (code & PRIMARY_MASK) as usize;
+                sub_bits[prefix] = sub_bits[prefix].max(len - PRIMARY_BITS);
+            }
+        }
+        let mut table = vec![0u32; 1 << PRIMARY_BITS];
+        for (prefix, &bits) in sub_bits.iter().enumerate()
+        {
+            if bits > 0
+            {
+                table[prefix] = ((table.len() as u32) << 16) | SUBTABLE | bits;
+                table.resize(table.len() + (1 << bits), 0);
+            }
+        }
+
+        for (symbol, (&code, &len)) in codes.iter().zip(lengths).enumerate()
+        {
+            let len = len as u32;
+            if len == 0
+            {
+                continue;
+            }
+            let (offset, index, used, size) = if len <= PRIMARY_BITS
+            {
+                (0, code, len, 1 << PRIMARY_BITS)
+            }
+            else
+            {
+                let link = table[(code & PRIMARY_MASK) as usize];
+                if link & SUBTABLE == 0
+                {
+                    return Err(invalid("over-subscribed Huffman code"));
+                }
+                let size = 1 << (link & 0xf);
+                ((link >> 16) as usize, code >> PRIMARY_BITS, len - PRIMARY_BITS, size)
+            };
+            let entry = ((symbol as u32) << 16) | used;
+            let mut i = index as usize;
+            while i < size
+            {
+                // A short code covering a subtable link means the code is over-subscribed
+                if offset == 0 && table[i] & SUBTABLE != 0
+                {
+                    return Err(invalid("over-subscribed Huffman code"));
+                }
+                table[offset + i] = entry;
+                i += 1 << used;
+            }
+        }
+        Ok(Self { table })
+    }
+
+    fn decode<R: Read>(&self, bits: &mut BitReader<R>) -> io::Result<u16>
+    {
+        let code = bits.peek(MAX_CODE_BITS)?;
+        let mut entry = self.table[(code & PRIMARY_MASK) as usize];
+        let mut used = 0;
+        if entry & SUBTABLE != 0
+        {
+            let index = (code >> PRIMARY_BITS) & ((1 << (entry & 0xf)) - 1);
+            entry = self.table[(entry >> 16) as usize + index as usize];
+            used = PRIMARY_BITS;
+        }
+        let len = entry & 0xf;
+        if len == 0
+        {
+            return Err(invalid("invalid Huffman code"));
+        }
+        bits.consume(used + len)?;
+        Ok((entry >> 16) as u16)
+    }
+}
+
+/// Literal/length and distance tables of fixed-Huffman blocks, built once.
+fn fixed_tables() -> &'static (Huffman, Huffman)
+{
+    static FIXED: OnceLock<(Huffman, Huffman)> = OnceLock::new();
+    FIXED.get_or_init(|| {
+        let mut lengths = [0u8; 288];
+        lengths[..144].fill(8);
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        lengths[280..].fill(8);
+        let litlen = Huffman::new(&lengths).expect("fixed literal/length code is valid");
+        let dist = Huffman::new(&[5u8; 30]).expect("fixed distance code is valid");
+        (litlen, dist)
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State
+{
+    /// Expecting a gzip member header
+    Header,
+    /// At the start of a deflate block
+    Block,
+    /// After the final block of a member, expecting the trailer
+    Trailer,
+    /// No more members
+    Done,
+}
+
+/// Block-wise gzip decoder.
+///
+/// Each call to [`step`](Inflater::step) processes one unit of input (a member
+/// header, a deflate block or a member trailer) and appends any output to a
+/// history buffer that must hold at least the previous 32 KiB of output.
+pub(crate) struct Inflater<R: Read>
+{
+    bits: BitReader<R>,
+    state: State,
+    /// CRC32 of the current member's output so far
+    crc: Crc,
+    /// Length of the current member's output so far, modulo 2^32
+    size: u32,
+    /// Whether the member was decoded from its header, so its trailer can be checked
+    verify: bool,
+}
+
+impl<R: Read> Inflater<R>
+{
+    /// Start decoding a gzip stream at its beginning.
+    pub(crate) fn new(inner: R) -> Self
+    {
+        Self {
+            bits: BitReader::new(inner, 0),
+            state: State::Header,
+            crc: Crc::new(),
+            size: 0,
+            verify: false,
+        }
+    }
+
+    /// Resume decoding at the start of a deflate block.
+    ///
+    /// `inner` must be positioned at byte `bit_position / 8` of the input.
+    pub(crate) fn resume(inner: R, bit_position: u64) -> io::Result<Self>
+    {
+        let mut bits = BitReader::new(inner, bit_position / 8);
+        bits.read_bits((bit_position % 8) as u32)?;
+        Ok(Self {
+            bits,
+            state: State::Block,
+            crc: Crc::new(),
+            size: 0,
+            verify: false,
+        })
+    }
+
+    /// Return the underlying reader. Buffered read-ahead is discarded.
+    pub(crate) fn into_inner(self) -> R
+    {
+        self.bits.into_inner()
+    }
+
+    /// Whether the decoder is at a deflate block boundary (a valid resume point).
+    pub(crate) fn at_block_start(&self) -> bool
+    {
+        self.state == State::Block
+    }
+
+    /// Absolute input bit position of the decoder.
+    pub(crate) fn bit_position(&self) -> u64
+    {
+        self.bits.bit_position()
+    }
+
+    /// Process the next unit of input, appending output to `history`.
+    ///
+    /// Returns false once the end of the stream has been reached.
+    pub(crate) fn step(&mut self, history: &mut Vec<u8>) -> io::Result<bool>
+    {
+        match self.state
+        {
+            State::Header =>
+            {
+                self.read_header()?;
+                self.state = State::Block;
+                self.crc.reset();
+                self.size = 0;
+                self.verify = true;
+            }
+            State::Block =>
+            {
+                let last = self.bits.read_bits(1)? == 1;
+                let before = history.len();
+                match self.bits.read_bits(2)?
+                {
+                    0 => self.stored_block(history)?,
+                    1 =>
+                    {
+                        let (litlen, dist) = fixed_tables();
+                        self.codes(history, litlen, dist)?
+                    }
+                    2 => self.dynamic_block(history)?,
+                    _ => return Err(invalid("reserved block type")),
+                }
+                if self.verify
+                {
+                    self.crc.update(&history[before..]);
+                    self.size = self.size.wrapping_add((history.len() - before) as u32);
+                }
+                if last
+                {
+                    self.state = State::Trailer;
+                }
+            }
+            State::Trailer =>
+            {
+                self.bits.align_to_byte();
+                let crc = self.bits.read_u32_le()?;
+                let size = self.bits.read_u32_le()?;
+                // CRC32 and ISIZE cannot be checked when decoding was resumed mid-member
+                if self.verify && crc != self.crc.sum()
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "gzip CRC32 mismatch (expected {:08x}, got {:08x})",
+                            crc,
+                            self.crc.sum()
+                        ),
+                    ));
+                }
+                if self.verify && size != self.size
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("gzip size mismatch (expected {}, got {})", size, self.size),
+                    ));
+                }
+                self.state = if self.bits.at_end()? || self.bits.peek(16)? != 0x8b1f
+                {
+                    // Trailing garbage after the last member is ignored, like gzip does
+                    State::Done
+                }
+                else
+                {
+                    State::Header
+                };
+            }
+            State::Done => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn read_header(&mut self) -> io::Result<()>
+    {
+        let bits = &mut self.bits;
+        if bits.read_u8()? != 0x1f || bits.read_u8()? != 0x8b
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid gzip magic number"));
+        }
+        if bits.read_u8()? != 8
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not deflate compression"));
+        }
+        let flags = bits.read_u8()?;
+        // MTIME, XFL, OS
+        for _ in 0..6
+        {
+            bits.read_u8()?;
+        }
+        if flags & 4 != 0
+        {
+            let xlen = bits.read_u16_le()?;
+            for _ in 0..xlen
+            {
+                bits.read_u8()?;
+            }
+        }
+        for flag in [8, 16]
+        {
+            // FNAME, FCOMMENT: zero-terminated strings
+            if flags & flag != 0
+            {
+                while bits.read_u8()? != 0
+                {}
+            }
+        }
+        if flags & 2 != 0
+        {
+            bits.read_u16_le()?;
+        }
+        Ok(())
+    }
+
+    fn stored_block(&mut self, history: &mut Vec<u8>) -> io::Result<()>
+    {
+        self.bits.align_to_byte();
+        let len = self.bits.read_u16_le()?;
+        let nlen = self.bits.read_u16_le()?;
+        if len != !nlen
+        {
+            return Err(invalid("stored block length mismatch"));
+        }
+        self.bits.read_bytes(len as usize, history)
+    }
+
+    fn dynamic_block(&mut self, history: &mut Vec<u8>) -> io::Result<()>
+    {
+        let hlit = self.bits.read_bits(5)? as usize + 257;
+        let hdist = self.bits.read_bits(5)? as usize + 1;
+        let hclen = self.bits.read_bits(4)? as usize + 4;
+
+        let mut cl_lengths = [0u8; 19];
+        for &i in &CODE_LENGTH_ORDER[..hclen]
+        {
+            cl_lengths[i] = self.bits.read_bits(3)? as u8;
+        }
+        let cl = Huffman::new(&cl_lengths)?;
+
+        let mut lengths = vec![0u8; hlit + hdist];
+        let mut i = 0;
+        while i < lengths.len()
+        {
+            let symbol = cl.decode(&mut self.bits)?;
+            let (value, repeat) = match symbol
+            {
+                0..=15 => (symbol as u8, 1),
+                16 =>
+                {
+                    if i == 0
+                    {
+                        return Err(invalid("repeat without previous length"));
+                    }
+                    (lengths[i - 1], 3 + self.bits.read_bits(2)? as usize)
+                }
+                17 => (0, 3 + self.bits.read_bits(3)? as usize),
+                18 => (0, 11 + self.bits.read_bits(7)? as usize),
+                _ => return Err(invalid("invalid code length symbol")),
+            };
+            if i + repeat > lengths.len()
+            {
+                return Err(invalid("code lengths overflow"));
+            }
+            lengths[i..i + repeat].fill(value);
+            i += repeat;
+        }
+        if lengths[256] == 0
+        {
+            return Err(invalid("missing end-of-block code"));
+        }
+
+        let litlen = Huffman::new(&lengths[..hlit])?;
+        let dist = Huffman::new(&lengths[hlit..])?;
+        self.codes(history, &litlen, &dist)
+    }
+
+    fn codes(&mut self, history: &mut Vec<u8>, litlen: &Huffman, dist: &Huffman)
+        -> io::Result<()>
+    {
+        loop
+        {
+            let symbol = litlen.decode(&mut self.bits)?;
+            if symbol < 256
+            {
+                history.push(symbol as u8);
+                continue;
+            }
+            if symbol == 256
+            {
+                return Ok(());
+            }
+
+            let idx = (symbol - 257) as usize;
+            if idx >= LENGTH_BASE.len()
+            {
+                return Err(invalid("invalid length symbol"));
+            }
+            let length = LENGTH_BASE[idx] as usize
+                + self.bits.read_bits(LENGTH_EXTRA[idx] as u32)? as usize;
+
+            let dsym = dist.decode(&mut self.bits)? as usize;
+            if dsym >= DIST_BASE.len()
+            {
+                return Err(invalid("invalid distance symbol"));
+            }
+            let distance =
+                DIST_BASE[dsym] as usize + self.bits.read_bits(DIST_EXTRA[dsym] as u32)? as usize;
+            if distance > history.len()
+            {
+                return Err(invalid("distance too far back"));
+            }
+
+            let start = history.len() - distance;
+            if distance >= length
+            {
+                history.extend_from_within(start..start + length);
+            }
+            else
+            {
+                for i in 0..length
+                {
+                    let b = history[start + i];
+                    history.push(b);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn gzip(data: &[u8], level: u32) -> Vec<u8>
+    {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn inflate(data: &[u8]) -> io::Result<Vec<u8>>
+    {
+        let mut inflater = Inflater::new(data);
+        let mut out = Vec::new();
+        while inflater.step(&mut out)?
+        {}
+        Ok(out)
+    }
+
+    fn inflate_all(data: &[u8]) -> Vec<u8>
+    {
+        inflate(data).unwrap()
+    }
+
+    /// Type of the first deflate block of a gzip member with a 10-byte header.
+    fn first_block_type(gz: &[u8]) -> u8
+    {
+        (gz[10] >> 1) & 3
+    }
+
+    fn test_data() -> Vec<u8>
+    {
+        (0..200_000u32).map(|i| b"ACGTN\nacgt"[((i * 7 + i / 13) % 10) as usize]).collect()
+    }
+
+    #[test]
+    fn test_block_types()
+    {
+        let data = test_data();
+
+        let stored = gzip(&data, 0);
+        assert_eq!(first_block_type(&stored), 0);
+        assert_eq!(inflate_all(&stored), data);
+
+        let fixed = gzip(b"GATTACA", 6);
+        assert_eq!(first_block_type(&fixed), 1);
+        assert_eq!(inflate_all(&fixed), b"GATTACA");
+
+        for level in [1, 6, 9]
+        {
+            let dynamic = gzip(&data, level);
+            assert_eq!(first_block_type(&dynamic), 2);
+            assert_eq!(inflate_all(&dynamic), data);
+        }
+    }
+
+    #[test]
+    fn test_long_codes()
+    {
+        // Skewed symbol frequencies give codes longer than the first-level table
+        let mut state = 7u32;
+        let data: Vec<u8> = (0..300_000u32)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                ((state >> 16) | 1 << 15).trailing_zeros() as u8 * 13
+            })
+            .collect();
+        assert_eq!(inflate_all(&gzip(&data, 9)), data);
+    }
+
+    #[test]
+    fn test_multiple_members()
+    {
+        let mut two = gzip(b"hello ", 6);
+        two.extend(gzip(b"", 6));
+        two.extend(gzip(&test_data(), 0));
+        let mut expected = b"hello ".to_vec();
+        expected.extend(test_data());
+        assert_eq!(inflate_all(&two), expected);
+
+        // Trailing garbage after the last member is ignored
+        let mut trailing = gzip(b"world", 6);
+        trailing.extend_from_slice(b"\0\0\0");
+        assert_eq!(inflate_all(&trailing), b"world");
+    }
+
+    #[test]
+    fn test_corrupted_input()
+    {
+        let gz = gzip(&test_data(), 6);
+
+        let mut bad_crc = gz.clone();
+        let n = bad_crc.len();
+        bad_crc[n - 8] ^= 1;
+        let err = inflate(&bad_crc).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("CRC32"));
+
+        let mut bad_size = gz.clone();
+        bad_size[n - 1] ^= 1;
+        assert!(inflate(&bad_size).unwrap_err().to_string().contains("size"));
+
+        let truncated = inflate(&gz[..gz.len() / 2]).unwrap_err();
+        assert_eq!(truncated.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut bad_magic = gz.clone();
+        bad_magic[1] = 0;
+        assert_eq!(inflate(&bad_magic).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Reserved block type 3
+        let mut reserved = gzip(b"GATTACA", 6);
+        reserved[10] |= 0b110;
+        assert!(inflate(&reserved).unwrap_err().to_string().contains("reserved"));
+
+        // Stored block whose length complement does not match
+        let mut stored = gzip(b"GATTACA", 0);
+        stored[13] ^= 1;
+        assert!(inflate(&stored).unwrap_err().to_string().contains("length mismatch"));
+
+        // Damage inside compressed data never panics
+        for i in (10..n - 8).step_by(97)
+        {
+            let mut damaged = gz.clone();
+            damaged[i] ^= 0x5a;
+            let _ = inflate(&damaged);
+        }
+    }
+}
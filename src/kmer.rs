@@ -0,0 +1,447 @@
+//! 2-bit packed k-mer and minimizer iteration.
+//!
+//! K-mers are packed two bits per base (`A`=0, `C`=1, `G`=2, `T`=3) with the first
+//! base in the most significant position. Iteration runs directly over raw record
+//! data, skipping line terminators, so multi-line FASTA does not need to be copied
+//! with `seq()` first. Any base other than `ACGT` (case-insensitive) resets the
+//! k-mer, so no k-mer spans an `N`.
+//!
+//! K-mers up to 32 bases fit in a `u64`, up to 64 bases in a `u128`.
+//!
+//! # Example
+//!
+//! ```
+//! use fastx::kmer::{KmerIter, Kmers};
+//! use fastx::FastX::{FastARecord, FastXRead};
+//! use std::io::BufReader;
+//!
+//! let mut record = FastARecord::default();
+//! record.read(&mut BufReader::new(&b">a\nACG\nTNAC\n"[..])).unwrap();
+//!
+//! let kmers: Vec<_> = record.kmers(3).map(|k| (k.position, k.forward)).collect();
+//! assert_eq!(kmers, vec![(0, 0b000110), (1, 0b011011)]);
+//!
+//! // Larger k-mers use u128
+//! let long: Vec<_> = KmerIter::<u128>::new(&[b'A'; 40], 40).collect();
+//! assert_eq!(long.len(), 1);
+//! ```
+
+use crate::FastX::FastXRead;
+use std::collections::VecDeque;
+
+/// Lookup table from ASCII to 2-bit code, 4 for non-ACGT bytes.
+const ENCODE: [u8; 256] = {
+    let mut table = [4u8; 256];
+    table[b'A' as usize] = 0;
+    table[b'a' as usize] = 0;
+    table[b'C' as usize] = 1;
+    table[b'c' as usize] = 1;
+    table[b'G' as usize] = 2;
+    table[b'g' as usize] = 2;
+    table[b'T' as usize] = 3;
+    table[b't' as usize] = 3;
+    table
+};
+
+/// Integer types usable as packed k-mer storage (`u64` and `u128`).
+pub trait KmerWord: Copy + Ord + std::fmt::Debug
+{
+    /// Maximum k-mer length that fits into this type.
+    const MAX_K: usize;
+
+    /// The all-zero k-mer.
+    fn zero() -> Self;
+
+    /// Shift in a base at the least significant end, keeping the lowest `2k` bits.
+    fn push(self, code: u8, k: usize) -> Self;
+
+    /// Shift in the complement of a base at the most significant end.
+    fn push_rev(self, code: u8, k: usize) -> Self;
+
+    /// The 2-bit code at index `i`, counted from the most significant base.
+    fn code_at(self, i: usize, k: usize) -> u8;
+}
+
+macro_rules! impl_kmer_word {
+    ($t:ty) => {
+        impl KmerWord for $t
+        {
+            const MAX_K: usize = <$t>::BITS as usize / 2;
+
+            fn zero() -> Self
+            {
+                0
+            }
+
+            fn push(self, code: u8, k: usize) -> Self
+            {
+                let shifted = (self << 2) | code as $t;
+                if k == Self::MAX_K
+                {
+                    shifted
+                }
+                else
+                {
+                    shifted & ((1 << (2 * k)) - 1)
+                }
+            }
+
+            fn push_rev(self, code: u8, k: usize) -> Self
+            {
+                (self >> 2) | (((3 - code) as $t) << (2 * (k - 1)))
+            }
+
+            fn code_at(self, i: usize, k: usize) -> u8
+            {
+                ((self >> (2 * (k - 1 - i))) & 3) as u8
+            }
+        }
+    };
+}
+
+impl_kmer_word!(u64);
+impl_kmer_word!(u128);
+
+/// A k-mer occurrence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Kmer<W>
+{
+    /// 0-based base position of the first base (excluding line terminators)
+    pub position: usize,
+    /// The k-mer as read on the forward strand
+    pub forward: W,
+    /// The smaller of the forward k-mer and its reverse complement
+    pub canonical: W,
+}
+
+/// Iterator over the k-mers of a sequence.
+///
+/// Line terminators are skipped and non-`ACGT` bases restart the k-mer.
+pub struct KmerIter<'a, W: KmerWord = u64>
+{
+    seq: &'a [u8],
+    k: usize,
+    /// Index into `seq`
+    idx: usize,
+    /// Number of bases seen (excluding line terminators)
+    base_pos: usize,
+    /// Number of valid bases in the current run
+    valid: usize,
+    forward: W,
+    reverse: W,
+}
+
+impl<'a, W: KmerWord> KmerIter<'a, W>
+{
+    /// Create a k-mer iterator over raw sequence data.
+    ///
+    /// # Panics
+    ///
+    /// If `k` is 0 or larger than `W::MAX_K` (32 for `u64`, 64 for `u128`).
+    pub fn new(seq: &'a [u8], k: usize) -> Self
+    {
+        assert!(
+            k > 0 && k <= W::MAX_K,
+            "k must be between 1 and {}, got {}",
+            W::MAX_K,
+            k
+        );
+        Self {
+            seq,
+            k,
+            idx: 0,
+            base_pos: 0,
+            valid: 0,
+            forward: W::zero(),
+            reverse: W::zero(),
+        }
+    }
+
+    /// The k-mer length.
+    pub fn k(&self) -> usize
+    {
+        self.k
+    }
+}
+
+impl<W: KmerWord> Iterator for KmerIter<'_, W>
+{
+    type Item = Kmer<W>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        while self.idx < self.seq.len()
+        {
+            let b = self.seq[self.idx];
+            self.idx += 1;
+            if b == b'\n' || b == b'\r'
+            {
+                continue;
+            }
+            self.base_pos += 1;
+
+            let code = ENCODE[b as usize];
+            if code > 3
+            {
+                self.valid = 0;
+                continue;
+            }
+
+            self.forward = self.forward.push(code, self.k);
+            self.reverse = self.reverse.push_rev(code, self.k);
+            self.valid += 1;
+
+            if self.valid >= self.k
+            {
+                return Some(Kmer {
+                    position: self.base_pos - self.k,
+                    forward: self.forward,
+                    canonical: self.forward.min(self.reverse),
+                });
+            }
+        }
+        None
+    }
+}
+
+/// A (w,k) minimizer occurrence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Minimizer
+{
+    /// 0-based base position of the minimizer k-mer
+    pub position: usize,
+    /// The canonical k-mer
+    pub kmer: u64,
+    /// The hash value the minimizer was selected by
+    pub hash: u64,
+}
+
+/// Iterator over the (w,k) minimizers of a sequence.
+///
+/// For every window of `w` consecutive k-mers the canonical k-mer with the
+/// smallest hash is selected (the leftmost on ties). Each selected k-mer is
+/// yielded once, even if it is the minimizer of several overlapping windows.
+/// Windows never span a non-`ACGT` base.
+pub struct MinimizerIter<'a>
+{
+    kmers: KmerIter<'a, u64>,
+    w: usize,
+    /// Monotone queue of (position, hash, kmer) candidates
+    queue: VecDeque<(usize, u64, u64)>,
+    /// Number of contiguous k-mers in the current run
+    run: usize,
+    /// Position of the previous k-mer
+    last_kmer: Option<usize>,
+    /// Position of the last yielded minimizer
+    last_emitted: Option<usize>,
+}
+
+impl<'a> MinimizerIter<'a>
+{
+    /// Create a minimizer iterator over raw sequence data.
+    ///
+    /// # Panics
+    ///
+    /// If `w` is 0, or `k` is 0 or larger than 32.
+    pub fn new(seq: &'a [u8], w: usize, k: usize) -> Self
+    {
+        assert!(w > 0, "window size w must be positive");
+        Self {
+            kmers: KmerIter::new(seq, k),
+            w,
+            queue: VecDeque::with_capacity(w),
+            run: 0,
+            last_kmer: None,
+            last_emitted: None,
+        }
+    }
+}
+
+impl Iterator for MinimizerIter<'_>
+{
+    type Item = Minimizer;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        for kmer in self.kmers.by_ref()
+        {
+            if self.last_kmer.map(|p| p + 1) != Some(kmer.position)
+            {
+                self.queue.clear();
+                self.run = 0;
+            }
+            self.last_kmer = Some(kmer.position);
+            self.run += 1;
+
+            let hash = hash64(kmer.canonical);
+            while self.queue.back().is_some_and(|&(_, h, _)| h > hash)
+            {
+                self.queue.pop_back();
+            }
+            self.queue.push_back((kmer.position, hash, kmer.canonical));
+            while self.queue.front().is_some_and(|&(p, _, _)| p + self.w <= kmer.position)
+            {
+                self.queue.pop_front();
+            }
+
+            if self.run >= self.w
+            {
+                let (position, hash, kmer) = self.queue[0];
+                if self.last_emitted != Some(position)
+                {
+                    self.last_emitted = Some(position);
+                    return Some(Minimizer {
+                        position,
+                        kmer,
+                        hash,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Invertible 64-bit integer hash used to order minimizers.
+///
+/// Avoids the bias of lexicographic ordering towards poly-A k-mers.
+pub fn hash64(key: u64) -> u64
+{
+    let mut key = key;
+    key = (!key).wrapping_add(key << 21);
+    key ^= key >> 24;
+    key = key.wrapping_add(key << 3).wrapping_add(key << 8);
+    key ^= key >> 14;
+    key = key.wrapping_add(key << 2).wrapping_add(key << 4);
+    key ^= key >> 28;
+    key.wrapping_add(key << 31)
+}
+
+/// Decode a packed k-mer back into `ACGT` bytes.
+pub fn decode<W: KmerWord>(kmer: W, k: usize) -> Vec<u8>
+{
+    (0..k).map(|i| b"ACGT"[kmer.code_at(i, k) as usize]).collect()
+}
+
+/// K-mer and minimizer iteration over sequence records.
+///
+/// Implemented for every [`FastXRead`] type, so it works on records from the
+/// `for_each` functions as well as on records yielded by the iterators.
+pub trait Kmers
+{
+    /// Iterate over the `u64` k-mers of the sequence (`k` ≤ 32).
+    fn kmers(&self, k: usize) -> KmerIter<'_, u64>;
+
+    /// Iterate over the `u128` k-mers of the sequence (`k` ≤ 64).
+    fn kmers_u128(&self, k: usize) -> KmerIter<'_, u128>;
+
+    /// Iterate over the (w,k) minimizers of the sequence (`k` ≤ 32).
+    fn minimizers(&self, w: usize, k: usize) -> MinimizerIter<'_>;
+}
+
+impl<T: FastXRead + ?Sized> Kmers for T
+{
+    fn kmers(&self, k: usize) -> KmerIter<'_, u64>
+    {
+        KmerIter::new(self.seq_raw(), k)
+    }
+
+    fn kmers_u128(&self, k: usize) -> KmerIter<'_, u128>
+    {
+        KmerIter::new(self.seq_raw(), k)
+    }
+
+    fn minimizers(&self, w: usize, k: usize) -> MinimizerIter<'_>
+    {
+        MinimizerIter::new(self.seq_raw(), w, k)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn revcomp(seq: &[u8]) -> Vec<u8>
+    {
+        seq.iter()
+            .rev()
+            .map(|b| match b
+            {
+                b'A' => b'T',
+                b'C' => b'G',
+                b'G' => b'C',
+                _ => b'A',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_forward_and_canonical()
+    {
+        let seq = b"ACGTTGCAAGGC";
+        for k in 1..=seq.len()
+        {
+            let kmers: Vec<Kmer<u64>> = KmerIter::new(seq, k).collect();
+            assert_eq!(kmers.len(), seq.len() - k + 1);
+            for kmer in kmers
+            {
+                let fwd = &seq[kmer.position..kmer.position + k];
+                assert_eq!(decode(kmer.forward, k), fwd);
+                let canonical = std::cmp::min(fwd.to_vec(), revcomp(fwd));
+                assert_eq!(decode(kmer.canonical, k), canonical);
+            }
+        }
+    }
+
+    #[test]
+    fn test_skips_newlines_and_resets_on_n()
+    {
+        let kmers: Vec<_> = KmerIter::<u64>::new(b"AC\nGT\r\nNAC\nG", 3)
+            .map(|k| (k.position, decode(k.forward, 3)))
+            .collect();
+        assert_eq!(
+            kmers,
+            vec![
+                (0, b"ACG".to_vec()),
+                (1, b"CGT".to_vec()),
+                (5, b"ACG".to_vec())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_k()
+    {
+        let seq: Vec<u8> = b"ACGT".iter().cycle().take(70).copied().collect();
+        let kmers: Vec<_> = KmerIter::<u64>::new(&seq, 32).collect();
+        assert_eq!(decode(kmers[0].forward, 32), &seq[..32]);
+        let kmers: Vec<_> = KmerIter::<u128>::new(&seq, 64).collect();
+        assert_eq!(kmers.len(), 7);
+        assert_eq!(decode(kmers[6].forward, 64), &seq[6..70]);
+    }
+
+    #[test]
+    fn test_minimizers_match_naive()
+    {
+        let seq = b"ACGGTAGCTAGCTTTAGCGCGATCGACGNNACGTAGCTAGCATCGACTAGCTACGAT";
+        let (w, k) = (4, 5);
+        let mins: Vec<_> = MinimizerIter::new(seq, w, k).map(|m| m.position).collect();
+
+        let mut expected = Vec::new();
+        let kmers: Vec<Kmer<u64>> = KmerIter::new(seq, k).collect();
+        for window in kmers.windows(w)
+        {
+            if window[w - 1].position - window[0].position != w - 1
+            {
+                continue;
+            }
+            let best = window.iter().min_by_key(|k| hash64(k.canonical)).unwrap();
+            if expected.last() != Some(&best.position)
+            {
+                expected.push(best.position);
+            }
+        }
+        assert_eq!(mins, expected);
+    }
+}
@@ -16,6 +16,16 @@ License: Attribution-ShareAlike 4.0 International
 //! - Iterator-based API for easy processing
 //! - Manual read API for fine-grained control
 //! - Automatic format detection
+//! - Sequence alphabet validation and normalization
+//! - 2-bit packed k-mer and minimizer iteration
+//! - Protein translation with NCBI genetic codes and ORF finding
+//! - Random access to UCSC .2bit references
+//! - Random access to ordinary gzip files via a checkpoint index
+//! - Region queries on tabix/CSI-indexed BED, GFF and VCF files
+//! - Chromosome name aliases across UCSC, Ensembl and RefSeq naming
+//! - Sequence dictionaries (.dict) with MD5 and refget checksums
+//! - Memory-mapped reading of uncompressed files (`mmap` feature)
+//! - Async record streams and indexed fetches for tokio (`async` feature)
 //!
 //! # Format Overview
 //!
@@ -114,18 +124,45 @@ License: Attribution-ShareAlike 4.0 International
 //! println!("Region: {} bp", region.len());
 //! ```
 
+pub mod alphabet;
+pub mod kmer;
+pub mod translate;
+
 // Indexed random access modules
+pub mod alias;
 pub mod bgzf;
+pub mod cache;
+pub mod dict;
 pub mod fai;
 pub mod gzi;
 pub mod indexed;
-
+pub mod region;
+pub mod shared;
+pub mod source;
+pub mod tabix;
+pub mod twobit;
+pub mod zran;
+
+mod digest;
+mod inflate;
+#[cfg(all(test, feature = "url"))]
+mod test_server;
+
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+#[cfg(feature = "url")]
+pub mod disk_cache;
 #[cfg(feature = "url")]
 pub mod remote;
+#[cfg(feature = "url")]
+pub mod s3;
 
 #[allow(non_snake_case)]
 pub mod FastX
 {
+    use crate::alphabet::{Normalize, Normalized, Normalizer};
     use flate2::read::MultiGzDecoder;
     use std::ffi::OsStr;
     use std::io;
@@ -310,6 +347,35 @@ pub mod FastX
         }
     }
 
+    impl<R: BufRead, T: FastXRead + Default + Normalize> FastXIterator<R, T>
+    {
+        /// Apply a [`Normalizer`] to every record read by this iterator.
+        ///
+        /// Records failing the normalizer's validation are yielded as
+        /// `io::ErrorKind::InvalidData` errors.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use fastx::alphabet::{Alphabet, Normalizer};
+        /// use fastx::FastX::{fasta_iter, FastXRead};
+        /// use std::io::BufReader;
+        ///
+        /// let reader = BufReader::new(&b">a\nacgu\n"[..]);
+        /// let normalizer = Normalizer::new()
+        ///     .with_uppercase(true)
+        ///     .with_rna_to_dna(true)
+        ///     .with_validate(Some(Alphabet::Dna));
+        /// for result in fasta_iter(reader).normalized(normalizer) {
+        ///     assert_eq!(result.unwrap().seq(), b"ACGT");
+        /// }
+        /// ```
+        pub fn normalized(self, normalizer: Normalizer) -> Normalized<Self>
+        {
+            Normalized::new(self, normalizer)
+        }
+    }
+
     impl<R: BufRead, T: FastXRead + Default> Iterator for FastXIterator<R, T>
     {
         type Item = io::Result<T>;
@@ -491,6 +557,14 @@ pub mod FastX
         }
     }
 
+    impl Normalize for FastARecord
+    {
+        fn normalize(&mut self, normalizer: &Normalizer) -> io::Result<()>
+        {
+            Ok(normalizer.apply(&mut self.raw_seq)?)
+        }
+    }
+
     impl std::fmt::Display for FastQRecord
     {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
@@ -500,7 +574,7 @@ pub mod FastX
                 "@{}\n{}\n+\n{}",
                 self.name(),
                 String::from_utf8_lossy(&self.seq()),
-                String::from_utf8_lossy(&self.qual())
+                String::from_utf8_lossy(self.qual())
             )
         }
     }
@@ -620,7 +694,7 @@ pub mod FastX
     {
         fn comment(&self) -> &str
         {
-            if self.comment.len() > 0 {
+            if !self.comment.is_empty() {
                 &self.comment[1..]
             } else {
                 ""
@@ -633,6 +707,14 @@ pub mod FastX
         }
     }
 
+    impl Normalize for FastQRecord
+    {
+        fn normalize(&mut self, normalizer: &Normalizer) -> io::Result<()>
+        {
+            normalizer.apply_with_qual(&mut self.seq, &mut self.qual)
+        }
+    }
+
     fn rstrip_newline_string(s: &mut String)
     {
         while s.ends_with('\n') || s.ends_with('\r')
@@ -718,7 +800,8 @@ pub mod FastX
     ///
     /// This function opens a file and wraps it in a large (600MB) buffered reader
     /// for optimal performance. If the file has a `.gz` extension, it automatically
-    /// applies gzip decompression.
+    /// applies gzip decompression. Use [`reader_from_path_with_capacity`] for a
+    /// smaller buffer.
     ///
     /// # Arguments
     ///
@@ -741,19 +824,74 @@ pub mod FastX
     /// }
     /// ```
     pub fn reader_from_path(path: &Path) -> io::Result<Box<dyn BufRead>>
+    {
+        reader_from_path_with_capacity(path, PER_THREAD_BUF_SIZE)
+    }
+
+    /// Create a buffered reader from a file path with a read buffer of `capacity` bytes.
+    ///
+    /// Like [`reader_from_path`], which uses a 600MB buffer. Smaller buffers save
+    /// memory when many files are open at once.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::FastX::{reader_from_path_with_capacity, fasta_iter};
+    /// use std::path::Path;
+    ///
+    /// let reader = reader_from_path_with_capacity(Path::new("sequences.fasta"), 1 << 20).unwrap();
+    /// let count = fasta_iter(reader).count();
+    /// ```
+    pub fn reader_from_path_with_capacity(
+        path: &Path,
+        capacity: usize,
+    ) -> io::Result<Box<dyn BufRead>>
     {
         let file = File::open(path)?;
         let reader: Box<dyn BufRead> = match path.extension()
         {
             Some(extension) if extension == OsStr::new("gz") => Box::new(BufReader::with_capacity(
-                PER_THREAD_BUF_SIZE,
+                capacity,
                 MultiGzDecoder::new(BufReader::new(file)),
             )),
-            _ => Box::new(BufReader::with_capacity(PER_THREAD_BUF_SIZE, file)),
+            _ => Box::new(BufReader::with_capacity(capacity, file)),
         };
         Ok(reader)
     }
 
+    /// Create a reader from a file path that memory-maps uncompressed files.
+    ///
+    /// Plain files are read through a [`MmapReader`](crate::mmap::MmapReader)
+    /// instead of being copied through a read buffer. Gzip files and files that
+    /// cannot be mapped, such as pipes, are read like [`reader_from_path`] does.
+    /// Requires the `mmap` feature.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::FastX::{reader_from_path_mmap, fastq_iter};
+    /// use std::path::Path;
+    ///
+    /// let reader = reader_from_path_mmap(Path::new("reads.fastq")).unwrap();
+    /// let count = fastq_iter(reader).count();
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub fn reader_from_path_mmap(path: &Path) -> io::Result<Box<dyn BufRead>>
+    {
+        if path.extension() != Some(OsStr::new("gz"))
+        {
+            let file = File::open(path)?;
+            if file.metadata()?.is_file()
+            {
+                if let Ok(map) = crate::mmap::MmapReader::from_file(&file)
+                {
+                    return Ok(Box::new(map));
+                }
+            }
+        }
+        reader_from_path(path)
+    }
+
     /// Create a record reader with automatic format detection.
     ///
     /// This function peeks at the first byte of the input to determine whether
@@ -928,6 +1066,63 @@ pub mod FastX
         Ok(())
     }
 
+    /// Iterate over FASTA records, normalizing each sequence before calling `func`.
+    ///
+    /// Like [`fasta_for_each`], but applies a [`Normalizer`] to every record.
+    /// Validation failures are returned as `io::ErrorKind::InvalidData` errors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fastx::alphabet::{Alphabet, Normalizer};
+    /// use fastx::FastX::{fasta_for_each_normalized, FastXRead};
+    /// use std::io::BufReader;
+    ///
+    /// let reader = BufReader::new(&b">a\nACGT\n>b\nAC*T\n"[..]);
+    /// let normalizer = Normalizer::new().with_validate(Some(Alphabet::Dna));
+    /// let result = fasta_for_each_normalized(reader, &normalizer, |record| {
+    ///     println!("{}", record.id());
+    /// });
+    /// assert!(result.is_err());
+    /// ```
+    pub fn fasta_for_each_normalized<R: BufRead, F>(
+        mut reader: R,
+        normalizer: &Normalizer,
+        mut func: F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(&FastARecord),
+    {
+        let mut record = FastARecord::default();
+        while record.read(&mut reader)? > 0
+        {
+            record.normalize(normalizer)?;
+            func(&record);
+        }
+        Ok(())
+    }
+
+    /// Iterate over FASTQ records, normalizing each sequence before calling `func`.
+    ///
+    /// Like [`fastq_for_each`], but applies a [`Normalizer`] to every record.
+    /// Quality scores of stripped bases are removed along with them.
+    pub fn fastq_for_each_normalized<R: BufRead, F>(
+        mut reader: R,
+        normalizer: &Normalizer,
+        mut func: F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(&FastQRecord),
+    {
+        let mut record = FastQRecord::default();
+        while record.read(&mut reader)? > 0
+        {
+            record.normalize(normalizer)?;
+            func(&record);
+        }
+        Ok(())
+    }
+
     /// Iterate over sequence records with automatic format detection and buffer reuse.
     ///
     /// This function peeks at the first byte to determine if the file is FASTA or FASTQ,
@@ -984,12 +1179,7 @@ pub mod FastX
         loop
         {
             let (done, used) = {
-                let available = match r.fill_buf()
-                {
-                    Ok(n) => n,
-                    //Err(ref e) if e.is_interrupted() => continue,
-                    Err(e) => return Err(e),
-                };
+                let available = r.fill_buf()?;
                 match memchr::memchr(delim, available)
                 {
                     Some(i) =>
@@ -1143,4 +1333,25 @@ mod tests
         assert_eq!("c", records[2].name());
         assert_eq!(b"GCTA".to_vec(), records[2].seq());
     }
+
+    #[test]
+    fn fastq_normalized()
+    {
+        use super::alphabet::{Alphabet, Normalizer};
+        use super::FastX::{fastq_for_each_normalized, FastQRead};
+        let reader = BufReader::new(Cursor::new("@a\nac gu\n+\n12345\n"));
+        let normalizer = Normalizer::new()
+            .with_strip_whitespace(true)
+            .with_uppercase(true)
+            .with_rna_to_dna(true)
+            .with_validate(Some(Alphabet::Dna));
+        let mut seen = 0;
+        fastq_for_each_normalized(reader, &normalizer, |record| {
+            assert_eq!(b"ACGT".to_vec(), record.seq());
+            assert_eq!(&b"1245".to_vec(), record.qual());
+            seen += 1;
+        })
+        .unwrap();
+        assert_eq!(1, seen);
+    }
 }
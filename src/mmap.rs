@@ -0,0 +1,650 @@
+//! Memory-mapped reading of uncompressed FASTA/FASTQ files (`mmap` feature).
+//!
+//! [`MmapReader`] maps a whole file instead of copying it through a read buffer.
+//! It implements `BufRead + Seek`, so the usual parsers work on it, and it can
+//! hand out records that borrow straight from the map ([`FastARecordRef`],
+//! [`FastQRecordRef`]) without copying. [`MmapFastaReader`] uses the map and a
+//! `.fai` index to fetch regions of plain FASTA files in constant time.
+//!
+//! A mapped file must not be modified or truncated by other processes while it
+//! is mapped; reads of a truncated file fault (SIGBUS) instead of failing.
+
+use crate::fai::{FaiEntry, FaiIndex};
+use crate::indexed::{find_index_file, SequenceFetch};
+use crate::FastX::FastARecord;
+use memmap2::Mmap;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// A memory-mapped file, readable as a `BufRead + Seek` stream.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::mmap::MmapReader;
+/// use std::path::Path;
+///
+/// let reader = MmapReader::open(Path::new("reads.fastq")).unwrap();
+/// for record in reader.fastq_records()
+/// {
+///     let record = record.unwrap();
+///     println!("{}: {} bp", record.id(), record.seq_len());
+/// }
+/// ```
+pub struct MmapReader
+{
+    map: Mmap,
+    pos: usize,
+}
+
+impl MmapReader
+{
+    /// Map a file into memory.
+    pub fn open(path: &Path) -> io::Result<Self>
+    {
+        Self::from_file(&File::open(path)?)
+    }
+
+    /// Map an open file into memory.
+    pub fn from_file(file: &File) -> io::Result<Self>
+    {
+        // SAFETY: the map is read-only; changes to the file by other processes
+        // while it is mapped are documented as unsupported.
+        let map = unsafe { Mmap::map(file)? };
+        Ok(Self { map, pos: 0 })
+    }
+
+    /// Get the whole file.
+    pub fn as_bytes(&self) -> &[u8]
+    {
+        &self.map
+    }
+
+    /// Iterate over the FASTA records of the file without copying them.
+    pub fn fasta_records(&self) -> FastARecordRefs<'_>
+    {
+        fasta_records(&self.map)
+    }
+
+    /// Iterate over the FASTQ records of the file without copying them.
+    pub fn fastq_records(&self) -> FastQRecordRefs<'_>
+    {
+        fastq_records(&self.map)
+    }
+}
+
+impl Read for MmapReader
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl BufRead for MmapReader
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]>
+    {
+        Ok(self.map.get(self.pos..).unwrap_or_default())
+    }
+
+    fn consume(&mut self, amt: usize)
+    {
+        self.pos = (self.pos + amt).min(self.map.len());
+    }
+}
+
+impl Seek for MmapReader
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64>
+    {
+        let (base, offset) = match pos
+        {
+            SeekFrom::Start(n) => (n, 0),
+            SeekFrom::End(offset) => (self.map.len() as u64, offset),
+            SeekFrom::Current(offset) => (self.pos as u64, offset),
+        };
+        let target = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Seek before file start")
+        })?;
+        self.pos = target.min(self.map.len() as u64) as usize;
+        Ok(target)
+    }
+}
+
+/// Split a name into the ID (before the first space) and the description.
+fn split_name(name: &str) -> (&str, &str)
+{
+    match memchr::memchr(b' ', name.as_bytes())
+    {
+        None => (name, ""),
+        Some(i) => (&name[..i], &name[i + 1..]),
+    }
+}
+
+/// Strip trailing `\n` and `\r`.
+fn trim_end(mut bytes: &[u8]) -> &[u8]
+{
+    while let [rest @ .., b'\n' | b'\r'] = bytes
+    {
+        bytes = rest;
+    }
+    bytes
+}
+
+/// A line of `data` from `start`: its content without line ending, and the
+/// start of the next line.
+fn line_at(data: &[u8], start: usize) -> (&[u8], usize)
+{
+    match memchr::memchr(b'\n', &data[start..])
+    {
+        Some(i) => (trim_end(&data[start..start + i]), start + i + 1),
+        None => (trim_end(&data[start..]), data.len()),
+    }
+}
+
+/// Check that `bytes` is UTF-8; `what` names them in the error.
+fn utf8<'a>(bytes: &'a [u8], what: &str) -> io::Result<&'a str>
+{
+    std::str::from_utf8(bytes).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{} is not valid UTF-8", what))
+    })
+}
+
+/// A FASTA record borrowed from a byte slice.
+#[derive(Debug, Clone, Copy)]
+pub struct FastARecordRef<'a>
+{
+    name: &'a str,
+    raw_seq: &'a [u8],
+}
+
+impl<'a> FastARecordRef<'a>
+{
+    /// Get the full header line (without the leading `>`).
+    pub fn name(&self) -> &'a str
+    {
+        self.name
+    }
+
+    /// Get the sequence identifier (the part before the first space in the name).
+    pub fn id(&self) -> &'a str
+    {
+        split_name(self.name).0
+    }
+
+    /// Get the description (the part after the first space in the name).
+    pub fn desc(&self) -> &'a str
+    {
+        split_name(self.name).1
+    }
+
+    /// Get the raw sequence data including any newlines.
+    pub fn seq_raw(&self) -> &'a [u8]
+    {
+        self.raw_seq
+    }
+
+    /// Get the sequence data with newlines removed.
+    ///
+    /// Single-line sequences are borrowed; multi-line sequences are copied.
+    pub fn seq(&self) -> Cow<'a, [u8]>
+    {
+        if memchr::memchr(b'\n', self.raw_seq).is_none()
+        {
+            return Cow::Borrowed(self.raw_seq);
+        }
+        Cow::Owned(self.lines().concat())
+    }
+
+    /// Get the sequence length (excluding newlines).
+    pub fn seq_len(&self) -> usize
+    {
+        self.lines().iter().map(|line| line.len()).sum()
+    }
+
+    /// Get the sequence split into individual lines.
+    pub fn lines(&self) -> Vec<&'a [u8]>
+    {
+        self.raw_seq.split(|&b| b == b'\n').map(trim_end).collect()
+    }
+
+    /// Copy the record into an owned [`FastARecord`].
+    pub fn to_owned_record(&self) -> FastARecord
+    {
+        FastARecord { name: self.name.to_string(), raw_seq: self.raw_seq.to_vec() }
+    }
+}
+
+/// A FASTQ record borrowed from a byte slice.
+#[derive(Debug, Clone, Copy)]
+pub struct FastQRecordRef<'a>
+{
+    name: &'a str,
+    seq: &'a [u8],
+    comment: &'a str,
+    qual: &'a [u8],
+}
+
+impl<'a> FastQRecordRef<'a>
+{
+    /// Get the full header line (without the leading `@`).
+    pub fn name(&self) -> &'a str
+    {
+        self.name
+    }
+
+    /// Get the sequence identifier (the part before the first space in the name).
+    pub fn id(&self) -> &'a str
+    {
+        split_name(self.name).0
+    }
+
+    /// Get the description (the part after the first space in the name).
+    pub fn desc(&self) -> &'a str
+    {
+        split_name(self.name).1
+    }
+
+    /// Get the sequence.
+    pub fn seq(&self) -> &'a [u8]
+    {
+        self.seq
+    }
+
+    /// Get the sequence length.
+    pub fn seq_len(&self) -> usize
+    {
+        self.seq.len()
+    }
+
+    /// Get the comment line (the content after the `+` separator).
+    pub fn comment(&self) -> &'a str
+    {
+        self.comment
+    }
+
+    /// Get the quality scores.
+    pub fn qual(&self) -> &'a [u8]
+    {
+        self.qual
+    }
+}
+
+/// Iterator over the FASTA records of a byte slice, from [`fasta_records`].
+pub struct FastARecordRefs<'a>
+{
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> FastARecordRefs<'a>
+{
+    fn next_record(&mut self) -> io::Result<FastARecordRef<'a>>
+    {
+        if self.data[self.pos] != b'>'
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected '>' at offset {}", self.pos),
+            ));
+        }
+        let (name, seq_start) = line_at(self.data, self.pos + 1);
+        let seq_end = memchr::memmem::find(&self.data[seq_start..], b"\n>")
+            .map_or(self.data.len(), |i| seq_start + i + 1);
+        self.pos = seq_end;
+        Ok(FastARecordRef {
+            name: utf8(name, "FASTA header")?,
+            raw_seq: trim_end(&self.data[seq_start..seq_end]),
+        })
+    }
+}
+
+impl<'a> Iterator for FastARecordRefs<'a>
+{
+    type Item = io::Result<FastARecordRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        if self.pos >= self.data.len()
+        {
+            return None;
+        }
+        let record = self.next_record();
+        if record.is_err()
+        {
+            self.pos = self.data.len();
+        }
+        Some(record)
+    }
+}
+
+/// Iterator over the FASTQ records of a byte slice, from [`fastq_records`].
+pub struct FastQRecordRefs<'a>
+{
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> FastQRecordRefs<'a>
+{
+    fn next_record(&mut self) -> io::Result<FastQRecordRef<'a>>
+    {
+        let data = self.data;
+        let mut lines = [&data[..0]; 4];
+        let mut pos = self.pos;
+        for (i, line) in lines.iter_mut().enumerate()
+        {
+            if pos >= data.len()
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("FASTQ record at offset {} truncated after {} lines", self.pos, i),
+                ));
+            }
+            (*line, pos) = line_at(data, pos);
+        }
+        let [header, seq, comment, qual] = lines;
+
+        let name = header.strip_prefix(b"@").ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "FASTQ header must start with @")
+        })?;
+        let comment = comment.strip_prefix(b"+").ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "FASTQ comment must start with +")
+        })?;
+        self.pos = pos;
+        Ok(FastQRecordRef {
+            name: utf8(name, "FASTQ header")?,
+            seq,
+            comment: utf8(comment, "FASTQ comment")?,
+            qual,
+        })
+    }
+}
+
+impl<'a> Iterator for FastQRecordRefs<'a>
+{
+    type Item = io::Result<FastQRecordRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        // Tolerate blank lines at the end of the file
+        if trim_end(&self.data[self.pos..]).is_empty()
+        {
+            return None;
+        }
+        let record = self.next_record();
+        if record.is_err()
+        {
+            self.pos = self.data.len();
+        }
+        Some(record)
+    }
+}
+
+/// Iterate over the FASTA records in `data` without copying them.
+///
+/// # Example
+///
+/// ```
+/// use fastx::mmap::fasta_records;
+///
+/// let data = b">seq1 description\nACGT\nACGT\n>seq2\nTT\n";
+/// let records: Vec<_> = fasta_records(data).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(records[0].id(), "seq1");
+/// assert_eq!(&records[0].seq()[..], b"ACGTACGT");
+/// assert_eq!(records[1].seq_raw(), b"TT");
+/// ```
+pub fn fasta_records(data: &[u8]) -> FastARecordRefs<'_>
+{
+    FastARecordRefs { data, pos: 0 }
+}
+
+/// Iterate over the FASTQ records in `data` without copying them.
+pub fn fastq_records(data: &[u8]) -> FastQRecordRefs<'_>
+{
+    FastQRecordRefs { data, pos: 0 }
+}
+
+/// Random access to an uncompressed FASTA file with a `.fai` index.
+///
+/// Regions are located by arithmetic on the index and read straight from the
+/// map, so fetches take constant time regardless of their position in the file.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::indexed::SequenceFetch;
+/// use fastx::mmap::MmapFastaReader;
+/// use std::path::Path;
+///
+/// // Requires ref.fa and ref.fa.fai
+/// let reader = MmapFastaReader::from_path(Path::new("ref.fa")).unwrap();
+/// let bases = reader.fetch_range("chr1", 1000, 2000).unwrap();
+/// ```
+pub struct MmapFastaReader
+{
+    reader: MmapReader,
+    fai_index: FaiIndex,
+}
+
+impl MmapFastaReader
+{
+    /// Map a FASTA file and load its `.fai` index.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(reader)` - The indexed reader
+    /// * `Err(io::Error)` - If the file or index cannot be opened
+    pub fn from_path(path: &Path) -> io::Result<Self>
+    {
+        let fai_path = find_index_file(path, "fai").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No .fai index found for {}", path.display()),
+            )
+        })?;
+        Ok(Self::new(MmapReader::open(path)?, FaiIndex::from_path(&fai_path)?))
+    }
+
+    /// Create a reader from a mapped file and its index.
+    pub fn new(reader: MmapReader, fai_index: FaiIndex) -> Self
+    {
+        Self { reader, fai_index }
+    }
+
+    /// Get the FASTA index.
+    pub fn index(&self) -> &FaiIndex
+    {
+        &self.fai_index
+    }
+
+    fn entry(&self, seq_id: &str) -> io::Result<&FaiEntry>
+    {
+        self.fai_index.get(seq_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Sequence '{}' not found in index", seq_id),
+            )
+        })
+    }
+
+    /// Get the bytes of `[start, end)` of a sequence as stored, including line
+    /// breaks, without copying. `end` is clamped to the sequence length.
+    pub fn raw_range(&self, seq_id: &str, start: u64, end: u64) -> io::Result<&[u8]>
+    {
+        let entry = self.entry(seq_id)?;
+        let end = end.min(entry.length);
+        if start >= end
+        {
+            return Ok(&[]);
+        }
+        let from = entry.offset_for_position(start) as usize;
+        let to = entry.offset_for_position(end - 1) as usize + 1;
+        self.reader.as_bytes().get(from..to).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Index entry for '{}' points beyond the end of the file", seq_id),
+            )
+        })
+    }
+
+    /// Fetch the bases in `[start, end)` of a sequence, `end` clamped to its length.
+    pub fn fetch_range(&self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
+    {
+        let raw = self.raw_range(seq_id, start, end)?;
+        let seq: Vec<u8> = raw.iter().copied().filter(|&b| b != b'\n' && b != b'\r').collect();
+        let expected = self.entry(seq_id)?.region_length(start, end);
+        if seq.len() as u64 != expected
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Fetched {} bases of '{}' instead of {}; does the index match the file?",
+                    seq.len(),
+                    seq_id,
+                    expected
+                ),
+            ));
+        }
+        Ok(seq)
+    }
+
+    /// Fetch a whole sequence with its header line.
+    pub fn fetch(&self, seq_id: &str) -> io::Result<FastARecord>
+    {
+        let entry = self.entry(seq_id)?;
+        let data = self.reader.as_bytes();
+        let seq_start = (entry.offset as usize).min(data.len());
+        let header_start = memchr::memrchr(b'>', &data[..seq_start]).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("No header found before sequence '{}'", seq_id),
+            )
+        })?;
+        let (header, _) = line_at(data, header_start + 1);
+        Ok(FastARecord {
+            name: utf8(header, "FASTA header")?.to_string(),
+            raw_seq: self.raw_range(seq_id, 0, entry.length)?.to_vec(),
+        })
+    }
+
+    /// Check if a sequence exists in the index.
+    pub fn contains(&self, seq_id: &str) -> bool
+    {
+        self.fai_index.contains(seq_id)
+    }
+
+    /// Get all sequence names in the index.
+    pub fn sequence_names(&self) -> Vec<&str>
+    {
+        self.fai_index.sequence_names().collect()
+    }
+}
+
+impl SequenceFetch for MmapFastaReader
+{
+    fn fetch(&mut self, seq_id: &str) -> io::Result<FastARecord>
+    {
+        MmapFastaReader::fetch(self, seq_id)
+    }
+
+    fn fetch_range(&mut self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
+    {
+        MmapFastaReader::fetch_range(self, seq_id, start, end)
+    }
+
+    fn sequence_length(&mut self, seq_id: &str) -> io::Result<u64>
+    {
+        Ok(self.entry(seq_id)?.length)
+    }
+
+    fn contains(&self, seq_id: &str) -> bool
+    {
+        MmapFastaReader::contains(self, seq_id)
+    }
+
+    fn sequence_names(&self) -> Vec<&str>
+    {
+        MmapFastaReader::sequence_names(self)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::FastX::{fasta_iter, FastXRead};
+
+    fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf
+    {
+        let file_name = format!("fastx-mmap-{}-{}", std::process::id(), name);
+        let path = std::env::temp_dir().join(file_name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_borrowed_records()
+    {
+        let fasta = b">a first\nACGT\nAC\n\n>b\nGG\r\n>c\n";
+        let records: Vec<_> = fasta_records(fasta).collect::<io::Result<_>>().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!((records[0].id(), records[0].desc()), ("a", "first"));
+        assert_eq!(&records[0].seq()[..], b"ACGTAC");
+        assert_eq!(records[0].seq_len(), 6);
+        assert_eq!(records[1].seq_raw(), b"GG");
+        assert!(matches!(records[1].seq(), Cow::Borrowed(_)));
+        assert_eq!(records[2].seq_len(), 0);
+        assert!(fasta_records(b"ACGT\n").next().unwrap().is_err());
+
+        let fastq = b"@r1 x\nACGT\n+\n!!!!\n@r2\nGG\n+r2\nII\n\n";
+        let records: Vec<_> = fastq_records(fastq).collect::<io::Result<_>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id(), "r1");
+        assert_eq!((records[0].seq(), records[0].qual()), (&b"ACGT"[..], &b"!!!!"[..]));
+        assert_eq!(records[1].comment(), "r2");
+        let truncated: Vec<_> = fastq_records(b"@r1\nACGT\n+\n").collect();
+        assert!(truncated[0].is_err());
+    }
+
+    #[test]
+    fn test_mmap_reader_and_indexed_fetch()
+    {
+        let fasta = b">chr1 test\nACGTACGTAC\nGTACGTACGT\nAC\n>chr2\nTTTTGGGG\n";
+        let path = temp_file("ref.fa", fasta);
+        let fai = "chr1\t22\t11\t10\t11\nchr2\t8\t42\t8\t9\n";
+        let fai_path = std::path::PathBuf::from(format!("{}.fai", path.display()));
+        std::fs::write(&fai_path, fai).unwrap();
+
+        // The map works with the buffered parsers
+        let reader = MmapReader::open(&path).unwrap();
+        let names: Vec<String> =
+            fasta_iter(reader).map(|record| record.unwrap().id().to_string()).collect();
+        assert_eq!(names, ["chr1", "chr2"]);
+        let reader = crate::FastX::reader_from_path_mmap(&path).unwrap();
+        assert_eq!(fasta_iter(reader).count(), 2);
+        let reader = crate::FastX::reader_from_path_with_capacity(&path, 16).unwrap();
+        assert_eq!(fasta_iter(reader).count(), 2);
+
+        let mut reader = MmapFastaReader::from_path(&path).unwrap();
+        assert_eq!(reader.fetch_range("chr1", 8, 13).unwrap(), b"ACGTA");
+        assert_eq!(reader.raw_range("chr1", 8, 13).unwrap(), b"AC\nGTA");
+        assert_eq!(reader.fetch_range("chr1", 20, 100).unwrap(), b"AC");
+        assert_eq!(reader.fetch_range("chr2", 2, 6).unwrap(), b"TTGG");
+        let record = SequenceFetch::fetch(&mut reader, "chr1").unwrap();
+        assert_eq!(record.name(), "chr1 test");
+        assert_eq!(record.seq(), b"ACGTACGTACGTACGTACGTAC");
+        assert_eq!(reader.fetch_region(&"chr2:7-8".parse().unwrap()).unwrap(), b"GG");
+        assert_eq!(reader.fetch_range("chrX", 0, 1).unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&fai_path).unwrap();
+    }
+}
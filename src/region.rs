@@ -0,0 +1,173 @@
+//! Genomic regions shared by sequence and annotation lookups.
+//!
+//! A [`Region`] names a sequence and a 0-based, half-open interval on it. It can
+//! be parsed from the samtools/tabix notation `chr1:1,001-2,000` (1-based, inclusive)
+//! and is accepted by [`SequenceFetch::fetch_region`](crate::indexed::SequenceFetch::fetch_region)
+//! and [`TabixReader::query`](crate::tabix::TabixReader::query), so the same
+//! coordinates select bases and overlapping annotation lines.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// A sequence name and a 0-based, half-open interval `[start, end)` on it.
+///
+/// An `end` of None extends the region to the end of the sequence.
+///
+/// # Example
+///
+/// ```
+/// use fastx::region::Region;
+///
+/// let region: Region = "chr1:1,001-2,000".parse().unwrap();
+/// assert_eq!(region, Region::new("chr1", 1000, 2000));
+/// assert_eq!(region.to_string(), "chr1:1001-2000");
+///
+/// let whole: Region = "chrM".parse().unwrap();
+/// assert_eq!(whole.end, None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Region
+{
+    /// Sequence name
+    pub name: String,
+    /// 0-based start
+    pub start: u64,
+    /// Exclusive end, None for the end of the sequence
+    pub end: Option<u64>,
+}
+
+impl Region
+{
+    /// Create a region covering `[start, end)` of a sequence.
+    pub fn new(name: impl Into<String>, start: u64, end: u64) -> Self
+    {
+        Self { name: name.into(), start, end: Some(end) }
+    }
+
+    /// Create a region covering a whole sequence.
+    pub fn whole(name: impl Into<String>) -> Self
+    {
+        Self { name: name.into(), start: 0, end: None }
+    }
+
+    /// Exclusive end, or `u64::MAX` if the region extends to the end of the sequence.
+    pub fn end_or_max(&self) -> u64
+    {
+        self.end.unwrap_or(u64::MAX)
+    }
+
+    /// Check if the interval `[start, end)` overlaps the region.
+    pub fn overlaps(&self, start: u64, end: u64) -> bool
+    {
+        start < self.end_or_max() && end > self.start
+    }
+}
+
+impl FromStr for Region
+{
+    type Err = io::Error;
+
+    /// Parse `name`, `name:start` or `name:start-end` with 1-based, inclusive
+    /// coordinates; commas in numbers are ignored.
+    ///
+    /// If the part after the last `:` is not a range, the whole string is taken
+    /// as the name, so names containing `:` (e.g. `HLA-A*01:01:01G`) still work.
+    /// Names ending in `:` and a number need an explicit range.
+    fn from_str(s: &str) -> io::Result<Self>
+    {
+        if s.is_empty()
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Empty region"));
+        }
+        let Some((name, range)) = s.rsplit_once(':')
+        else
+        {
+            return Ok(Self::whole(s));
+        };
+
+        let number = |text: &str| -> Option<u64> {
+            let digits: String = text.chars().filter(|&c| c != ',').collect();
+            digits.parse().ok()
+        };
+        let (start, end) = match range.split_once('-')
+        {
+            Some((start, "")) => (number(start), None),
+            Some((start, end)) => match number(end)
+            {
+                Some(end) => (number(start), Some(end)),
+                None => (None, None),
+            },
+            None => (number(range), None),
+        };
+        let Some(start) = start
+        else
+        {
+            return Ok(Self::whole(s));
+        };
+
+        if start == 0 || end.is_some_and(|end| end < start) || name.is_empty()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid region: {}", s),
+            ));
+        }
+        Ok(Self { name: name.to_string(), start: start - 1, end })
+    }
+}
+
+impl fmt::Display for Region
+{
+    /// Format as `name`, `name:start-` or `name:start-end` with 1-based, inclusive
+    /// coordinates.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match (self.start, self.end)
+        {
+            (0, None) => write!(f, "{}", self.name),
+            (start, None) => write!(f, "{}:{}-", self.name, start + 1),
+            (start, Some(end)) => write!(f, "{}:{}-{}", self.name, start + 1, end),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format()
+    {
+        assert_eq!("chr1:1,001-2,000".parse::<Region>().unwrap(), Region::new("chr1", 1000, 2000));
+        assert_eq!("chr1:1-1".parse::<Region>().unwrap(), Region::new("chr1", 0, 1));
+        let open: Region = "chr2:500".parse().unwrap();
+        assert_eq!((open.start, open.end), (499, None));
+        assert_eq!("chr2:500-".parse::<Region>().unwrap(), open);
+        assert_eq!(open.to_string(), "chr2:500-");
+        let hla = "HLA-A*01:01:01G";
+        assert_eq!(hla.parse::<Region>().unwrap(), Region::whole(hla));
+        assert_eq!(format!("{}:1-10", hla).parse::<Region>().unwrap(), Region::new(hla, 0, 10));
+
+        assert!("chr1:0-10".parse::<Region>().is_err());
+        assert!("chr1:20-10".parse::<Region>().is_err());
+        assert!(":1-10".parse::<Region>().is_err());
+        assert!("".parse::<Region>().is_err());
+
+        for text in ["chr1", "chr1:5-", "chr1:1001-2000"]
+        {
+            assert_eq!(text.parse::<Region>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_overlaps()
+    {
+        let region = Region::new("chr1", 100, 200);
+        assert!(region.overlaps(199, 300));
+        assert!(!region.overlaps(200, 300));
+        assert!(!region.overlaps(50, 100));
+        assert!(Region::whole("chr1").overlaps(1 << 40, (1 << 40) + 1));
+    }
+}
@@ -3,28 +3,969 @@
 //! This module provides `RemoteReader` which implements `Read` and `Seek`
 //! for HTTP/HTTPS URLs using range requests, with intelligent caching to
 //! minimize network requests.
+//!
+//! Servers that ignore `Range` headers are detected by their `200 OK` responses
+//! (or `Accept-Ranges: none`); the file is then downloaded once into a local
+//! spill file that serves all further reads.
 
 #![cfg(feature = "url")]
 
-use std::collections::HashMap;
+use crate::cache::LruCache;
+pub use crate::cache::CacheStats;
+use crate::digest::{base64, BASE64_STANDARD};
+use crate::disk_cache::{CacheEntry, DiskCache};
+use crate::s3::S3Config;
+use crate::source::{FileSource, RangeSource};
+use std::fs::{self, File};
 use std::io::{self, Read, Seek, SeekFrom};
-use std::sync::Mutex;
-use ureq::Agent;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use ureq::http::Response;
+use ureq::tls::{PemItem, RootCerts, TlsConfig};
+use ureq::typestate::WithoutBody;
+use ureq::{Agent, Body, Proxy, RequestBuilder};
 
 /// Default block size for caching (64KB).
 const DEFAULT_BLOCK_SIZE: u64 = 64 * 1024;
 
-/// A cached block of data from the remote file.
+/// Default upper bound for cached block data (64MB).
+pub const DEFAULT_CACHE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Default number of blocks fetched ahead when reads are sequential.
+pub const DEFAULT_READ_AHEAD: u64 = 4;
+
+/// How failed HTTP requests are retried.
+///
+/// Connection failures, timeouts, truncated bodies and the statuses 408, 429
+/// and 5xx are retried with exponential backoff. A `Retry-After` header, in
+/// seconds or as an HTTP date, overrides the backoff for that attempt. Other
+/// failures are returned immediately with a matching error kind:
+///
+/// - `NotFound` for 404 and 410
+/// - `PermissionDenied` for 401 and 403
+/// - `TimedOut` when a request or the whole operation times out
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::remote::{RemoteReader, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::default()
+///     .with_max_retries(5)
+///     .with_request_timeout(Some(Duration::from_secs(10)));
+/// let reader = RemoteReader::new("https://example.com/data.fasta.gz")
+///     .unwrap()
+///     .with_retry_policy(policy);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy
+{
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    request_timeout: Option<Duration>,
+    total_timeout: Option<Duration>,
+}
+
+impl Default for RetryPolicy
+{
+    /// 3 retries starting at 200ms backoff (capped at 10s) with jitter,
+    /// 30s per request and 2 minutes in total.
+    fn default() -> Self
+    {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+            request_timeout: Some(Duration::from_secs(30)),
+            total_timeout: Some(Duration::from_secs(120)),
+        }
+    }
+}
+
+impl RetryPolicy
+{
+    /// A policy that never retries, keeping the default timeouts.
+    pub fn none() -> Self
+    {
+        Self::default().with_max_retries(0)
+    }
+
+    /// Set the number of retries after the first attempt.
+    pub fn with_max_retries(mut self, retries: u32) -> Self
+    {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Set the delay before the first retry and the upper bound it doubles up to.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self
+    {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Enable or disable randomizing delays (to between half and the full backoff).
+    pub fn with_jitter(mut self, jitter: bool) -> Self
+    {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set the timeout for a single request, or None for no limit.
+    pub fn with_request_timeout(mut self, timeout: Option<Duration>) -> Self
+    {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Set the timeout for a request including all its retries, or None for no limit.
+    pub fn with_total_timeout(mut self, timeout: Option<Duration>) -> Self
+    {
+        self.total_timeout = timeout;
+        self
+    }
+
+    /// Get the number of retries after the first attempt.
+    pub fn max_retries(&self) -> u32
+    {
+        self.max_retries
+    }
+
+    /// Delay before retry number `retry` (0-based).
+    fn backoff(&self, retry: u32) -> Duration
+    {
+        let delay = self
+            .initial_backoff
+            .saturating_mul(1u32.checked_shl(retry).unwrap_or(u32::MAX))
+            .min(self.max_backoff);
+        if !self.jitter
+        {
+            return delay;
+        }
+        // Cheap xorshift seeded from the clock; quality does not matter here
+        let mut x = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+            ^ ((retry as u64 + 1) << 32)
+            | 1;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        let half = delay / 2;
+        half + half.mul_f64((x % 1000) as f64 / 1000.0)
+    }
+
+    /// Run `attempt` until it succeeds, fails permanently or retries run out.
+    ///
+    /// `attempt` receives the timeout for this request.
+    fn run<T>(
+        &self,
+        mut attempt: impl FnMut(Option<Duration>) -> Result<T, Failure>,
+    ) -> io::Result<T>
+    {
+        let started = Instant::now();
+        let mut retry = 0;
+        loop
+        {
+            match attempt(self.attempt_timeout(started))
+            {
+                Ok(value) => return Ok(value),
+                Err(failure) => thread::sleep(self.retry_delay(started, retry, failure)?),
+            }
+            retry += 1;
+        }
+    }
+
+    /// Like [`run`](Self::run), for attempts that are futures.
+    #[cfg(feature = "async")]
+    pub(crate) async fn run_async<T, F>(
+        &self,
+        mut attempt: impl FnMut(Option<Duration>) -> F,
+    ) -> io::Result<T>
+    where
+        F: std::future::Future<Output = Result<T, Failure>>,
+    {
+        let started = Instant::now();
+        let mut retry = 0;
+        loop
+        {
+            match attempt(self.attempt_timeout(started)).await
+            {
+                Ok(value) => return Ok(value),
+                Err(failure) =>
+                {
+                    tokio::time::sleep(self.retry_delay(started, retry, failure)?).await;
+                }
+            }
+            retry += 1;
+        }
+    }
+
+    /// Timeout for the next attempt of an operation started at `started`.
+    fn attempt_timeout(&self, started: Instant) -> Option<Duration>
+    {
+        let remaining = self.total_timeout.map(|t| t.saturating_sub(started.elapsed()));
+        match (self.request_timeout, remaining)
+        {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Delay before retrying after attempt number `retry` (0-based) failed, or
+    /// the error to return if the operation should not be retried.
+    fn retry_delay(&self, started: Instant, retry: u32, failure: Failure) -> io::Result<Duration>
+    {
+        if !failure.retryable || retry >= self.max_retries
+        {
+            return Err(failure.error);
+        }
+
+        let delay = failure.retry_after.unwrap_or_else(|| self.backoff(retry));
+        if let Some(total) = self.total_timeout
+        {
+            if started.elapsed() + delay >= total
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("Gave up after {} attempts: {}", retry + 1, failure.error),
+                ));
+            }
+        }
+        Ok(delay)
+    }
+}
+
+/// A failed request attempt.
+pub(crate) struct Failure
+{
+    error: io::Error,
+    retryable: bool,
+    /// Delay requested by the server with `Retry-After`
+    retry_after: Option<Duration>,
+}
+
+impl Failure
+{
+    pub(crate) fn fatal(error: io::Error) -> Self
+    {
+        Self { error, retryable: false, retry_after: None }
+    }
+
+    pub(crate) fn transient(error: io::Error) -> Self
+    {
+        Self { error, retryable: true, retry_after: None }
+    }
+}
+
+/// Classify a transport error from ureq.
+fn request_failure(error: ureq::Error, method: &str) -> Failure
+{
+    let message = format!("HTTP {} request failed: {}", method, error);
+    match error
+    {
+        ureq::Error::Timeout(_) =>
+        {
+            Failure::transient(io::Error::new(io::ErrorKind::TimedOut, message))
+        }
+        ureq::Error::ConnectionFailed =>
+        {
+            Failure::transient(io::Error::new(io::ErrorKind::ConnectionRefused, message))
+        }
+        ureq::Error::Io(e) => body_failure(e, method),
+        ureq::Error::Protocol(_) =>
+        {
+            Failure::transient(io::Error::new(io::ErrorKind::InvalidData, message))
+        }
+        ureq::Error::HostNotFound =>
+        {
+            Failure::fatal(io::Error::new(io::ErrorKind::NotFound, message))
+        }
+        ureq::Error::BadUri(_) | ureq::Error::Http(_) =>
+        {
+            Failure::fatal(io::Error::new(io::ErrorKind::InvalidInput, message))
+        }
+        _ => Failure::fatal(io::Error::other(message)),
+    }
+}
+
+/// Classify an I/O error while sending a request or reading a body.
+fn body_failure(error: io::Error, method: &str) -> Failure
+{
+    let kind = error.kind();
+    let error = io::Error::new(kind, format!("HTTP {} request failed: {}", method, error));
+    match kind
+    {
+        io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::BrokenPipe
+        | io::ErrorKind::UnexpectedEof
+        | io::ErrorKind::TimedOut
+        | io::ErrorKind::Interrupted => Failure::transient(error),
+        _ => Failure::fatal(error),
+    }
+}
+
+/// Classify an unsuccessful HTTP status.
+fn status_failure(response: &Response<Body>, url: &str) -> Failure
+{
+    let retry_after = response.headers().get("Retry-After").and_then(|v| v.to_str().ok());
+    status_code_failure(response.status().as_u16(), retry_after, url)
+}
+
+/// Classify an unsuccessful HTTP status code, given the `Retry-After` header if any.
+pub(crate) fn status_code_failure(status: u16, retry_after: Option<&str>, url: &str) -> Failure
+{
+    let message = format!("HTTP status {} for {}", status, url);
+    let retry_after = retry_after.and_then(|s| parse_retry_after(s, SystemTime::now()));
+    let (kind, retryable) = match status
+    {
+        404 | 410 => (io::ErrorKind::NotFound, false),
+        401 | 403 => (io::ErrorKind::PermissionDenied, false),
+        416 => (io::ErrorKind::InvalidInput, false),
+        408 | 504 => (io::ErrorKind::TimedOut, true),
+        429 | 500..=599 => (io::ErrorKind::Other, true),
+        _ => (io::ErrorKind::InvalidData, false),
+    };
+    Failure { error: io::Error::new(kind, message), retryable, retry_after }
+}
+
+/// Parse a `Retry-After` value, either delay-seconds or an HTTP date.
+///
+/// Only the IMF-fixdate form of HTTP dates (`Wed, 21 Oct 2015 07:28:00 GMT`) is
+/// understood; the obsolete RFC 850 and asctime forms give None, so the normal
+/// backoff applies. Dates in the past mean retrying at once.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration>
+{
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>()
+    {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Parse an IMF-fixdate such as `Wed, 21 Oct 2015 07:28:00 GMT`.
+fn parse_http_date(value: &str) -> Option<SystemTime>
+{
+    const MONTHS: [&str; 12] =
+        ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+    let parts: Vec<&str> = value.split_ascii_whitespace().collect();
+    let [weekday, day, month, year, time, "GMT"] = parts[..]
+    else
+    {
+        return None;
+    };
+    if !weekday.ends_with(',')
+    {
+        return None;
+    }
+    let day: i64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let year: i64 = year.parse().ok()?;
+    let hms: Vec<u64> = time.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let [hour, minute, second] = hms[..]
+    else
+    {
+        return None;
+    };
+    if !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60
+    {
+        return None;
+    }
+
+    // Days since 1970-01-01 from a civil date (Howard Hinnant's algorithm)
+    let y = year - i64::from(month <= 2);
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = u64::try_from(era * 146097 + doe - 719468).ok()?;
+
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Error for a server that ignores range requests when spilling is disabled.
+pub(crate) fn ranges_unsupported(url: &str) -> io::Error
+{
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "Server does not support range requests for {}; \
+             set a spill directory with RemoteConfig::with_spill_dir to download it whole",
+            url
+        ),
+    )
+}
+
+/// Connection settings shared by all requests of a remote reader.
+///
+/// One [`Agent`] is built from the configuration and reused for every request,
+/// including the index downloads of
+/// [`IndexedFastXReader::from_url_with_config`](crate::indexed::IndexedFastXReader::from_url_with_config).
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::remote::{RemoteConfig, RemoteReader, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let config = RemoteConfig::default()
+///     .with_bearer_token("secret-token")
+///     .with_user_agent("my-pipeline/1.0")
+///     .with_header("X-Project", "genomes")
+///     .with_proxy("http://proxy.example.com:3128")
+///     .with_connect_timeout(Some(Duration::from_secs(5)))
+///     .with_retry_policy(RetryPolicy::default().with_max_retries(5))
+///     .with_block_size(256 * 1024);
+/// let reader = RemoteReader::with_config("https://example.com/data.fasta.gz", &config).unwrap();
+/// ```
 #[derive(Debug, Clone)]
-struct CachedBlock
+pub struct RemoteConfig
 {
-    /// Starting offset of this block in the file
-    #[allow(dead_code)]
-    offset: u64,
-    /// The cached data
-    data: Vec<u8>,
+    headers: Vec<(String, String)>,
+    user_agent: Option<String>,
+    proxy: Option<String>,
+    /// PEM bundles of trusted root certificates, empty for the built-in roots
+    root_certs: Vec<Vec<u8>>,
+    connect_timeout: Option<Duration>,
+    retry: RetryPolicy,
+    block_size: u64,
+    cache_size: u64,
+    read_ahead: u64,
+    disk_cache: Option<Arc<DiskCache>>,
+    /// Directory for downloads of files whose server ignores ranges
+    spill_dir: Option<PathBuf>,
+    /// Endpoint and credentials for signing S3 requests
+    s3: Option<Arc<S3Config>>,
 }
 
+impl Default for RemoteConfig
+{
+    fn default() -> Self
+    {
+        Self {
+            headers: Vec::new(),
+            user_agent: None,
+            proxy: None,
+            root_certs: Vec::new(),
+            connect_timeout: None,
+            retry: RetryPolicy::default(),
+            block_size: DEFAULT_BLOCK_SIZE,
+            cache_size: DEFAULT_CACHE_SIZE,
+            read_ahead: DEFAULT_READ_AHEAD,
+            disk_cache: None,
+            spill_dir: Some(std::env::temp_dir()),
+            s3: None,
+        }
+    }
+}
+
+impl RemoteConfig
+{
+    /// Add a header sent with every request.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self
+    {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Send `Authorization: Bearer <token>` with every request.
+    pub fn with_bearer_token(self, token: impl AsRef<str>) -> Self
+    {
+        let value = format!("Bearer {}", token.as_ref());
+        self.with_header("Authorization", value)
+    }
+
+    /// Send HTTP basic authentication with every request.
+    pub fn with_basic_auth(self, user: impl AsRef<str>, password: impl AsRef<str>) -> Self
+    {
+        let credentials = format!("{}:{}", user.as_ref(), password.as_ref());
+        let value = format!("Basic {}", base64(credentials.as_bytes(), BASE64_STANDARD, true));
+        self.with_header("Authorization", value)
+    }
+
+    /// Set the `User-Agent` header.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self
+    {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Route requests through a proxy, e.g. `http://host:3128` or `socks5://host:1080`.
+    ///
+    /// Without this, proxies are taken from the usual environment variables.
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self
+    {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Trust the root certificates in a PEM bundle instead of the built-in roots.
+    ///
+    /// May be called several times to combine bundles.
+    pub fn with_root_certificates_pem(mut self, pem: impl Into<Vec<u8>>) -> Self
+    {
+        self.root_certs.push(pem.into());
+        self
+    }
+
+    /// Set the timeout for establishing connections, or None for no limit.
+    pub fn with_connect_timeout(mut self, timeout: Option<Duration>) -> Self
+    {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set how failed requests are retried, including their timeouts.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self
+    {
+        self.retry = policy;
+        self
+    }
+
+    /// Set the size of the blocks fetched and cached by [`RemoteReader`].
+    pub fn with_block_size(mut self, size: u64) -> Self
+    {
+        self.block_size = size;
+        self
+    }
+
+    /// Set the maximum number of bytes kept in the block cache of [`RemoteReader`].
+    ///
+    /// Prefetched blocks are kept in this cache too, so a cache smaller than one
+    /// block disables [`RemoteReader::prefetch`].
+    pub fn with_cache_size(mut self, bytes: u64) -> Self
+    {
+        self.cache_size = bytes;
+        self
+    }
+
+    /// Set how many further blocks [`RemoteReader`] fetches with a block when
+    /// reads are sequential; 0 disables read-ahead.
+    pub fn with_read_ahead(mut self, blocks: u64) -> Self
+    {
+        self.read_ahead = blocks;
+        self
+    }
+
+    /// Keep fetched blocks and index downloads in a persistent disk cache.
+    ///
+    /// Cached data is reused across processes as long as the remote file's
+    /// `ETag`, `Last-Modified` and `Content-Length` are unchanged.
+    pub fn with_disk_cache(mut self, cache: DiskCache) -> Self
+    {
+        self.disk_cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Set where [`RemoteReader`] keeps a local copy of files whose server does
+    /// not support range requests.
+    ///
+    /// Defaults to the system temporary directory. With `None`, reading such a
+    /// file fails with `ErrorKind::Unsupported` instead of downloading it whole.
+    pub fn with_spill_dir(mut self, dir: Option<PathBuf>) -> Self
+    {
+        self.spill_dir = dir;
+        self
+    }
+
+    /// Resolve `s3://` URLs and sign requests to the S3 endpoint with these settings.
+    ///
+    /// Without this, `s3://` URLs use [`S3Config::from_env`].
+    pub fn with_s3(mut self, s3: S3Config) -> Self
+    {
+        self.s3 = Some(Arc::new(s3));
+        self
+    }
+
+    /// Get the S3 settings, if set.
+    pub fn s3_config(&self) -> Option<&S3Config>
+    {
+        self.s3.as_deref()
+    }
+
+    /// Fill in S3 settings from the environment unless set explicitly.
+    pub(crate) fn with_s3_defaults(self) -> Self
+    {
+        match self.s3
+        {
+            Some(_) => self,
+            None => self.with_s3(S3Config::from_env()),
+        }
+    }
+
+    /// Get the retry policy.
+    pub fn retry_policy(&self) -> &RetryPolicy
+    {
+        &self.retry
+    }
+
+    /// Build an HTTP agent with these settings.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(agent)` - The configured agent
+    /// * `Err(io::Error)` - If the proxy URL or a certificate bundle is invalid
+    pub fn build_agent(&self) -> io::Result<Agent>
+    {
+        let mut builder = Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_connect(self.connect_timeout);
+        if let Some(user_agent) = &self.user_agent
+        {
+            builder = builder.user_agent(user_agent.as_str());
+        }
+        if let Some(proxy) = &self.proxy
+        {
+            let proxy = Proxy::new(proxy).map_err(|e| {
+                let message = format!("Invalid proxy {}: {}", proxy, e);
+                io::Error::new(io::ErrorKind::InvalidInput, message)
+            })?;
+            builder = builder.proxy(Some(proxy));
+        }
+        if !self.root_certs.is_empty()
+        {
+            let mut certs = Vec::new();
+            for pem in &self.root_certs
+            {
+                for item in ureq::tls::parse_pem(pem)
+                {
+                    match item
+                    {
+                        Ok(PemItem::Certificate(cert)) => certs.push(cert),
+                        Ok(_) =>
+                        {}
+                        Err(e) =>
+                        {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("Invalid root certificate bundle: {}", e),
+                            ));
+                        }
+                    }
+                }
+            }
+            if certs.is_empty()
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "No certificates found in root certificate bundle",
+                ));
+            }
+            let tls = TlsConfig::builder().root_certs(RootCerts::new_with_certs(&certs)).build();
+            builder = builder.tls_config(tls);
+        }
+        Ok(builder.build().into())
+    }
+
+    /// Build an async HTTP client with these settings.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(client)` - The configured client
+    /// * `Err(io::Error)` - If the proxy URL or a certificate bundle is invalid
+    #[cfg(feature = "async")]
+    pub(crate) fn build_async_client(&self) -> io::Result<reqwest::Client>
+    {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = self.connect_timeout
+        {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(user_agent) = &self.user_agent
+        {
+            builder = builder.user_agent(user_agent.as_str());
+        }
+        if let Some(proxy) = &self.proxy
+        {
+            let proxy = reqwest::Proxy::all(proxy.as_str()).map_err(|e| {
+                let message = format!("Invalid proxy {}: {}", proxy, e);
+                io::Error::new(io::ErrorKind::InvalidInput, message)
+            })?;
+            builder = builder.proxy(proxy);
+        }
+        if !self.root_certs.is_empty()
+        {
+            builder = builder.tls_built_in_root_certs(false);
+            for pem in &self.root_certs
+            {
+                let certs = reqwest::Certificate::from_pem_bundle(pem).map_err(|e| {
+                    let message = format!("Invalid root certificate bundle: {}", e);
+                    io::Error::new(io::ErrorKind::InvalidData, message)
+                })?;
+                if certs.is_empty()
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "No certificates found in root certificate bundle",
+                    ));
+                }
+                for cert in certs
+                {
+                    builder = builder.add_root_certificate(cert);
+                }
+            }
+        }
+        builder.build().map_err(io::Error::other)
+    }
+
+    /// Headers added to every request.
+    #[cfg(feature = "async")]
+    pub(crate) fn headers(&self) -> &[(String, String)]
+    {
+        &self.headers
+    }
+
+    /// Directory for whole-file downloads from servers that ignore ranges.
+    #[cfg(feature = "async")]
+    pub(crate) fn spill_dir(&self) -> Option<&Path>
+    {
+        self.spill_dir.as_deref()
+    }
+
+    /// Headers signing a request to the configured S3 endpoint, empty for
+    /// other hosts.
+    pub(crate) fn s3_signature(
+        &self,
+        method: &str,
+        uri: &ureq::http::Uri,
+        range: Option<&str>,
+    ) -> Vec<(String, String)>
+    {
+        match &self.s3
+        {
+            Some(s3) if s3.signs(uri) => s3.sign(method, uri, range, SystemTime::now()),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Add the configured headers and a request timeout to a request.
+    fn prepare(
+        &self,
+        mut request: RequestBuilder<WithoutBody>,
+        timeout: Option<Duration>,
+    ) -> RequestBuilder<WithoutBody>
+    {
+        for (name, value) in &self.headers
+        {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let signature = match (request.method_ref(), request.uri_ref())
+        {
+            (Some(method), Some(uri)) =>
+            {
+                let range = request
+                    .headers_ref()
+                    .and_then(|headers| headers.get("Range"))
+                    .and_then(|value| value.to_str().ok());
+                self.s3_signature(method.as_str(), uri, range)
+            }
+            _ => Vec::new(),
+        };
+        for (name, value) in signature
+        {
+            request = request.header(name, value);
+        }
+        request.config().timeout_global(timeout).http_status_as_error(false).build()
+    }
+}
+
+/// What a HEAD request tells about a remote file.
+struct RemoteMetadata
+{
+    /// File size, unknown for chunked responses of servers without range support
+    length: Option<u64>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// False if the server is known to ignore `Range` headers
+    ranges: bool,
+}
+
+impl RemoteMetadata
+{
+    /// Describe the version of the file for validating disk cache entries.
+    ///
+    /// Returns None if the server sent neither length, ETag nor Last-Modified, so
+    /// a changed file could not be told apart from a cached one.
+    fn validator(&self) -> Option<String>
+    {
+        if self.length.is_none() && self.etag.is_none() && self.last_modified.is_none()
+        {
+            return None;
+        }
+        Some(format!(
+            "length:{} etag:{} last-modified:{}",
+            self.length.map(|n| n.to_string()).unwrap_or_default(),
+            self.etag.as_deref().unwrap_or(""),
+            self.last_modified.as_deref().unwrap_or("")
+        ))
+    }
+}
+
+/// Get the value of a response header.
+fn header_value(response: &Response<Body>, name: &str) -> Option<String>
+{
+    response.headers().get(name).and_then(|v| v.to_str().ok()).map(|v| v.to_string())
+}
+
+/// Request the size and version headers of a remote file.
+///
+/// Without a `Content-Length`, the size is taken from the `Content-Range` of a
+/// `Range: bytes=0-0` request instead. That request also replaces a `HEAD`
+/// rejected with 403 or 405, as by presigned URLs signed for `GET` only.
+fn head(agent: &Agent, config: &RemoteConfig, url: &str) -> io::Result<RemoteMetadata>
+{
+    let response = config.retry.run(|timeout| {
+        let response = config
+            .prepare(agent.head(url), timeout)
+            .call()
+            .map_err(|e| request_failure(e, "HEAD"))?;
+        if matches!(response.status().as_u16(), 403 | 405)
+        {
+            return Ok(None);
+        }
+        if !response.status().is_success()
+        {
+            return Err(status_failure(&response, url));
+        }
+        Ok(Some(response))
+    })?;
+    let Some(response) = response
+    else
+    {
+        return probe_with_get(agent, config, url);
+    };
+
+    let header = |name: &str| header_value(&response, name);
+    let mut metadata = RemoteMetadata {
+        length: header("Content-Length").and_then(|s| s.parse::<u64>().ok()),
+        etag: header("ETag"),
+        last_modified: header("Last-Modified"),
+        ranges: !header("Accept-Ranges").is_some_and(|v| v.trim().eq_ignore_ascii_case("none")),
+    };
+    if metadata.length.is_none() && metadata.ranges
+    {
+        let probed = probe_with_get(agent, config, url)?;
+        metadata.length = probed.length;
+        metadata.ranges = probed.ranges;
+    }
+    Ok(metadata)
+}
+
+/// Request the size and version headers of a file with a `Range: bytes=0-0` request.
+///
+/// `ranges` is false if the server answers with the whole file.
+fn probe_with_get(agent: &Agent, config: &RemoteConfig, url: &str) -> io::Result<RemoteMetadata>
+{
+    let response = config.retry.run(|timeout| {
+        let response = config
+            .prepare(agent.get(url).header("Range", "bytes=0-0"), timeout)
+            .call()
+            .map_err(|e| request_failure(e, "GET"))?;
+        if !response.status().is_success()
+        {
+            return Err(status_failure(&response, url));
+        }
+        Ok(response)
+    })?;
+
+    // Dropping the response closes the connection without reading the body
+    let header = |name: &str| header_value(&response, name);
+    let mut metadata = RemoteMetadata {
+        length: None,
+        etag: header("ETag"),
+        last_modified: header("Last-Modified"),
+        ranges: response.status().as_u16() == 206,
+    };
+    if !metadata.ranges
+    {
+        metadata.length = header("Content-Length").and_then(|s| s.parse().ok());
+        return Ok(metadata);
+    }
+    let total = header("Content-Range")
+        .and_then(|range| range.rsplit_once('/').and_then(|(_, total)| total.parse().ok()));
+    match total
+    {
+        Some(total) => Ok(RemoteMetadata { length: Some(total), ..metadata }),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Could not determine the size of {}: no Content-Length or Content-Range", url),
+        )),
+    }
+}
+
+/// Check that a remote file exists with a HEAD request.
+pub(crate) fn probe(agent: &Agent, config: &RemoteConfig, url: &str) -> io::Result<()>
+{
+    head(agent, config, url).map(|_| ())
+}
+
+/// Download a whole (small) file, such as an index, with retries.
+///
+/// With a disk cache configured, a HEAD request validates the cached copy first.
+pub(crate) fn fetch_url(agent: &Agent, config: &RemoteConfig, url: &str) -> io::Result<Vec<u8>>
+{
+    const FILE_NAME: &str = "file";
+
+    let disk = config.disk_cache.as_ref().and_then(|cache| {
+        let metadata = head(agent, config, url).ok()?;
+        let entry = cache.entry(url, &metadata.validator()?).ok()?;
+        Some((cache, entry, metadata.length))
+    });
+    if let Some((cache, entry, length)) = &disk
+    {
+        if let Some(data) =
+            cache.read(entry, FILE_NAME).filter(|d| length.is_none_or(|n| d.len() as u64 == n))
+        {
+            return Ok(data);
+        }
+    }
+
+    let data = download(agent, config, url)?;
+    if let Some((cache, entry, _)) = &disk
+    {
+        // The disk cache is best effort
+        let _ = cache.write(entry, FILE_NAME, &data);
+    }
+    Ok(data)
+}
+
+/// Download a whole file with retries.
+fn download(agent: &Agent, config: &RemoteConfig, url: &str) -> io::Result<Vec<u8>>
+{
+    config.retry.run(|timeout| {
+        let response = config
+            .prepare(agent.get(url), timeout)
+            .call()
+            .map_err(|e| request_failure(e, "GET"))?;
+        if !response.status().is_success()
+        {
+            return Err(status_failure(&response, url));
+        }
+        response.into_body().into_with_config().limit(u64::MAX).read_to_vec().map_err(|e| match e
+        {
+            ureq::Error::Io(e) => body_failure(e, "GET"),
+            e => request_failure(e, "GET"),
+        })
+    })
+}
+
+/// Marks that no block has been read yet.
+const NO_BLOCK: u64 = u64::MAX;
+
 /// A remote file reader with HTTP range request support and caching.
 ///
 /// This reader fetches data from HTTP/HTTPS URLs on demand, caching blocks
@@ -35,7 +976,16 @@ struct CachedBlock
 ///
 /// The reader caches 64KB blocks. When data is requested, it fetches the
 /// entire block containing that position, serving subsequent reads from
-/// the same range from the cache.
+/// the same range from the cache. The cache holds at most
+/// [`DEFAULT_CACHE_SIZE`] bytes (see [`with_cache_size`](RemoteReader::with_cache_size))
+/// and evicts the least recently used blocks first.
+///
+/// # Read-ahead
+///
+/// When reads move on to the next block, the reader fetches that block and the
+/// following [`DEFAULT_READ_AHEAD`] blocks in one range request. Callers that know
+/// which bytes they will need can fetch them up front with
+/// [`prefetch`](RemoteReader::prefetch).
 ///
 /// # Example
 ///
@@ -51,13 +1001,90 @@ pub struct RemoteReader
     /// The HTTP agent for making requests
     agent: Agent,
     /// Cache of fetched blocks (offset -> data)
-    cache: Mutex<HashMap<u64, CachedBlock>>,
+    cache: Mutex<LruCache<u64, Vec<u8>>>,
     /// Current position in the file
     pos: u64,
     /// Total file size (cached after first request)
     file_size: Option<u64>,
     /// Block size for caching
     block_size: u64,
+    /// Headers and retry policy applied to requests
+    config: RemoteConfig,
+    /// Blocks fetched ahead on sequential reads
+    read_ahead: u64,
+    /// Start of the block read last, to detect sequential reads
+    last_block: AtomicU64,
+    /// Number of range requests sent
+    requests: AtomicU64,
+    /// Persistent cache and the entry for this file's current version
+    disk: Option<(Arc<DiskCache>, CacheEntry)>,
+    /// Local copy of the whole file if the server ignores range requests
+    spill: Mutex<Option<SpillFile>>,
+}
+
+/// A downloaded copy of a remote file, removed when dropped.
+pub(crate) struct SpillFile
+{
+    source: FileSource,
+    path: PathBuf,
+    len: u64,
+}
+
+impl SpillFile
+{
+    /// Stream a response body into a new file in `dir`.
+    fn create(dir: &Path, mut body: impl Read) -> io::Result<Self>
+    {
+        let (mut file, path) = Self::new_file(dir)?;
+        let copied = io::copy(&mut body, &mut file);
+        // A failed download is removed when dropped
+        let spill = Self::from_file(file, path, *copied.as_ref().unwrap_or(&0));
+        copied.map(|_| spill)
+    }
+
+    /// Create an empty file in `dir` for a download, returning it with its path.
+    pub(crate) fn new_file(dir: &Path) -> io::Result<(File, PathBuf)>
+    {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "fastx-spill-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = File::options().read(true).write(true).create_new(true).open(&path)?;
+        Ok((file, path))
+    }
+
+    /// Take over a file of `len` bytes created with [`new_file`](Self::new_file).
+    pub(crate) fn from_file(file: File, path: PathBuf, len: u64) -> Self
+    {
+        SpillFile { source: FileSource::with_size(file, len), path, len }
+    }
+
+    /// Get the number of bytes downloaded.
+    #[cfg(feature = "async")]
+    pub(crate) fn len(&self) -> u64
+    {
+        self.len
+    }
+
+    /// Read up to `length` bytes at `offset`.
+    pub(crate) fn read(&self, offset: u64, length: u64) -> io::Result<Vec<u8>>
+    {
+        let length = length.min(self.len.saturating_sub(offset));
+        let mut data = vec![0; length as usize];
+        self.source.read_exact_at(offset, &mut data)?;
+        Ok(data)
+    }
+}
+
+impl Drop for SpillFile
+{
+    fn drop(&mut self)
+    {
+        let _ = fs::remove_file(&self.path);
+    }
 }
 
 impl RemoteReader
@@ -81,46 +1108,80 @@ impl RemoteReader
     /// let reader = RemoteReader::new("https://example.com/data.fasta.gz").unwrap();
     /// ```
     pub fn new(url: impl Into<String>) -> io::Result<Self>
+    {
+        Self::with_config(url, &RemoteConfig::default())
+    }
+
+    /// Create a new remote reader with custom connection settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The HTTP/HTTPS URL to read from
+    /// * `config` - Headers, authentication, proxy, TLS, timeout and cache settings
+    pub fn with_config(url: impl Into<String>, config: &RemoteConfig) -> io::Result<Self>
+    {
+        let agent = config.build_agent()?;
+        Self::with_agent(url, agent, config)
+    }
+
+    /// Create a new remote reader reusing an agent built from `config`.
+    pub(crate) fn with_agent(
+        url: impl Into<String>,
+        agent: Agent,
+        config: &RemoteConfig,
+    ) -> io::Result<Self>
     {
         let url = url.into();
-        let agent = Agent::new_with_defaults();
 
         // Probe for file size using a HEAD request
-        let file_size = Self::get_file_size_for_url(&agent, &url)?;
+        let metadata = head(&agent, config, &url)?;
+        let disk = config.disk_cache.as_ref().and_then(|cache| {
+            let entry = cache.entry(&url, &metadata.validator()?).ok()?;
+            Some((Arc::clone(cache), entry))
+        });
 
-        Ok(Self {
+        let mut reader = Self {
             url,
             agent,
-            cache: Mutex::new(HashMap::new()),
+            cache: Mutex::new(LruCache::new(config.cache_size)),
             pos: 0,
-            file_size: Some(file_size),
-            block_size: DEFAULT_BLOCK_SIZE,
-        })
+            file_size: metadata.length,
+            block_size: config.block_size,
+            config: config.clone(),
+            read_ahead: config.read_ahead,
+            last_block: AtomicU64::new(NO_BLOCK),
+            requests: AtomicU64::new(0),
+            disk,
+            spill: Mutex::new(None),
+        };
+        if !metadata.ranges
+        {
+            // Without range support, only a full download tells the size
+            let spill = reader.download_spill()?;
+            reader.file_size = Some(spill.len);
+            reader.spill = Mutex::new(Some(spill));
+        }
+        Ok(reader)
     }
 
-    /// Get the total file size for a URL (static helper).
-    fn get_file_size_for_url(agent: &Agent, url: &str) -> io::Result<u64>
+    /// Create a reader with the default configuration without sending a request.
+    #[cfg(test)]
+    fn offline(block_size: u64, cache_size: u64, file_size: Option<u64>) -> Self
     {
-        let response = agent.head(url).call().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::ConnectionRefused,
-                format!("HTTP HEAD request failed: {}", e),
-            )
-        })?;
-
-        let content_length = response
-            .headers()
-            .get("Content-Length")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse::<u64>().ok())
-            .ok_or_else(|| {
-                io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Missing or invalid Content-Length header",
-                )
-            })?;
-
-        Ok(content_length)
+        Self {
+            url: "http://example.com/test".to_string(),
+            agent: Agent::new_with_defaults(),
+            cache: Mutex::new(LruCache::new(cache_size)),
+            pos: 0,
+            file_size,
+            block_size,
+            config: RemoteConfig::default(),
+            read_ahead: DEFAULT_READ_AHEAD,
+            last_block: AtomicU64::new(NO_BLOCK),
+            requests: AtomicU64::new(0),
+            disk: None,
+            spill: Mutex::new(None),
+        }
     }
 
     /// Set the block size for caching.
@@ -133,9 +1194,121 @@ impl RemoteReader
     pub fn with_block_size(mut self, size: u64) -> Self
     {
         self.block_size = size;
+        if let Ok(cache) = self.cache.get_mut()
+        {
+            cache.clear();
+        }
+        self
+    }
+
+    /// Set how many further blocks are fetched with a block when reads are
+    /// sequential.
+    ///
+    /// # Arguments
+    ///
+    /// * `blocks` - Number of extra blocks; 0 disables read-ahead
+    pub fn with_read_ahead(mut self, blocks: u64) -> Self
+    {
+        self.read_ahead = blocks;
+        self
+    }
+
+    /// Set the maximum number of bytes kept in the block cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Cache capacity in bytes; 0 disables caching, and with it
+    ///   [`prefetch`](RemoteReader::prefetch)
+    pub fn with_cache_size(mut self, bytes: u64) -> Self
+    {
+        if let Ok(cache) = self.cache.get_mut()
+        {
+            cache.set_capacity(bytes);
+        }
         self
     }
 
+    /// Set how failed requests are retried.
+    ///
+    /// The size probe when creating the reader uses the policy of its
+    /// [`RemoteConfig`].
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self
+    {
+        self.config.retry = policy;
+        self
+    }
+
+    /// Get the block cache counters.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::remote::RemoteReader;
+    /// use std::io::Read;
+    ///
+    /// let mut reader = RemoteReader::new("https://example.com/data.fasta.gz").unwrap();
+    /// let mut buf = [0u8; 1024];
+    /// reader.read_exact(&mut buf).unwrap();
+    /// let stats = reader.cache_stats();
+    /// println!("hit rate {:.2}, {} evictions", stats.hit_rate(), stats.evictions);
+    /// ```
+    pub fn cache_stats(&self) -> CacheStats
+    {
+        self.cache.lock().map(|cache| cache.stats()).unwrap_or_default()
+    }
+
+    /// Get the number of range requests sent so far, including retries.
+    pub fn request_count(&self) -> u64
+    {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Fetch the byte range `[start, end)` into the cache ahead of reading it.
+    ///
+    /// Blocks that are not cached yet are fetched with as few range requests as
+    /// possible. Prefetched blocks live in the block cache, so at most the cache
+    /// capacity is fetched, which keeps them from evicting each other. With a
+    /// cache smaller than one block (e.g. `with_cache_size(0)`) nothing can be
+    /// kept and prefetching does nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - First byte of the span
+    /// * `end` - End of the span (exclusive), clamped to the file size
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use fastx::remote::RemoteReader;
+    /// use std::io::{Read, Seek, SeekFrom};
+    ///
+    /// let mut reader = RemoteReader::new("https://example.com/data.fasta.gz").unwrap();
+    /// // One request for the whole span instead of one per block
+    /// reader.prefetch(1_000_000, 2_000_000).unwrap();
+    /// reader.seek(SeekFrom::Start(1_000_000)).unwrap();
+    /// let mut buf = vec![0u8; 1_000_000];
+    /// reader.read_exact(&mut buf).unwrap();
+    /// ```
+    pub fn prefetch(&self, start: u64, end: u64) -> io::Result<()>
+    {
+        let end = end.min(self.get_file_size()?);
+        let mut budget = self.cache_stats().capacity / self.block_size;
+        let mut block = self.block_start(start);
+        while block < end && budget > 0
+        {
+            let wanted = (end - block).div_ceil(self.block_size).min(budget);
+            let blocks = self.uncached_run(block, wanted)?;
+            if blocks > 0
+            {
+                let data = self.fetch_blocks(block, blocks)?;
+                self.cache_blocks(block, data)?;
+                budget -= blocks;
+            }
+            block += blocks.max(1) * self.block_size;
+        }
+        Ok(())
+    }
+
     /// Get the total file size.
     ///
     /// Makes a HEAD request to determine Content-Length if not already cached.
@@ -146,26 +1319,50 @@ impl RemoteReader
             return Ok(size);
         }
 
-        let response = self.agent.head(&self.url).call().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::ConnectionRefused,
-                format!("HTTP HEAD request failed: {}", e),
-            )
-        })?;
+        head(&self.agent, &self.config, &self.url)?.length.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Unknown size of {}", self.url))
+        })
+    }
 
-        let content_length = response
-            .headers()
-            .get("Content-Length")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse::<u64>().ok())
-            .ok_or_else(|| {
-                io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Missing or invalid Content-Length header",
-                )
-            })?;
+    /// Download the whole file into a spill file.
+    fn download_spill(&self) -> io::Result<SpillFile>
+    {
+        let dir = self.config.spill_dir.as_deref().ok_or_else(|| ranges_unsupported(&self.url))?;
+        self.config.retry.run(|timeout| {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            let response = self
+                .config
+                .prepare(self.agent.get(&self.url), timeout)
+                .call()
+                .map_err(|e| request_failure(e, "GET"))?;
+            if !response.status().is_success()
+            {
+                return Err(status_failure(&response, &self.url));
+            }
+            self.spill_response(dir, response)
+        })
+    }
+
+    /// Stream a full-file response into a spill file.
+    fn spill_response(&self, dir: &Path, response: Response<Body>) -> Result<SpillFile, Failure>
+    {
+        let spill = SpillFile::create(dir, response.into_body().into_reader())
+            .map_err(|e| body_failure(e, "GET"))?;
+        match self.file_size
+        {
+            Some(size) if size != spill.len => Err(Failure::transient(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Truncated download of {}: got {} of {} bytes", self.url, spill.len, size),
+            ))),
+            _ => Ok(spill),
+        }
+    }
 
-        Ok(content_length)
+    /// Read from the spill file, if the file has been downloaded whole.
+    fn read_spill(&self, offset: u64, length: u64) -> io::Result<Option<Vec<u8>>>
+    {
+        let spill = self.spill.lock().map_err(|_| io::Error::other("Spill lock poisoned"))?;
+        spill.as_ref().map(|spill| spill.read(offset, length)).transpose()
     }
 
     /// Get the starting offset of the block containing a given position.
@@ -174,15 +1371,17 @@ impl RemoteReader
         (pos / self.block_size) * self.block_size
     }
 
-    /// Fetch a block from the remote server.
+    /// Fetch consecutive blocks from the remote server in one range request.
     ///
     /// # Arguments
     ///
-    /// * `offset` - Starting offset of the block
-    fn fetch_block(&self, offset: u64) -> io::Result<CachedBlock>
+    /// * `offset` - Starting offset of the first block
+    /// * `blocks` - Number of blocks
+    fn fetch_blocks(&self, offset: u64, blocks: u64) -> io::Result<Vec<u8>>
     {
         let file_size = self.get_file_size()?;
-        let end = std::cmp::min(offset + self.block_size - 1, file_size.saturating_sub(1));
+        let length = blocks.max(1) * self.block_size;
+        let end = std::cmp::min(offset + length - 1, file_size.saturating_sub(1));
 
         let range = if offset >= file_size
         {
@@ -198,98 +1397,230 @@ impl RemoteReader
             format!("bytes={}-{}", offset, end)
         };
 
-        let response = self
-            .agent
-            .get(&self.url)
-            .header("Range", &range)
-            .call()
-            .map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::ConnectionRefused,
-                    format!("HTTP GET request failed: {}", e),
-                )
-            })?;
-
-        // Check for partial content or OK status
-        let status = response.status();
-        if status != 206 && status != 200
+        if let Some(data) = self.read_spill(offset, length)?
         {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Unexpected HTTP status: {}", status),
-            ));
+            return Ok(data);
         }
 
-        let data = response.into_body().read_to_vec().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::ConnectionRefused,
-                format!("Failed to read response body: {}", e),
-            )
+        let expected = end.saturating_sub(offset) + 1;
+        let whole_file = offset == 0 && expected >= file_size;
+        let data = self.config.retry.run(|timeout| {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            let response = self
+                .config
+                .prepare(self.agent.get(&self.url).header("Range", &range), timeout)
+                .call()
+                .map_err(|e| request_failure(e, "GET"))?;
+
+            // Check for partial content or OK status
+            let status = response.status().as_u16();
+            if status != 206 && status != 200
+            {
+                return Err(status_failure(&response, &self.url));
+            }
+            if status == 200 && !whole_file
+            {
+                // The server ignored the range and sends the whole file
+                let dir = self.config.spill_dir.as_deref();
+                let dir = dir.ok_or_else(|| Failure::fatal(ranges_unsupported(&self.url)))?;
+                let spill = self.spill_response(dir, response)?;
+                *self.spill.lock().map_err(|_| {
+                    Failure::fatal(io::Error::other("Spill lock poisoned"))
+                })? = Some(spill);
+                return Ok(None);
+            }
+
+            let data = response
+                .into_body()
+                .into_with_config()
+                .limit(u64::MAX)
+                .read_to_vec()
+                .map_err(|e| match e
+                {
+                    ureq::Error::Io(e) => body_failure(e, "GET"),
+                    e => request_failure(e, "GET"),
+                })?;
+
+            if status == 206 && (data.len() as u64) < expected
+            {
+                return Err(Failure::transient(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "Truncated response for {}: got {} of {} bytes",
+                        range,
+                        data.len(),
+                        expected
+                    ),
+                )));
+            }
+            Ok(Some(data))
         })?;
 
-        Ok(CachedBlock { offset, data })
+        match data
+        {
+            Some(data) => Ok(data),
+            None => Ok(self.read_spill(offset, length)?.unwrap_or_default()),
+        }
+    }
+
+    /// Count how many blocks from `offset` on, up to `max`, are not cached yet.
+    fn uncached_run(&self, offset: u64, max: u64) -> io::Result<u64>
+    {
+        let file_size = self.get_file_size()?;
+        let cache = self.cache.lock().map_err(|_| io::Error::other("Cache lock poisoned"))?;
+        let mut blocks = 0;
+        while blocks < max
+        {
+            let block = offset + blocks * self.block_size;
+            if block >= file_size || cache.contains(&block) || self.on_disk(block)
+            {
+                break;
+            }
+            blocks += 1;
+        }
+        Ok(blocks)
+    }
+
+    /// Split fetched data starting at block `offset` into blocks and cache them.
+    fn cache_blocks(&self, offset: u64, mut data: Vec<u8>) -> io::Result<()>
+    {
+        let mut cache = self.cache.lock().map_err(|_| io::Error::other("Cache lock poisoned"))?;
+        let blocks = (data.len() as u64).div_ceil(self.block_size);
+        // Split from the back so that every block is moved, not copied
+        for i in (0..blocks).rev()
+        {
+            let block_start = offset + i * self.block_size;
+            let block = data.split_off((i * self.block_size) as usize);
+            if let Some((disk, entry)) = &self.disk
+            {
+                // The disk cache is best effort
+                let _ = disk.write(entry, &self.block_file_name(block_start), &block);
+            }
+            let size = block.len() as u64;
+            cache.insert(block_start, block, size);
+        }
+        Ok(())
+    }
+
+    /// Name of the disk cache file for a block.
+    fn block_file_name(&self, block_start: u64) -> String
+    {
+        format!("{}-{}", self.block_size, block_start)
+    }
+
+    /// Check whether a block is in the disk cache.
+    fn on_disk(&self, block_start: u64) -> bool
+    {
+        match &self.disk
+        {
+            Some((disk, entry)) => disk.contains(entry, &self.block_file_name(block_start)),
+            None => false,
+        }
+    }
+
+    /// Load a complete block from the disk cache.
+    fn read_disk_block(&self, block_start: u64) -> Option<Vec<u8>>
+    {
+        let (disk, entry) = self.disk.as_ref()?;
+        let expected = self.file_size?.saturating_sub(block_start).min(self.block_size);
+        disk.read(entry, &self.block_file_name(block_start))
+            .filter(|block| block.len() as u64 == expected)
     }
 
-    /// Get data at a specific offset, using cache if available.
+    /// Copy data at a specific offset into `buf`, using cache if available.
+    ///
+    /// Reads at most up to the end of the block containing `offset`.
     ///
     /// # Arguments
     ///
     /// * `offset` - Position in the file to read from
+    /// * `buf` - Destination buffer
+    /// * `read_ahead` - Number of following blocks to fetch along on a cache miss
     ///
     /// # Returns
     ///
-    /// A slice containing the cached block data
-    fn get_data_at(&self, offset: u64) -> io::Result<Vec<u8>>
+    /// The number of bytes copied
+    fn copy_from_block(&self, offset: u64, buf: &mut [u8], read_ahead: u64) -> io::Result<usize>
     {
         let block_start = self.block_start(offset);
+        let offset_in_block = (offset - block_start) as usize;
+
+        let copy = |block: &[u8], buf: &mut [u8]| {
+            let available = block.get(offset_in_block..).unwrap_or(&[]);
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
 
-        // Check if we need to fetch the block
-        if !self
+        if let Some(block) = self
             .cache
             .lock()
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Cache lock poisoned"))?
-            .contains_key(&block_start)
+            .map_err(|_| io::Error::other("Cache lock poisoned"))?
+            .get(&block_start)
+        {
+            return Ok(copy(block, buf));
+        }
+
+        if let Some(block) = self.read_disk_block(block_start)
         {
-            // Fetch the block
-            let block = self.fetch_block(block_start)?;
-            let mut cache = self
-                .cache
+            let n = copy(&block, buf);
+            let size = block.len() as u64;
+            self.cache
                 .lock()
-                .map_err(|_| io::Error::new(io::ErrorKind::Other, "Cache lock poisoned"))?;
-            cache.insert(block_start, block);
+                .map_err(|_| io::Error::other("Cache lock poisoned"))?
+                .insert(block_start, block, size);
+            return Ok(n);
         }
 
-        // Get the data from cache
-        let cache = self
-            .cache
-            .lock()
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Cache lock poisoned"))?;
-        let block = cache.get(&block_start).unwrap();
-        let offset_in_block = (offset - block_start) as usize;
-        Ok(block.data[offset_in_block..].to_vec())
+        // Fetch without holding the lock, coalescing the following uncached blocks
+        let blocks = self.uncached_run(block_start, 1 + read_ahead)?;
+        let data = self.fetch_blocks(block_start, blocks)?;
+        let n = copy(&data[..data.len().min(self.block_size as usize)], buf);
+        self.cache_blocks(block_start, data)?;
+        Ok(n)
     }
 }
 
-impl Read for RemoteReader
+impl RangeSource for RemoteReader
 {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    fn size(&self) -> io::Result<u64>
+    {
+        self.get_file_size()
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>
     {
         let file_size = self.get_file_size()?;
-        if self.pos >= file_size
+        if offset >= file_size
         {
             return Ok(0);
         }
 
-        let remaining = file_size - self.pos;
+        let remaining = file_size - offset;
         let to_read = std::cmp::min(buf.len() as u64, remaining) as usize;
 
-        // Fetch data for current position
-        let data = self.get_data_at(self.pos)?;
+        // Moving on to the next block means sequential access: read ahead
+        let block_start = self.block_start(offset);
+        let last_block = self.last_block.swap(block_start, Ordering::Relaxed);
+        let sequential = last_block != NO_BLOCK && last_block + self.block_size == block_start;
+        let read_ahead = if sequential { self.read_ahead } else { 0 };
 
-        let actual_read = std::cmp::min(to_read, data.len());
-        buf[..actual_read].copy_from_slice(&data[..actual_read]);
-        self.pos += actual_read as u64;
+        // Copy data for the offset straight from the cached block
+        self.copy_from_block(offset, &mut buf[..to_read], read_ahead)
+    }
+
+    fn prefetch(&self, start: u64, end: u64) -> io::Result<()>
+    {
+        RemoteReader::prefetch(self, start, end)
+    }
+}
 
+impl Read for RemoteReader
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+        let actual_read = RangeSource::read_at(self, self.pos, buf)?;
+        self.pos += actual_read as u64;
         Ok(actual_read)
     }
 }
@@ -307,7 +1638,7 @@ impl Seek for RemoteReader
             {
                 let size = file_size
                     .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Unknown file size"))?;
-                let offset_i64 = offset as i64;
+                let offset_i64 = offset;
                 if offset_i64 < 0
                 {
                     size.checked_sub(offset_i64.unsigned_abs()).ok_or_else(|| {
@@ -323,7 +1654,7 @@ impl Seek for RemoteReader
             }
             SeekFrom::Current(offset) =>
             {
-                let offset_i64 = offset as i64;
+                let offset_i64 = offset;
                 if offset_i64 < 0
                 {
                     self.pos
@@ -354,18 +1685,7 @@ mod tests
     fn test_block_start()
     {
         // Test block_start calculation without making HTTP requests
-        let url = "http://example.com/test";
-        let agent = Agent::new_with_defaults();
-
-        // Create a reader without probing file size
-        let reader = RemoteReader {
-            url: url.to_string(),
-            agent,
-            cache: Mutex::new(HashMap::new()),
-            pos: 0,
-            file_size: None,
-            block_size: DEFAULT_BLOCK_SIZE,
-        };
+        let reader = RemoteReader::offline(DEFAULT_BLOCK_SIZE, DEFAULT_CACHE_SIZE, None);
 
         assert_eq!(reader.block_start(0), 0);
         assert_eq!(reader.block_start(100), 0);
@@ -377,21 +1697,404 @@ mod tests
     #[test]
     fn test_block_start_custom_size()
     {
-        let url = "http://example.com/test";
-        let agent = Agent::new_with_defaults();
-
-        let reader = RemoteReader {
-            url: url.to_string(),
-            agent,
-            cache: Mutex::new(HashMap::new()),
-            pos: 0,
-            file_size: None,
-            block_size: 1024,
-        };
+        let reader = RemoteReader::offline(1024, DEFAULT_CACHE_SIZE, None);
 
         assert_eq!(reader.block_start(0), 0);
         assert_eq!(reader.block_start(100), 0);
         assert_eq!(reader.block_start(1024), 1024);
         assert_eq!(reader.block_start(2000), 1024);
     }
+
+    #[test]
+    fn test_read_from_cached_blocks()
+    {
+        let mut reader = RemoteReader::offline(1024, 2048, Some(3072));
+        for block in 0..3u64
+        {
+            let data: Vec<u8> = (0..1024u64).map(|i| ((block * 1024 + i) % 251) as u8).collect();
+            reader.cache.get_mut().unwrap().insert(block * 1024, data, 1024);
+        }
+
+        // Only the last two blocks fit; the first one was evicted
+        let stats = reader.cache_stats();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.evictions, 1);
+
+        // Reads stop at block boundaries and copy only the requested slice
+        reader.seek(SeekFrom::Start(2044)).unwrap();
+        let mut buf = [0u8; 10];
+        assert_eq!(reader.read(&mut buf).unwrap(), 4);
+        assert_eq!(buf[..4], [2044 % 251, 2045 % 251, 2046 % 251, 2047 % 251].map(|v| v as u8));
+        let mut buf = [0u8; 3];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [2048 % 251, 2049 % 251, 2050 % 251].map(|v| v as u8));
+
+        let stats = reader.cache_stats();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 0);
+    }
+
+    fn fast_retries(retries: u32) -> RetryPolicy
+    {
+        RetryPolicy::default()
+            .with_max_retries(retries)
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(10))
+            .with_jitter(false)
+    }
+
+    #[test]
+    fn test_retry_transient_failures()
+    {
+        use crate::test_server::{Fault, StubServer};
+
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let server = StubServer::new(data.clone());
+        let mut reader = RemoteReader::new(server.url.as_str())
+            .unwrap()
+            .with_retry_policy(fast_retries(3));
+        assert_eq!(server.request_count(), 1);
+
+        server.push_fault(Fault::Status(503, None));
+        server.push_fault(Fault::Truncate);
+        server.push_fault(Fault::Status(429, None));
+        reader.seek(SeekFrom::Start(70_000)).unwrap();
+        let mut buf = vec![0u8; 1000];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[70_000..71_000]);
+        assert_eq!(server.request_count(), 5);
+        assert!(server.requests()[4].contains("bytes=65536-131071"));
+
+        // Retries run out
+        server.push_fault(Fault::Status(500, None));
+        server.push_fault(Fault::Status(502, None));
+        server.push_fault(Fault::Status(503, None));
+        server.push_fault(Fault::Status(504, None));
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(err.to_string().contains("504"));
+        assert_eq!(server.request_count(), 9);
+    }
+
+    #[test]
+    fn test_parse_retry_after()
+    {
+        let date = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(date));
+        assert_eq!(
+            parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_445_412_480))
+        );
+
+        let now = date - Duration::from_secs(30);
+        let parse = |value| parse_retry_after(value, now);
+        assert_eq!(parse(" 120 "), Some(Duration::from_secs(120)));
+        assert_eq!(parse("Sun, 06 Nov 1994 08:49:37 GMT"), Some(Duration::from_secs(30)));
+        assert_eq!(parse("Sun, 06 Nov 1994 08:00:00 GMT"), Some(Duration::ZERO));
+
+        // Obsolete date forms and garbage fall back to the normal backoff
+        assert_eq!(parse("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parse("Sun Nov  6 08:49:37 1994"), None);
+        assert_eq!(parse("Sun, 06 Nov 1994 25:49:37 GMT"), None);
+        assert_eq!(parse("soon"), None);
+    }
+
+    #[test]
+    fn test_retry_after_and_timeouts()
+    {
+        use crate::test_server::{Fault, StubServer};
+
+        let server = StubServer::new(vec![b'A'; 1000]);
+        let mut reader = RemoteReader::new(server.url.as_str())
+            .unwrap()
+            .with_retry_policy(fast_retries(1));
+        let mut buf = [0u8; 10];
+
+        server.push_fault(Fault::Status(429, Some(1)));
+        let started = Instant::now();
+        reader.read_exact(&mut buf).unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1));
+
+        // Retry-After beyond the total timeout gives up immediately
+        let mut reader = reader.with_retry_policy(
+            fast_retries(1).with_total_timeout(Some(Duration::from_millis(500))),
+        );
+        reader.cache.get_mut().unwrap().clear();
+        server.push_fault(Fault::Status(503, Some(5)));
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        let mut reader = reader.with_retry_policy(
+            RetryPolicy::none().with_request_timeout(Some(Duration::from_millis(100))),
+        );
+        server.push_fault(Fault::Delay(Duration::from_millis(1000)));
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_error_kinds()
+    {
+        use crate::test_server::{Fault, StubServer};
+
+        let server = StubServer::new(vec![b'A'; 1000]);
+        server.push_fault(Fault::Status(404, None));
+        let err = RemoteReader::new(server.url.as_str()).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(server.request_count(), 1);
+
+        let mut reader = RemoteReader::new(server.url.as_str())
+            .unwrap()
+            .with_retry_policy(fast_retries(3));
+        let mut buf = [0u8; 10];
+        for (status, kind) in [
+            (401, io::ErrorKind::PermissionDenied),
+            (403, io::ErrorKind::PermissionDenied),
+            (410, io::ErrorKind::NotFound),
+        ]
+        {
+            let before = server.request_count();
+            server.push_fault(Fault::Status(status, None));
+            assert_eq!(reader.read(&mut buf).unwrap_err().kind(), kind);
+            assert_eq!(server.request_count(), before + 1, "status {} must not be retried", status);
+        }
+    }
+
+    #[test]
+    fn test_head_rejected()
+    {
+        use crate::test_server::{Fault, StubServer};
+
+        // Presigned URLs are signed for GET only, so HEAD is refused
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let server = StubServer::new(data.clone());
+        server.set_etag("v1");
+        for status in [403, 405]
+        {
+            server.reject_head(status);
+            let mut reader = RemoteReader::new(server.url.as_str()).unwrap();
+            assert_eq!(reader.seek(SeekFrom::End(0)).unwrap(), data.len() as u64);
+            let requests = server.requests();
+            assert!(requests.last().unwrap().to_ascii_lowercase().contains("range: bytes=0-0"));
+
+            let mut buf = [0u8; 100];
+            reader.seek(SeekFrom::Start(500)).unwrap();
+            reader.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, &data[500..600]);
+        }
+
+        // A GET refused as well is still reported
+        let server = StubServer::new(data);
+        server.reject_head(403);
+        // The first fault is taken by the rejected HEAD, the second by the GET
+        server.push_fault(Fault::Status(403, None));
+        server.push_fault(Fault::Status(403, None));
+        let err = RemoteReader::new(server.url.as_str()).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_config_headers_and_block_size()
+    {
+        use crate::test_server::StubServer;
+
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let server = StubServer::new(data.clone());
+        let config = RemoteConfig::default()
+            .with_bearer_token("tok123")
+            .with_header("X-Project", "genomes")
+            .with_user_agent("fastx-test/1.0")
+            .with_block_size(100)
+            .with_cache_size(1000);
+        let mut reader = RemoteReader::with_config(server.url.as_str(), &config).unwrap();
+        reader.seek(SeekFrom::Start(150)).unwrap();
+        let mut buf = [0u8; 100];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf[..], data[150..250]);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        for request in &requests
+        {
+            let request = request.to_ascii_lowercase();
+            assert!(request.contains("authorization: bearer tok123"), "{}", request);
+            assert!(request.contains("x-project: genomes"));
+            assert!(request.contains("user-agent: fastx-test/1.0"));
+        }
+        assert!(requests[1].to_ascii_lowercase().contains("range: bytes=100-199"));
+    }
+
+    #[test]
+    fn test_config_errors_and_basic_auth()
+    {
+        let config = RemoteConfig::default().with_basic_auth("Aladdin", "open sesame");
+        assert_eq!(
+            config.headers,
+            vec![("Authorization".to_string(), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==".to_string())]
+        );
+
+        let err = RemoteConfig::default().with_proxy("not a proxy://").build_agent().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = RemoteConfig::default()
+            .with_root_certificates_pem(b"no certificates here".to_vec())
+            .build_agent()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_read_ahead_and_prefetch()
+    {
+        use crate::test_server::StubServer;
+
+        let data: Vec<u8> = (0..1950u32).map(|i| (i % 251) as u8).collect();
+        let server = StubServer::new(data.clone());
+        let config = RemoteConfig::default().with_block_size(100).with_read_ahead(3);
+
+        // Sequential reads fetch 1 + 3 blocks per request after the first block
+        let mut reader = RemoteReader::with_config(server.url.as_str(), &config).unwrap();
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        assert_eq!(all, data);
+        assert_eq!(reader.request_count(), 6);
+        assert!(server.requests()[2].contains("bytes=100-499"));
+
+        // Prefetching a span coalesces the uncached blocks
+        let mut reader = RemoteReader::with_config(server.url.as_str(), &config).unwrap();
+        reader.seek(SeekFrom::Start(250)).unwrap();
+        let mut buf = [0u8; 10];
+        reader.read_exact(&mut buf).unwrap();
+        reader.prefetch(120, 1250).unwrap();
+        assert_eq!(reader.request_count(), 3);
+        let requests = server.requests();
+        assert!(requests[requests.len() - 2].contains("bytes=100-199"));
+        assert!(requests[requests.len() - 1].contains("bytes=300-1299"));
+        reader.seek(SeekFrom::Start(120)).unwrap();
+        let mut buf = vec![0u8; 1130];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[120..1250]);
+        assert_eq!(reader.request_count(), 3);
+
+        // Prefetching stops at the cache capacity
+        let config = config.with_cache_size(300);
+        let reader = RemoteReader::with_config(server.url.as_str(), &config).unwrap();
+        reader.prefetch(0, u64::MAX).unwrap();
+        assert_eq!(reader.request_count(), 1);
+        assert_eq!(reader.cache_stats().bytes, 300);
+
+        // Without a cache there is nowhere to keep prefetched blocks
+        let config = config.with_cache_size(0);
+        let reader = RemoteReader::with_config(server.url.as_str(), &config).unwrap();
+        reader.prefetch(0, u64::MAX).unwrap();
+        assert_eq!(reader.request_count(), 0);
+    }
+
+    #[test]
+    fn test_disk_cache()
+    {
+        use crate::test_server::StubServer;
+
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let server = StubServer::new(data.clone());
+        server.set_etag("v1");
+        let dir = std::env::temp_dir().join(format!("fastx-remote-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = RemoteConfig::default()
+            .with_block_size(100)
+            .with_read_ahead(0)
+            .with_disk_cache(DiskCache::open(&dir, 1 << 20).unwrap());
+
+        let read_range = |start: u64, len: usize| {
+            let mut reader = RemoteReader::with_config(server.url.as_str(), &config).unwrap();
+            reader.seek(SeekFrom::Start(start)).unwrap();
+            let mut buf = vec![0u8; len];
+            reader.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, &data[start as usize..start as usize + len]);
+            reader.request_count()
+        };
+
+        // A second reader, as in a new process, is served from disk
+        assert_eq!(read_range(50, 300), 4);
+        assert_eq!(read_range(50, 300), 0);
+        assert_eq!(read_range(250, 200), 1);
+
+        // Whole file downloads are validated with a HEAD request
+        let agent = config.build_agent().unwrap();
+        assert_eq!(fetch_url(&agent, &config, &server.url).unwrap(), data);
+        let gets = || server.requests().iter().filter(|r| r.starts_with("GET ")).count();
+        let before = gets();
+        assert_eq!(fetch_url(&agent, &config, &server.url).unwrap(), data);
+        assert_eq!(gets(), before);
+
+        // A changed ETag invalidates everything
+        server.set_etag("v2");
+        assert_eq!(read_range(50, 300), 4);
+        assert_eq!(fetch_url(&agent, &config, &server.url).unwrap(), data);
+        assert_eq!(gets(), before + 5);
+
+        // Without any validator the disk cache is not used
+        let server = StubServer::new(data.clone());
+        server.ignore_ranges();
+        server.omit_content_length();
+        let downloads = || {
+            let requests = server.requests();
+            let whole = |r: &&String| r.starts_with("GET ") && !r.to_lowercase().contains("range:");
+            requests.iter().filter(whole).count()
+        };
+        assert_eq!(fetch_url(&agent, &config, &server.url).unwrap(), data);
+        let before = downloads();
+        assert_eq!(fetch_url(&agent, &config, &server.url).unwrap(), data);
+        assert!(downloads() > before);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_servers_without_range_support()
+    {
+        use crate::test_server::StubServer;
+
+        let data: Vec<u8> = (0..5000u32).map(|i| (i % 239) as u8).collect();
+        let config = RemoteConfig::default()
+            .with_retry_policy(fast_retries(0))
+            .with_block_size(512)
+            .with_cache_size(0)
+            .with_read_ahead(0);
+        let read_range = |reader: &mut RemoteReader, offset: u64, len: usize| {
+            let mut buf = vec![0; len];
+            reader.seek(SeekFrom::Start(offset)).unwrap();
+            reader.read_exact(&mut buf).unwrap();
+            buf
+        };
+
+        // Missing Content-Length: the size comes from a one-byte range request
+        let server = StubServer::new(data.clone());
+        server.omit_content_length();
+        let mut reader = RemoteReader::with_config(server.url.clone(), &config).unwrap();
+        assert_eq!(reader.seek(SeekFrom::End(0)).unwrap(), data.len() as u64);
+        assert!(server.requests()[1].to_ascii_lowercase().contains("range: bytes=0-0"));
+        assert_eq!(read_range(&mut reader, 1000, 100), &data[1000..1100]);
+
+        // Ignored ranges: the first 200 response is spilled and serves all reads
+        let server = StubServer::new(data.clone());
+        server.ignore_ranges();
+        let mut reader = RemoteReader::with_config(server.url.clone(), &config).unwrap();
+        assert_eq!(read_range(&mut reader, 1000, 100), &data[1000..1100]);
+        let requests = server.request_count();
+        assert_eq!(read_range(&mut reader, 4000, 1000), &data[4000..]);
+        assert_eq!(read_range(&mut reader, 0, 10), &data[..10]);
+        assert_eq!(server.request_count(), requests);
+
+        // Neither: the file is downloaded when opened, to learn its size
+        server.omit_content_length();
+        let mut reader = RemoteReader::with_config(server.url.clone(), &config).unwrap();
+        assert_eq!(reader.seek(SeekFrom::End(0)).unwrap(), data.len() as u64);
+        assert_eq!(read_range(&mut reader, 2500, 500), &data[2500..3000]);
+
+        // Without a spill directory such servers are reported clearly
+        let config = config.with_spill_dir(None);
+        let err = RemoteReader::with_config(server.url.clone(), &config).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(err.to_string().contains("does not support range requests"));
+    }
 }
@@ -0,0 +1,523 @@
+//! Thread-safe indexed reader for concurrent fetches.
+//!
+//! [`SharedIndexedReader`] reads BGZF-compressed FASTA files with positioned
+//! reads instead of a seek cursor, so `fetch` and `fetch_range` take `&self`.
+//! Clones share the byte source, the FAI and GZI indexes and one cache of
+//! decompressed blocks, so a single reader can serve all threads of a program.
+
+use crate::alias::ChromAliases;
+use crate::bgzf::{
+    block_len_from_extra, decompress_block, extra_field_len, Block, BLOCK_HEADER_LEN,
+};
+use crate::cache::{CacheStats, LruCache};
+use crate::fai::{FaiEntry, FaiIndex};
+use crate::gzi::GziIndex;
+use crate::indexed::{find_index_file, SequenceFetch};
+use crate::source::{FileSource, RangeSource};
+use crate::FastX::{FastARecord, FastXRead};
+use std::io::{self, Cursor};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Default upper bound for cached decompressed blocks (32MB).
+pub const DEFAULT_CACHE_SIZE: u64 = 32 * 1024 * 1024;
+
+/// An indexed FASTA reader that can be shared between threads.
+///
+/// Cloning is cheap: clones share the source, the indexes and the block cache.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::shared::SharedIndexedReader;
+/// use std::path::Path;
+/// use std::thread;
+///
+/// let reader = SharedIndexedReader::from_path(Path::new("ref.fa.gz")).unwrap();
+/// thread::scope(|scope| {
+///     for chrom in ["chr1", "chr2", "chr3"]
+///     {
+///         let reader = &reader;
+///         scope.spawn(move || {
+///             let region = reader.fetch_range(chrom, 10_000, 20_000).unwrap();
+///             println!("{}: {} bp", chrom, region.len());
+///         });
+///     }
+/// });
+/// ```
+#[derive(Clone)]
+pub struct SharedIndexedReader
+{
+    source: Arc<dyn RangeSource>,
+    fai_index: Arc<FaiIndex>,
+    gzi_index: Arc<GziIndex>,
+    /// Decompressed blocks by compressed offset
+    cache: Arc<Mutex<LruCache<u64, Arc<Block>>>>,
+}
+
+impl SharedIndexedReader
+{
+    /// Create a reader from a BGZF-compressed source and its indexes.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The compressed FASTA file
+    /// * `fai_index` - The .fai index
+    /// * `gzi_index` - The .gzi index of the BGZF blocks
+    pub fn new(source: Arc<dyn RangeSource>, fai_index: FaiIndex, gzi_index: GziIndex) -> Self
+    {
+        Self {
+            source,
+            fai_index: Arc::new(fai_index),
+            gzi_index: Arc::new(gzi_index),
+            cache: Arc::new(Mutex::new(LruCache::new(DEFAULT_CACHE_SIZE))),
+        }
+    }
+
+    /// Open a BGZF-compressed FASTA file with its .fai and .gzi indexes.
+    ///
+    /// The indexes are looked up like in
+    /// [`IndexedFastXReader::from_path`](crate::indexed::IndexedFastXReader::from_path).
+    pub fn from_path(path: &Path) -> io::Result<Self>
+    {
+        let index = |ext: &str| {
+            find_index_file(path, ext).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "{} index not found for {} (expected {}.{} or {}.{})",
+                        ext.to_uppercase(),
+                        path.display(),
+                        path.display(),
+                        ext,
+                        path.with_extension("").display(),
+                        ext
+                    ),
+                )
+            })
+        };
+        let fai_index = FaiIndex::from_path(&index("fai")?)?;
+        let gzi_index = GziIndex::from_path(&index("gzi")?)?;
+        Ok(Self::new(Arc::new(FileSource::open(path)?), fai_index, gzi_index))
+    }
+
+    /// Open a BGZF-compressed FASTA file and its indexes from URLs.
+    ///
+    /// Supports the same schemes as
+    /// [`IndexedFastXReader::from_url`](crate::indexed::IndexedFastXReader::from_url).
+    #[cfg(feature = "url")]
+    pub fn from_url(
+        data_url: &str,
+        fai_url: &str,
+        gzi_url: &str,
+        config: &crate::remote::RemoteConfig,
+    ) -> io::Result<Self>
+    {
+        use crate::indexed::{parse_fai_from_bytes, parse_gzi_from_bytes};
+        use crate::source::{open_url_with_agent, read_url};
+
+        let agent = config.build_agent()?;
+        let fai_index = parse_fai_from_bytes(&read_url(&agent, config, fai_url)?)?;
+        let gzi_index = parse_gzi_from_bytes(&read_url(&agent, config, gzi_url)?)?;
+        let source = Arc::clone(open_url_with_agent(data_url, Some(agent), config)?.source());
+        Ok(Self::new(source, fai_index, gzi_index))
+    }
+
+    /// Set the maximum number of bytes of decompressed blocks kept in the cache.
+    ///
+    /// The cache is shared with all clones of this reader.
+    pub fn with_cache_size(self, bytes: u64) -> Self
+    {
+        if let Ok(mut cache) = self.cache.lock()
+        {
+            cache.set_capacity(bytes);
+        }
+        self
+    }
+
+    /// Look sequences up by alternative names too.
+    ///
+    /// See [`IndexedFastXReader::with_aliases`](crate::indexed::IndexedFastXReader::with_aliases).
+    pub fn with_aliases(mut self, aliases: ChromAliases) -> Self
+    {
+        let fai_index = Arc::make_mut(&mut self.fai_index);
+        fai_index.aliases = Some(aliases);
+        self
+    }
+
+    /// Get the block cache counters of this reader and its clones.
+    pub fn cache_stats(&self) -> CacheStats
+    {
+        self.cache.lock().map(|cache| cache.stats()).unwrap_or_default()
+    }
+
+    /// Get a reference to the FAI index.
+    pub fn index(&self) -> &FaiIndex
+    {
+        &self.fai_index
+    }
+
+    /// Get a reference to the GZI index.
+    pub fn gzi_index(&self) -> &GziIndex
+    {
+        &self.gzi_index
+    }
+
+    /// Check if a sequence exists in the index.
+    pub fn contains(&self, seq_id: &str) -> bool
+    {
+        self.fai_index.contains(seq_id)
+    }
+
+    /// Get all sequence names in the index.
+    pub fn sequence_names(&self) -> Vec<&str>
+    {
+        self.fai_index.sequence_names().collect()
+    }
+
+    /// Fetch a sequence by its ID.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(FastARecord)` - The fetched sequence record
+    /// * `Err(io::Error)` - If the sequence is not found or reading fails
+    pub fn fetch(&self, seq_id: &str) -> io::Result<FastARecord>
+    {
+        let entry = self.entry(seq_id)?;
+        let (span_start, span_end) = record_span(entry);
+        record_from_span(entry, span_start, &self.read_uncompressed(span_start, span_end)?)
+    }
+
+    /// Fetch the bases in `[start, end)` of a sequence, `end` clamped to its length.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<u8>)` - The sequence data for the requested region
+    /// * `Err(io::Error)` - If the sequence is not found or reading fails
+    pub fn fetch_range(&self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
+    {
+        let entry = self.entry(seq_id)?;
+        let (span_start, span_end, bases) = range_span(entry, start, end)?;
+        span_bases(self.read_uncompressed(span_start, span_end)?, bases)
+    }
+
+    /// Read the uncompressed bytes `[start, end)` of the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<u8>)` - The bytes, fewer if the file ends before `end`
+    /// * `Err(io::Error)` - If reading or decompressing fails
+    pub fn read_uncompressed(&self, start: u64, end: u64) -> io::Result<Vec<u8>>
+    {
+        if start >= end
+        {
+            return Ok(Vec::new());
+        }
+        let (compressed_start, compressed_end) = self.gzi_index.compressed_range(start, end);
+        self.source.prefetch(compressed_start, compressed_end.unwrap_or(u64::MAX))?;
+
+        let file_size = self.source.size()?;
+        let (offset, block_start) = block_containing(&self.gzi_index, start);
+        collect_blocks(offset, block_start, start, end, file_size, |offset| self.block(offset))
+    }
+
+    /// Look up the FAI entry of a sequence.
+    fn entry(&self, seq_id: &str) -> io::Result<&FaiEntry>
+    {
+        self.fai_index.get(seq_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Sequence '{}' not found in index", seq_id),
+            )
+        })
+    }
+
+    /// Get the decompressed block at a compressed offset, from the cache if possible.
+    ///
+    /// Blocks are read and decompressed without holding the cache lock, so
+    /// threads only wait for each other on cache lookups.
+    fn block(&self, offset: u64) -> io::Result<Arc<Block>>
+    {
+        if let Some(block) = self.lock_cache()?.get(&offset)
+        {
+            return Ok(Arc::clone(block));
+        }
+
+        let mut header = [0u8; BLOCK_HEADER_LEN];
+        self.source.read_exact_at(offset, &mut header)?;
+        let mut extra = vec![0u8; extra_field_len(&header)?];
+        self.source.read_exact_at(offset + BLOCK_HEADER_LEN as u64, &mut extra)?;
+        let mut raw = vec![0u8; block_len_from_extra(&extra)?];
+        self.source.read_exact_at(offset, &mut raw)?;
+
+        let data = decompress_block(&raw)?;
+        let block = Arc::new(Block { data, compressed_len: raw.len() as u64 });
+        let size = block.data.len() as u64;
+        self.lock_cache()?.insert(offset, Arc::clone(&block), size);
+        Ok(block)
+    }
+
+    fn lock_cache(&self) -> io::Result<MutexGuard<'_, LruCache<u64, Arc<Block>>>>
+    {
+        self.cache.lock().map_err(|_| io::Error::other("Cache lock poisoned"))
+    }
+}
+
+impl SequenceFetch for SharedIndexedReader
+{
+    fn fetch(&mut self, seq_id: &str) -> io::Result<FastARecord>
+    {
+        SharedIndexedReader::fetch(self, seq_id)
+    }
+
+    fn fetch_range(&mut self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
+    {
+        SharedIndexedReader::fetch_range(self, seq_id, start, end)
+    }
+
+    fn sequence_length(&mut self, seq_id: &str) -> io::Result<u64>
+    {
+        self.entry(seq_id).map(|entry| entry.length)
+    }
+
+    fn contains(&self, seq_id: &str) -> bool
+    {
+        SharedIndexedReader::contains(self, seq_id)
+    }
+
+    fn sequence_names(&self) -> Vec<&str>
+    {
+        SharedIndexedReader::sequence_names(self)
+    }
+}
+
+/// Compressed and uncompressed offset of the block containing `pos`.
+pub(crate) fn block_containing(gzi_index: &GziIndex, pos: u64) -> (u64, u64)
+{
+    // The first block is implicit in .gzi files written by bgzip
+    let entries = &gzi_index.entries;
+    match entries.partition_point(|&(_, uncompressed)| uncompressed <= pos)
+    {
+        0 => (0, 0),
+        i => entries[i - 1],
+    }
+}
+
+/// Collect the uncompressed bytes `[start, end)` from consecutive blocks.
+///
+/// # Arguments
+///
+/// * `offset` - Compressed offset of the block containing `start`
+/// * `block_start` - Uncompressed offset of that block
+/// * `limit` - Compressed offset where the data ends
+/// * `block` - Get the decompressed block at a compressed offset
+pub(crate) fn collect_blocks(
+    mut offset: u64,
+    mut block_start: u64,
+    start: u64,
+    end: u64,
+    limit: u64,
+    mut block: impl FnMut(u64) -> io::Result<Arc<Block>>,
+) -> io::Result<Vec<u8>>
+{
+    // `end` may lie far past the end of the file, so only pre-allocate up to 16 MB
+    let mut data = Vec::with_capacity(end.saturating_sub(start).min(1 << 24) as usize);
+    while block_start < end && offset < limit
+    {
+        let block = block(offset)?;
+        let block_end = block_start + block.data.len() as u64;
+        if block_end > start
+        {
+            let from = start.saturating_sub(block_start) as usize;
+            let to = (end.min(block_end) - block_start) as usize;
+            data.extend_from_slice(&block.data[from..to]);
+        }
+        offset += block.compressed_len;
+        block_start = block_end;
+    }
+    Ok(data)
+}
+
+/// The uncompressed span holding the bases `[start, end)` of a sequence.
+///
+/// # Returns
+///
+/// `(span_start, span_end, bases)`, where `bases` is the number of bases in the
+/// range with `end` clamped to the sequence length.
+pub(crate) fn range_span(entry: &FaiEntry, start: u64, end: u64) -> io::Result<(u64, u64, u64)>
+{
+    if start >= entry.length
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Start position {} beyond sequence length {}", start, entry.length),
+        ));
+    }
+    let clamped_end = end.min(entry.length);
+    let span_end = entry.offset_for_position(clamped_end - 1) + 1;
+    Ok((entry.offset_for_position(start), span_end, clamped_end - start))
+}
+
+/// Drop the line breaks from a span read for [`range_span`], checking that
+/// `bases` bases remain.
+pub(crate) fn span_bases(mut span: Vec<u8>, bases: u64) -> io::Result<Vec<u8>>
+{
+    span.retain(|&b| b != b'\n' && b != b'\r');
+    if span.len() as u64 != bases
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Unexpected end of file while reading sequence",
+        ));
+    }
+    Ok(span)
+}
+
+/// The uncompressed span holding the header line and the bases of a sequence.
+pub(crate) fn record_span(entry: &FaiEntry) -> (u64, u64)
+{
+    // Search up to 4KB backwards from the sequence data for its header line
+    const MAX_HEADER_SEARCH: u64 = 4096;
+
+    let span_start = entry.offset.saturating_sub(MAX_HEADER_SEARCH);
+    (span_start, entry.offset_for_position(entry.length.saturating_sub(1)) + 1)
+}
+
+/// Parse the record of a sequence from the span read for [`record_span`].
+pub(crate) fn record_from_span(
+    entry: &FaiEntry,
+    span_start: u64,
+    data: &[u8],
+) -> io::Result<FastARecord>
+{
+    let before_seq = data.get(..(entry.offset - span_start) as usize).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::UnexpectedEof, "Unexpected end of file before sequence")
+    })?;
+    let header_start = before_seq.iter().rposition(|&b| b == b'>').ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Could not find FASTA header for sequence '{}'", entry.name),
+        )
+    })?;
+
+    let mut record = FastARecord::default();
+    record.read(&mut Cursor::new(&data[header_start..]))?;
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::bgzf::TestReference;
+    use crate::indexed::IndexedFastXReader;
+
+    /// Write two sequences with 60 bases per line, in BGZF blocks of 700 bytes, and
+    /// their indexes to `ref.fa.gz` in a new temporary directory.
+    fn write_reference(name: &str) -> (std::path::PathBuf, Vec<(&'static str, Vec<u8>)>)
+    {
+        let mut x: u32 = 7;
+        let mut bases = |n: usize| -> Vec<u8> {
+            (0..n)
+                .map(|_| {
+                    x = x.wrapping_mul(1103515245).wrapping_add(12345);
+                    b"ACGT"[(x >> 16) as usize % 4]
+                })
+                .collect()
+        };
+        let seqs = vec![("chr1", bases(5000)), ("chr2", bases(3333))];
+        (TestReference::new(&seqs, 700).write_to_temp_dir(name), seqs)
+    }
+
+    #[test]
+    fn test_concurrent_fetches()
+    {
+        fn assert_sync<T: Send + Sync>() {}
+        assert_sync::<SharedIndexedReader>();
+
+        let (dir, seqs) = write_reference("fastx-shared");
+        let path = dir.join("ref.fa.gz");
+        let reader = SharedIndexedReader::from_path(&path).unwrap();
+        std::thread::scope(|scope| {
+            for t in 0..4u64
+            {
+                let reader = reader.clone();
+                let seqs = &seqs;
+                scope.spawn(move || {
+                    for i in 0..20u64
+                    {
+                        let (name, seq) = &seqs[((t + i) % 2) as usize];
+                        let start = (t * 397 + i * 131) % (seq.len() as u64 - 1);
+                        let end = start + 1 + (i * 89) % 700;
+                        let expected = &seq[start as usize..(end as usize).min(seq.len())];
+                        assert_eq!(reader.fetch_range(name, start, end).unwrap(), expected);
+                    }
+                });
+            }
+        });
+        let stats = reader.cache_stats();
+        assert!(stats.hits > 0);
+        assert!(stats.entries > 0);
+
+        // Whole records match the cursor-based reader
+        let mut indexed = IndexedFastXReader::from_path(&path).unwrap();
+        for (name, seq) in &seqs
+        {
+            let record = reader.fetch(name).unwrap();
+            assert_eq!(record.seq(), *seq);
+            assert_eq!(record.id(), indexed.fetch(name).unwrap().id());
+        }
+        assert_eq!(reader.fetch_range("chr2", 3300, 4000).unwrap(), &seqs[1].1[3300..]);
+        assert_eq!(reader.fetch("chr3").err().unwrap().kind(), io::ErrorKind::NotFound);
+        let all = reader.read_uncompressed(0, u64::MAX).unwrap();
+        assert!(all.starts_with(b">chr1\n"));
+        assert!(all.ends_with(&[*seqs[1].1.last().unwrap(), b'\n']));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_fetches_match_sequential()
+    {
+        let (dir, seqs) = write_reference("fastx-shared-stress");
+        let path = dir.join("ref.fa.gz");
+
+        // Expected results from one sequential reader
+        let mut x: u64 = 11;
+        let ranges: Vec<(&str, u64, u64)> = (0..400)
+            .map(|_| {
+                x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let (name, seq) = &seqs[(x >> 63) as usize];
+                let start = (x >> 20) % seq.len() as u64;
+                let end = start + 1 + (x >> 40) % 2500;
+                (*name, start, end)
+            })
+            .collect();
+        let mut sequential = IndexedFastXReader::from_path(&path).unwrap();
+        let expected: Vec<Vec<u8>> = ranges
+            .iter()
+            .map(|&(name, start, end)| sequential.fetch_range(name, start, end).unwrap())
+            .collect();
+
+        // A cache of a few blocks makes threads evict each other's blocks
+        let reader = SharedIndexedReader::from_path(&path).unwrap().with_cache_size(3 * 700);
+        std::thread::scope(|scope| {
+            for t in 0..8usize
+            {
+                let reader = reader.clone();
+                let (ranges, expected) = (&ranges, &expected);
+                scope.spawn(move || {
+                    for i in (0..ranges.len()).map(|i| (i * 7 + t * 50) % ranges.len())
+                    {
+                        let (name, start, end) = ranges[i];
+                        let fetched = reader.fetch_range(name, start, end).unwrap();
+                        assert_eq!(fetched, expected[i], "{}:{}-{}", name, start, end);
+                    }
+                });
+            }
+        });
+        assert!(reader.cache_stats().evictions > 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,398 @@
+//! Random-access byte sources behind a common interface.
+//!
+//! A [`RangeSource`] reads bytes at arbitrary offsets of a local or remote file.
+//! [`SourceReader`] turns any source into a `Read + Seek` reader, so the BGZF and
+//! indexed readers work the same on local files, HTTP(S) servers and S3-compatible
+//! object storage.
+//!
+//! With the `url` feature, [`open_url`] picks the backend from the URL scheme:
+//!
+//! - `file://` - [`FileSource`]
+//! - `http://`, `https://` - [`RemoteReader`](crate::remote::RemoteReader)
+//! - `s3://bucket/key` - `RemoteReader` with signed requests, see [`S3Config`](crate::s3::S3Config)
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+#[cfg(feature = "url")]
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A file that can be read at arbitrary offsets.
+///
+/// Reads take `&self`, so one source can be shared between readers.
+pub trait RangeSource: Send + Sync
+{
+    /// Total size of the file in bytes.
+    fn size(&self) -> io::Result<u64>;
+
+    /// Read bytes starting at `offset` into `buf`.
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes read, which may be less than `buf.len()`, and 0 at
+    /// or beyond the end of the file.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Read exactly `buf.len()` bytes starting at `offset`.
+    ///
+    /// Fails with `UnexpectedEof` if the file ends first.
+    fn read_exact_at(&self, mut offset: u64, mut buf: &mut [u8]) -> io::Result<()>
+    {
+        while !buf.is_empty()
+        {
+            match self.read_at(offset, buf)
+            {
+                Ok(0) =>
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("Unexpected end of file at offset {}", offset),
+                    ))
+                }
+                Ok(n) =>
+                {
+                    offset += n as u64;
+                    buf = &mut buf[n..];
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Hint that the bytes `[start, end)` will be read soon.
+    ///
+    /// Sources with slow requests fetch the span ahead in as few requests as
+    /// possible; the default does nothing.
+    fn prefetch(&self, _start: u64, _end: u64) -> io::Result<()>
+    {
+        Ok(())
+    }
+}
+
+/// A local file as a [`RangeSource`].
+///
+/// Reads use positional I/O (`pread` on Unix, `ReadFile` with an offset on
+/// Windows), so concurrent readers sharing one source do not wait for each other.
+pub struct FileSource
+{
+    file: File,
+    size: u64,
+}
+
+impl FileSource
+{
+    /// Open a local file.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self>
+    {
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { file, size })
+    }
+
+    /// Use an open file of a known size.
+    #[cfg(feature = "url")]
+    pub(crate) fn with_size(file: File, size: u64) -> Self
+    {
+        Self { file, size }
+    }
+}
+
+impl RangeSource for FileSource
+{
+    fn size(&self) -> io::Result<u64>
+    {
+        Ok(self.size)
+    }
+
+    #[cfg(unix)]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>
+    {
+        std::os::unix::fs::FileExt::read_at(&self.file, buf, offset)
+    }
+
+    #[cfg(windows)]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>
+    {
+        // Moves the file cursor, which no other read depends on
+        std::os::windows::fs::FileExt::seek_read(&self.file, buf, offset)
+    }
+}
+
+/// A `Read + Seek` reader over a shared [`RangeSource`].
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::bgzf::BgzfReader;
+/// use fastx::source::{FileSource, SourceReader};
+/// use std::io::Read;
+///
+/// let reader = SourceReader::new(FileSource::open("data.fasta.gz").unwrap()).unwrap();
+/// let mut bgzf = BgzfReader::new(reader);
+/// let mut text = String::new();
+/// bgzf.read_to_string(&mut text).unwrap();
+/// ```
+pub struct SourceReader
+{
+    source: Arc<dyn RangeSource>,
+    pos: u64,
+    size: u64,
+}
+
+impl SourceReader
+{
+    /// Create a reader positioned at the start of `source`.
+    pub fn new(source: impl RangeSource + 'static) -> io::Result<Self>
+    {
+        Self::from_arc(Arc::new(source))
+    }
+
+    /// Create a reader over a source shared with other readers.
+    pub fn from_arc(source: Arc<dyn RangeSource>) -> io::Result<Self>
+    {
+        let size = source.size()?;
+        Ok(Self { source, pos: 0, size })
+    }
+
+    /// Get the underlying source.
+    pub fn source(&self) -> &Arc<dyn RangeSource>
+    {
+        &self.source
+    }
+
+    /// Hint that the bytes `[start, end)` will be read soon.
+    pub fn prefetch(&self, start: u64, end: u64) -> io::Result<()>
+    {
+        self.source.prefetch(start, end)
+    }
+}
+
+impl Read for SourceReader
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+        if self.pos >= self.size || buf.is_empty()
+        {
+            return Ok(0);
+        }
+        let n = self.source.read_at(self.pos, buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for SourceReader
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64>
+    {
+        let (base, offset) = match pos
+        {
+            SeekFrom::Start(n) =>
+            {
+                self.pos = n;
+                return Ok(n);
+            }
+            SeekFrom::End(offset) => (self.size, offset),
+            SeekFrom::Current(offset) => (self.pos, offset),
+        };
+        self.pos = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Seek before file start")
+        })?;
+        Ok(self.pos)
+    }
+}
+
+/// The local path of a `file://` URL.
+///
+/// Only local URLs (`file:///path` or `file://localhost/path`) are accepted;
+/// percent-escapes in the path are decoded.
+#[cfg(feature = "url")]
+pub(crate) fn file_url_path(url: &str) -> Option<PathBuf>
+{
+    let rest = url.strip_prefix("file://")?;
+    let path = rest.strip_prefix("localhost").unwrap_or(rest);
+    if !path.starts_with('/')
+    {
+        return None;
+    }
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len()
+    {
+        let escaped = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped
+        {
+            Some(byte) =>
+            {
+                decoded.push(byte);
+                i += 3;
+            }
+            None =>
+            {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    Some(PathBuf::from(String::from_utf8_lossy(&decoded).into_owned()))
+}
+
+/// Open a URL as a seekable reader, choosing the backend by scheme.
+///
+/// # Arguments
+///
+/// * `url` - A `file://`, `http://`, `https://` or `s3://` URL
+/// * `config` - Connection settings for remote backends
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::remote::RemoteConfig;
+/// use fastx::source::open_url;
+///
+/// let local = open_url("file:///data/ref.fa.gz", &RemoteConfig::default()).unwrap();
+/// let remote = open_url("s3://genomes/ref.fa.gz", &RemoteConfig::default()).unwrap();
+/// ```
+#[cfg(feature = "url")]
+pub fn open_url(url: &str, config: &crate::remote::RemoteConfig) -> io::Result<SourceReader>
+{
+    open_url_with_agent(url, None, config)
+}
+
+/// Open a URL, reusing `agent` for HTTP requests if given.
+#[cfg(feature = "url")]
+pub(crate) fn open_url_with_agent(
+    url: &str,
+    agent: Option<ureq::Agent>,
+    config: &crate::remote::RemoteConfig,
+) -> io::Result<SourceReader>
+{
+    use crate::remote::RemoteReader;
+
+    match resolve_url(url, config)?
+    {
+        Location::File(path) => SourceReader::new(FileSource::open(path)?),
+        Location::Http(url, config) =>
+        {
+            let agent = match agent
+            {
+                Some(agent) => agent,
+                None => config.build_agent()?,
+            };
+            SourceReader::new(RemoteReader::with_agent(url, agent, &config)?)
+        }
+    }
+}
+
+/// Read a whole (small) file, such as an index, from a URL of any supported scheme.
+#[cfg(feature = "url")]
+pub(crate) fn read_url(
+    agent: &ureq::Agent,
+    config: &crate::remote::RemoteConfig,
+    url: &str,
+) -> io::Result<Vec<u8>>
+{
+    match resolve_url(url, config)?
+    {
+        Location::File(path) => std::fs::read(path),
+        Location::Http(url, config) => crate::remote::fetch_url(agent, &config, &url),
+    }
+}
+
+/// Check that a file exists at a URL of any supported scheme.
+#[cfg(feature = "url")]
+pub(crate) fn url_exists(
+    agent: &ureq::Agent,
+    config: &crate::remote::RemoteConfig,
+    url: &str,
+) -> io::Result<()>
+{
+    match resolve_url(url, config)?
+    {
+        Location::File(path) => std::fs::metadata(path).map(|_| ()),
+        Location::Http(url, config) => crate::remote::probe(agent, &config, &url),
+    }
+}
+
+/// Where the bytes of a URL come from.
+#[cfg(feature = "url")]
+pub(crate) enum Location
+{
+    File(PathBuf),
+    /// An HTTP(S) URL and the settings to request it with
+    Http(String, Box<crate::remote::RemoteConfig>),
+}
+
+/// Map a URL to a local path or an HTTP(S) URL.
+///
+/// `s3://` URLs become requests to the configured S3 endpoint, or to the one
+/// described by the `AWS_*` environment variables.
+#[cfg(feature = "url")]
+pub(crate) fn resolve_url(url: &str, config: &crate::remote::RemoteConfig) -> io::Result<Location>
+{
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+    match scheme.as_deref()
+    {
+        Some("file") => file_url_path(url).map(Location::File).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("Not a local file URL: {}", url))
+        }),
+        Some("http") | Some("https") =>
+        {
+            Ok(Location::Http(url.to_string(), Box::new(config.clone())))
+        }
+        Some("s3") =>
+        {
+            let config = config.clone().with_s3_defaults();
+            let s3 = config.s3_config().cloned().unwrap_or_default();
+            Ok(Location::Http(s3.object_url(url)?, Box::new(config)))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Unsupported URL scheme in {} (expected file, http, https or s3)", url),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_file_source_and_reader()
+    {
+        let path = std::env::temp_dir().join(format!("fastx-source-{}", std::process::id()));
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let mut reader = SourceReader::new(FileSource::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.seek(SeekFrom::End(-10)).unwrap(), 990);
+        let mut tail = Vec::new();
+        reader.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, &data[990..]);
+
+        reader.seek(SeekFrom::Start(100)).unwrap();
+        reader.seek(SeekFrom::Current(-50)).unwrap();
+        let mut buf = [0u8; 20];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[50..70]);
+        assert!(reader.seek(SeekFrom::Current(-100)).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_file_urls()
+    {
+        assert_eq!(file_url_path("file:///data/ref.fa.gz"), Some(PathBuf::from("/data/ref.fa.gz")));
+        assert_eq!(file_url_path("file://localhost/a%20b"), Some(PathBuf::from("/a b")));
+        assert_eq!(file_url_path("file://host/a"), None);
+    }
+}
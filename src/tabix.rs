@@ -0,0 +1,949 @@
+//! Tabix (.tbi) and CSI (.csi) indexed access to bgzipped tabular files.
+//!
+//! BED, GFF, VCF and other tab-separated files that are sorted by position,
+//! compressed with bgzip and indexed with `tabix` can be queried by region with a
+//! [`TabixReader`], which uses the same [`Region`] type and BGZF machinery as the
+//! indexed FASTA readers.
+//!
+//! Both index formats map a region to bins of a hierarchical binning scheme; each
+//! bin lists chunks of the file as pairs of BGZF virtual offsets. TBI indexes use
+//! fixed bins (16kb windows, 6 levels) plus a linear index; CSI indexes store the
+//! bin parameters and an offset per bin instead.
+
+use crate::bgzf::BgzfReader;
+use crate::indexed::find_index_file;
+use crate::region::Region;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, Cursor, Read, Seek};
+use std::path::Path;
+
+const TBI_MAGIC: &[u8; 4] = b"TBI\x01";
+const CSI_MAGIC: &[u8; 4] = b"CSI\x01";
+/// Bin parameters fixed by the TBI format
+const TBI_MIN_SHIFT: u32 = 14;
+const TBI_DEPTH: u32 = 5;
+/// Flag in the format field for 0-based, half-open coordinates (BED)
+const FLAG_ZERO_BASED: i32 = 0x10000;
+
+/// Kind of records in an indexed file, which decides how their end is found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabixFormat
+{
+    /// Columns for the sequence, start and (optionally) end
+    Generic,
+    /// SAM; the end is computed from the CIGAR string
+    Sam,
+    /// VCF; the end is computed from REF or the `END` INFO field
+    Vcf,
+}
+
+/// Column layout of an indexed file, as stored in the index header.
+///
+/// Column numbers are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabixConfig
+{
+    /// Record format
+    pub format: TabixFormat,
+    /// Whether coordinates are 0-based, half-open (BED) instead of 1-based, inclusive
+    pub zero_based: bool,
+    /// Column of the sequence name
+    pub seq_column: usize,
+    /// Column of the start position
+    pub start_column: usize,
+    /// Column of the end position, 0 if there is none
+    pub end_column: usize,
+    /// Lines starting with this character are comments
+    pub meta_char: u8,
+    /// Number of header lines at the start of the file
+    pub skip_lines: usize,
+}
+
+impl TabixConfig
+{
+    /// Get the sequence name and 0-based, half-open interval of a line.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((name, start, end))` - The interval covered by the record
+    /// * `Err(io::Error)` - If a column is missing or not a number
+    pub fn interval<'a>(&self, line: &'a str) -> io::Result<(&'a str, u64, u64)>
+    {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let field = |column: usize| {
+            column.checked_sub(1).and_then(|i| fields.get(i).copied()).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Missing column {} in line: {}", column, line),
+                )
+            })
+        };
+        let number = |column: usize| {
+            let text = field(column)?;
+            text.trim().parse::<u64>().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Column {} is not a position in line: {}", column, line),
+                )
+            })
+        };
+
+        let name = field(self.seq_column)?;
+        let mut start = number(self.start_column)?;
+        if !self.zero_based
+        {
+            start = start.saturating_sub(1);
+        }
+        // A 1-based, inclusive end equals the 0-based, exclusive one
+        let end = match self.format
+        {
+            TabixFormat::Sam => start + cigar_reference_len(field(6)?),
+            TabixFormat::Vcf =>
+            {
+                let info_end = fields.get(7).and_then(|info| {
+                    info.split(';').find_map(|kv| kv.strip_prefix("END=")?.parse::<u64>().ok())
+                });
+                match info_end
+                {
+                    Some(end) => end,
+                    None => start + field(self.start_column + 2)?.len() as u64,
+                }
+            }
+            TabixFormat::Generic if self.end_column > 0 => number(self.end_column)?,
+            TabixFormat::Generic => start + 1,
+        };
+        Ok((name, start, end.max(start + 1)))
+    }
+
+    /// Parse the tabix header fields: format, columns, meta character, skip and names.
+    fn parse(data: &mut Bytes) -> io::Result<(Self, Vec<String>)>
+    {
+        let format_field = data.i32()?;
+        let format = match format_field & 0xffff
+        {
+            0 => TabixFormat::Generic,
+            1 => TabixFormat::Sam,
+            2 => TabixFormat::Vcf,
+            other =>
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unknown tabix format {}", other),
+                ))
+            }
+        };
+        let config = Self {
+            format,
+            zero_based: format_field & FLAG_ZERO_BASED != 0,
+            seq_column: data.count()?,
+            start_column: data.count()?,
+            end_column: data.count()?,
+            meta_char: data.i32()? as u8,
+            skip_lines: data.count()?,
+        };
+
+        let names_len = data.count()?;
+        let names = data
+            .take(names_len)?
+            .split(|&b| b == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .collect();
+        Ok((config, names))
+    }
+}
+
+/// A chunk of the file between two virtual offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Chunk
+{
+    start: u64,
+    end: u64,
+}
+
+/// A bin and the chunks holding its records.
+#[derive(Debug, Clone, Default)]
+struct Bin
+{
+    /// Smallest virtual offset of records in the bin (CSI only)
+    loffset: u64,
+    chunks: Vec<Chunk>,
+}
+
+/// Bins and linear index of one sequence.
+#[derive(Debug, Clone, Default)]
+struct ReferenceIndex
+{
+    bins: HashMap<u32, Bin>,
+    /// Smallest virtual offset of records in each window (TBI only)
+    linear: Vec<u64>,
+}
+
+/// A parsed .tbi or .csi index.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::tabix::TabixIndex;
+/// use std::path::Path;
+///
+/// let index = TabixIndex::from_path(Path::new("genes.bed.gz.tbi")).unwrap();
+/// println!("{:?}", index.sequence_names());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TabixIndex
+{
+    config: TabixConfig,
+    names: Vec<String>,
+    name_ids: HashMap<String, usize>,
+    min_shift: u32,
+    depth: u32,
+    references: Vec<ReferenceIndex>,
+}
+
+impl TabixIndex
+{
+    /// Load a .tbi or .csi index from a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the index file
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TabixIndex)` - The parsed index
+    /// * `Err(io::Error)` - If the file cannot be read or the format is invalid
+    pub fn from_path(path: &Path) -> io::Result<Self>
+    {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Load a .tbi or .csi index from its (BGZF-compressed) bytes.
+    ///
+    /// The format is detected from the magic number.
+    pub fn from_bytes(buffer: &[u8]) -> io::Result<Self>
+    {
+        let mut decompressed = Vec::new();
+        BgzfReader::new(Cursor::new(buffer)).read_to_end(&mut decompressed)?;
+        let mut data = Bytes { data: &decompressed, pos: 0 };
+
+        match data.take(4)?
+        {
+            magic if magic == TBI_MAGIC => Self::parse_tbi(&mut data),
+            magic if magic == CSI_MAGIC => Self::parse_csi(&mut data),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Not a tabix or CSI index")),
+        }
+    }
+
+    /// Parse a TBI index after the magic number.
+    fn parse_tbi(data: &mut Bytes) -> io::Result<Self>
+    {
+        let n_references = data.count()?;
+        let (config, names) = TabixConfig::parse(data)?;
+
+        let mut references = Vec::with_capacity(n_references.min(1 << 16));
+        for _ in 0..n_references
+        {
+            let mut reference = ReferenceIndex::default();
+            for _ in 0..data.count()?
+            {
+                let bin_id = data.u32()?;
+                let chunks = data.chunks()?;
+                reference.bins.insert(bin_id, Bin { loffset: 0, chunks });
+            }
+            for _ in 0..data.count()?
+            {
+                reference.linear.push(data.u64()?);
+            }
+            references.push(reference);
+        }
+        Self::new(config, names, TBI_MIN_SHIFT, TBI_DEPTH, references)
+    }
+
+    /// Parse a CSI index after the magic number.
+    ///
+    /// Only CSI indexes of tabular files, which carry a tabix header in their
+    /// auxiliary data, are supported.
+    fn parse_csi(data: &mut Bytes) -> io::Result<Self>
+    {
+        let min_shift = data.count()? as u32;
+        let depth = data.count()? as u32;
+        if min_shift + 3 * depth > 63
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid CSI parameters: min_shift {}, depth {}", min_shift, depth),
+            ));
+        }
+        let aux_len = data.count()?;
+        let mut aux = Bytes { data: data.take(aux_len)?, pos: 0 };
+        let (config, names) = TabixConfig::parse(&mut aux).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "CSI index has no tabix header (not an index of a tabular file)",
+            )
+        })?;
+
+        let n_references = data.count()?;
+        let mut references = Vec::with_capacity(n_references.min(1 << 16));
+        for _ in 0..n_references
+        {
+            let mut reference = ReferenceIndex::default();
+            for _ in 0..data.count()?
+            {
+                let bin_id = data.u32()?;
+                let loffset = data.u64()?;
+                let chunks = data.chunks()?;
+                reference.bins.insert(bin_id, Bin { loffset, chunks });
+            }
+            references.push(reference);
+        }
+        Self::new(config, names, min_shift, depth, references)
+    }
+
+    fn new(
+        config: TabixConfig,
+        names: Vec<String>,
+        min_shift: u32,
+        depth: u32,
+        references: Vec<ReferenceIndex>,
+    ) -> io::Result<Self>
+    {
+        if names.len() != references.len()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Index has {} names for {} sequences", names.len(), references.len()),
+            ));
+        }
+        let name_ids = names.iter().enumerate().map(|(i, name)| (name.clone(), i)).collect();
+        Ok(Self { config, names, name_ids, min_shift, depth, references })
+    }
+
+    /// Get the column layout of the indexed file.
+    pub fn config(&self) -> &TabixConfig
+    {
+        &self.config
+    }
+
+    /// Check if a sequence has records in the index.
+    pub fn contains(&self, name: &str) -> bool
+    {
+        self.name_ids.contains_key(name)
+    }
+
+    /// Get all sequence names, in file order.
+    pub fn sequence_names(&self) -> Vec<&str>
+    {
+        self.names.iter().map(String::as_str).collect()
+    }
+
+    /// Get the merged chunks that may hold records overlapping `[start, end)`.
+    fn chunks(&self, name: &str, start: u64, end: u64) -> Vec<Chunk>
+    {
+        let Some(&id) = self.name_ids.get(name)
+        else
+        {
+            return Vec::new();
+        };
+        let reference = &self.references[id];
+        let max_end = 1u64 << (self.min_shift + 3 * self.depth);
+        let end = end.min(max_end);
+        if start >= end
+        {
+            return Vec::new();
+        }
+
+        let min_offset = self.min_offset(reference, start);
+        let mut chunks: Vec<Chunk> = self
+            .overlapping_bins(start, end)
+            .filter_map(|bin| reference.bins.get(&bin))
+            .flat_map(|bin| bin.chunks.iter().copied())
+            .filter(|chunk| chunk.end > min_offset)
+            .collect();
+        chunks.sort_unstable_by_key(|chunk| chunk.start);
+
+        let mut merged: Vec<Chunk> = Vec::with_capacity(chunks.len());
+        for mut chunk in chunks
+        {
+            chunk.start = chunk.start.max(min_offset);
+            match merged.last_mut()
+            {
+                Some(last) if chunk.start <= last.end => last.end = last.end.max(chunk.end),
+                _ => merged.push(chunk),
+            }
+        }
+        merged
+    }
+
+    /// Smallest virtual offset of records that can overlap `start`.
+    fn min_offset(&self, reference: &ReferenceIndex, start: u64) -> u64
+    {
+        let window = (start >> self.min_shift) as usize;
+        if let Some(&last) = reference.linear.last()
+        {
+            return reference.linear.get(window).copied().unwrap_or(last);
+        }
+
+        // CSI: the offset of the smallest existing bin containing start
+        let mut bin = self.first_bin(self.depth) + window as u32;
+        loop
+        {
+            if let Some(found) = reference.bins.get(&bin)
+            {
+                return found.loffset;
+            }
+            if bin == 0
+            {
+                return 0;
+            }
+            bin = (bin - 1) >> 3;
+        }
+    }
+
+    /// Id of the first bin on a level of the binning scheme.
+    fn first_bin(&self, level: u32) -> u32
+    {
+        ((1u32 << (3 * level)) - 1) / 7
+    }
+
+    /// Ids of all bins overlapping `[start, end)`, from the root to the leaves.
+    fn overlapping_bins(&self, start: u64, end: u64) -> impl Iterator<Item = u32> + '_
+    {
+        let last = end - 1;
+        (0..=self.depth).flat_map(move |level| {
+            let shift = self.min_shift + 3 * (self.depth - level);
+            let first = self.first_bin(level) as u64;
+            (first + (start >> shift)..=first + (last >> shift)).map(|bin| bin as u32)
+        })
+    }
+}
+
+/// A bgzipped tabular file with a .tbi or .csi index.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::indexed::{IndexedFastXReader, SequenceFetch};
+/// use fastx::region::Region;
+/// use fastx::tabix::TabixReader;
+/// use std::path::Path;
+///
+/// let mut genome = IndexedFastXReader::from_path(Path::new("ref.fa.gz")).unwrap();
+/// let mut genes = TabixReader::from_path(Path::new("genes.bed.gz")).unwrap();
+///
+/// let region: Region = "chr1:1,000,001-1,010,000".parse().unwrap();
+/// let bases = genome.fetch_region(&region).unwrap();
+/// for line in genes.query(&region).unwrap()
+/// {
+///     println!("{}", line.unwrap());
+/// }
+/// ```
+pub struct TabixReader<R: Read + Seek>
+{
+    reader: BgzfReader<R>,
+    index: TabixIndex,
+}
+
+impl TabixReader<File>
+{
+    /// Open a bgzipped file with its index, `<path>.tbi` or `<path>.csi`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(reader)` - The indexed reader
+    /// * `Err(io::Error)` - If the file or an index cannot be opened
+    pub fn from_path(path: &Path) -> io::Result<Self>
+    {
+        let index_path = find_index_file(path, "tbi")
+            .or_else(|| find_index_file(path, "csi"))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No .tbi or .csi index found for {}", path.display()),
+                )
+            })?;
+        let index = TabixIndex::from_path(&index_path)?;
+        Ok(Self::new(File::open(path)?, index))
+    }
+}
+
+impl<R: Read + Seek> TabixReader<R>
+{
+    /// Create a reader from a bgzipped file and its index.
+    pub fn new(inner: R, index: TabixIndex) -> Self
+    {
+        Self { reader: BgzfReader::new(inner), index }
+    }
+
+    /// Get the index.
+    pub fn index(&self) -> &TabixIndex
+    {
+        &self.index
+    }
+
+    /// Read the header: the skipped lines and comment lines at the start of the file.
+    pub fn header(&mut self) -> io::Result<Vec<String>>
+    {
+        self.reader.seek_virtual(0)?;
+        let mut header = Vec::new();
+        let mut line = String::new();
+        loop
+        {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0
+            {
+                break;
+            }
+            let is_meta = line.as_bytes().first() == Some(&self.index.config.meta_char);
+            if header.len() >= self.index.config.skip_lines && !is_meta
+            {
+                break;
+            }
+            header.push(trim_newline(&line).to_string());
+        }
+        Ok(header)
+    }
+
+    /// Get the lines overlapping a region, in file order.
+    ///
+    /// Sequences without records in the index yield no lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - The region; positions are 0-based, half-open
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the lines, without line endings
+    pub fn query(&mut self, region: &Region) -> io::Result<Query<'_, R>>
+    {
+        let chunks = self.index.chunks(&region.name, region.start, region.end_or_max());
+        Ok(Query {
+            reader: &mut self.reader,
+            config: &self.index.config,
+            region: region.clone(),
+            chunks: chunks.into_iter(),
+            chunk_end: None,
+            line: String::new(),
+        })
+    }
+}
+
+/// Iterator over the lines overlapping a region, from [`TabixReader::query`].
+pub struct Query<'a, R: Read + Seek>
+{
+    reader: &'a mut BgzfReader<R>,
+    config: &'a TabixConfig,
+    region: Region,
+    chunks: std::vec::IntoIter<Chunk>,
+    /// End of the chunk being read
+    chunk_end: Option<u64>,
+    line: String,
+}
+
+impl<R: Read + Seek> Query<'_, R>
+{
+    /// Stop the iteration.
+    fn finish(&mut self)
+    {
+        self.chunks = Vec::new().into_iter();
+        self.chunk_end = None;
+    }
+
+    /// Read the next overlapping line.
+    fn next_line(&mut self) -> io::Result<Option<String>>
+    {
+        loop
+        {
+            match self.chunk_end
+            {
+                Some(end) if self.reader.virtual_position() < end => {}
+                _ =>
+                {
+                    let Some(chunk) = self.chunks.next()
+                    else
+                    {
+                        return Ok(None);
+                    };
+                    self.reader.seek_virtual(chunk.start)?;
+                    self.chunk_end = Some(chunk.end);
+                    continue;
+                }
+            }
+
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0
+            {
+                self.chunk_end = None;
+                continue;
+            }
+            let line = trim_newline(&self.line);
+            if line.is_empty() || line.as_bytes()[0] == self.config.meta_char
+            {
+                continue;
+            }
+
+            let (name, start, end) = self.config.interval(line)?;
+            if name != self.region.name
+            {
+                continue;
+            }
+            if start >= self.region.end_or_max()
+            {
+                // Records are sorted, none of the rest overlap
+                self.finish();
+                return Ok(None);
+            }
+            if self.region.overlaps(start, end)
+            {
+                return Ok(Some(line.to_string()));
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> Iterator for Query<'_, R>
+{
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        match self.next_line()
+        {
+            Ok(line) => line.map(Ok),
+            Err(e) =>
+            {
+                // Stop after an error
+                self.finish();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Strip a trailing `\n` or `\r\n`.
+fn trim_newline(line: &str) -> &str
+{
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Number of reference bases covered by a CIGAR string, at least 1.
+fn cigar_reference_len(cigar: &str) -> u64
+{
+    let mut len = 0;
+    let mut count = 0u64;
+    for c in cigar.bytes()
+    {
+        if c.is_ascii_digit()
+        {
+            count = count * 10 + (c - b'0') as u64;
+        }
+        else
+        {
+            if matches!(c, b'M' | b'D' | b'N' | b'=' | b'X')
+            {
+                len += count;
+            }
+            count = 0;
+        }
+    }
+    len.max(1)
+}
+
+/// Little-endian reader over the decompressed index.
+struct Bytes<'a>
+{
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Bytes<'a>
+{
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]>
+    {
+        let bytes = self.data.get(self.pos..self.pos.saturating_add(len)).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated tabix index")
+        })?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> io::Result<u32>
+    {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn i32(&mut self) -> io::Result<i32>
+    {
+        Ok(self.u32()? as i32)
+    }
+
+    fn u64(&mut self) -> io::Result<u64>
+    {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().expect("8 bytes")))
+    }
+
+    /// A non-negative i32 count or size.
+    fn count(&mut self) -> io::Result<usize>
+    {
+        let value = self.i32()?;
+        usize::try_from(value).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Negative count in index: {}", value),
+            )
+        })
+    }
+
+    /// A chunk list: n_chunk, then (start, end) virtual offsets.
+    fn chunks(&mut self) -> io::Result<Vec<Chunk>>
+    {
+        let n = self.count()?;
+        let mut chunks = Vec::with_capacity(n.min(1 << 16));
+        for _ in 0..n
+        {
+            chunks.push(Chunk { start: self.u64()?, end: self.u64()? });
+        }
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::bgzf::compress_for_tests;
+    use crate::gzi::GziIndex;
+
+    /// Smallest bin holding `[start, end)`.
+    fn reg2bin(start: u64, end: u64, min_shift: u32, depth: u32) -> u32
+    {
+        let last = end - 1;
+        for level in (0..=depth).rev()
+        {
+            let shift = min_shift + 3 * (depth - level);
+            if start >> shift == last >> shift
+            {
+                return (((1u64 << (3 * level)) - 1) / 7 + (start >> shift)) as u32;
+            }
+        }
+        0
+    }
+
+    /// Index `text`, compressed in blocks of `block_len`, the way tabix would.
+    fn index_for_tests(text: &str, block_len: usize, csi: bool) -> (Vec<u8>, Vec<u8>)
+    {
+        let (compressed, gzi) = compress_for_tests(text.as_bytes(), block_len);
+        let gzi = GziIndex::from_bytes(&gzi).unwrap();
+        let virtual_offset = |pos: usize| {
+            let block = pos / block_len;
+            let coffset = if block == 0 { 0 } else { gzi.entries()[block - 1].0 };
+            coffset << 16 | (pos % block_len) as u64
+        };
+        let (min_shift, depth) = if csi { (12, 6) } else { (TBI_MIN_SHIFT, TBI_DEPTH) };
+        let config = TabixConfig {
+            format: TabixFormat::Generic,
+            zero_based: true,
+            seq_column: 1,
+            start_column: 2,
+            end_column: 3,
+            meta_char: b'#',
+            skip_lines: 0,
+        };
+
+        let mut names: Vec<String> = Vec::new();
+        let mut references: Vec<ReferenceIndex> = Vec::new();
+        let mut pos = 0;
+        for line in text.split_inclusive('\n')
+        {
+            let (vstart, vend) = (virtual_offset(pos), virtual_offset(pos + line.len()));
+            pos += line.len();
+            if line.starts_with('#')
+            {
+                continue;
+            }
+            let (name, start, end) = config.interval(trim_newline(line)).unwrap();
+            if names.last().map(String::as_str) != Some(name)
+            {
+                names.push(name.to_string());
+                references.push(ReferenceIndex::default());
+            }
+            let reference = references.last_mut().unwrap();
+            let bin = reference.bins.entry(reg2bin(start, end, min_shift, depth)).or_default();
+            match bin.chunks.last_mut()
+            {
+                Some(chunk) if chunk.end == vstart => chunk.end = vend,
+                _ => bin.chunks.push(Chunk { start: vstart, end: vend }),
+            }
+            for window in (start >> min_shift)..=((end - 1) >> min_shift)
+            {
+                let window = window as usize;
+                if reference.linear.len() <= window
+                {
+                    reference.linear.resize(window + 1, 0);
+                    reference.linear[window] = vstart;
+                }
+            }
+        }
+        if csi
+        {
+            // Like htslib, a bin's offset is that of the first record overlapping
+            // its first window, which may be in a larger bin
+            for reference in &mut references
+            {
+                for (&id, bin) in &mut reference.bins
+                {
+                    let level = (0..=depth).rev().find(|&l| ((1u32 << (3 * l)) - 1) / 7 <= id);
+                    let level = level.unwrap();
+                    let first = ((1u32 << (3 * level)) - 1) / 7;
+                    let window = ((id - first) << (3 * (depth - level))) as usize;
+                    bin.loffset = reference.linear.get(window).copied().unwrap_or(0);
+                }
+                reference.linear.clear();
+            }
+        }
+
+        let mut header = Vec::new();
+        for value in [FLAG_ZERO_BASED, 1, 2, 3, b'#' as i32, 0]
+        {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        let names_bytes: Vec<u8> = names.iter().flat_map(|n| n.bytes().chain([0])).collect();
+        header.extend_from_slice(&(names_bytes.len() as i32).to_le_bytes());
+        header.extend_from_slice(&names_bytes);
+
+        let mut index = Vec::new();
+        if csi
+        {
+            index.extend_from_slice(CSI_MAGIC);
+            for value in [min_shift as i32, depth as i32, header.len() as i32]
+            {
+                index.extend_from_slice(&value.to_le_bytes());
+            }
+            index.extend_from_slice(&header);
+            index.extend_from_slice(&(references.len() as i32).to_le_bytes());
+        }
+        else
+        {
+            index.extend_from_slice(TBI_MAGIC);
+            index.extend_from_slice(&(references.len() as i32).to_le_bytes());
+            index.extend_from_slice(&header);
+        }
+        for reference in &references
+        {
+            index.extend_from_slice(&(reference.bins.len() as i32).to_le_bytes());
+            for (id, bin) in &reference.bins
+            {
+                index.extend_from_slice(&id.to_le_bytes());
+                if csi
+                {
+                    index.extend_from_slice(&bin.loffset.to_le_bytes());
+                }
+                index.extend_from_slice(&(bin.chunks.len() as i32).to_le_bytes());
+                for chunk in &bin.chunks
+                {
+                    index.extend_from_slice(&chunk.start.to_le_bytes());
+                    index.extend_from_slice(&chunk.end.to_le_bytes());
+                }
+            }
+            if !csi
+            {
+                index.extend_from_slice(&(reference.linear.len() as i32).to_le_bytes());
+                for offset in &reference.linear
+                {
+                    index.extend_from_slice(&offset.to_le_bytes());
+                }
+            }
+        }
+        (compressed, compress_for_tests(&index, 60_000).0)
+    }
+
+    #[test]
+    fn test_query_tbi_and_csi()
+    {
+        let mut text = String::from("#chrom\tstart\tend\tname\n");
+        let mut features = Vec::new();
+        for (chrom, count) in [("chr1", 400u64), ("chr2", 50), ("chrM", 3)]
+        {
+            for i in 0..count
+            {
+                let start = i * 997;
+                // Mostly short features, some spanning many 16kb windows
+                let len = if i % 37 == 0 { 150_000 } else { 200 + (i * 31) % 3000 };
+                text.push_str(&format!("{}\t{}\t{}\tf{}\n", chrom, start, start + len, i));
+                features.push((chrom, start, start + len));
+            }
+        }
+
+        for csi in [false, true]
+        {
+            let (compressed, index) = index_for_tests(&text, 4000, csi);
+            let index = TabixIndex::from_bytes(&index).unwrap();
+            assert_eq!(index.sequence_names(), ["chr1", "chr2", "chrM"]);
+            assert!(index.config().zero_based);
+            let mut reader = TabixReader::new(Cursor::new(compressed), index);
+            assert_eq!(reader.header().unwrap(), ["#chrom\tstart\tend\tname"]);
+
+            for region in
+                ["chr1:1-1", "chr1:100,001-120,000", "chr1:398,000-", "chr2", "chrM:999-999"]
+            {
+                let region: Region = region.parse().unwrap();
+                let expected: Vec<String> = text
+                    .lines()
+                    .zip(std::iter::once(None).chain(features.iter().map(Some)))
+                    .filter(|(_, feature)| {
+                        feature.is_some_and(|&(chrom, start, end)| {
+                            chrom == region.name && region.overlaps(start, end)
+                        })
+                    })
+                    .map(|(line, _)| line.to_string())
+                    .collect();
+                let lines: Vec<String> =
+                    reader.query(&region).unwrap().collect::<io::Result<_>>().unwrap();
+                assert!(!lines.is_empty(), "{}", region);
+                assert_eq!(lines, expected, "{} (csi: {})", region, csi);
+            }
+            assert_eq!(reader.query(&Region::whole("chrX")).unwrap().count(), 0);
+        }
+
+        // A corrupt reference count fails without allocating for it
+        let mut corrupt = TBI_MAGIC.to_vec();
+        for value in [i32::MAX, FLAG_ZERO_BASED, 1, 2, 3, b'#' as i32, 0, 5]
+        {
+            corrupt.extend_from_slice(&value.to_le_bytes());
+        }
+        corrupt.extend_from_slice(b"chr1\0");
+        assert!(TabixIndex::from_bytes(&compress_for_tests(&corrupt, 60_000).0).is_err());
+    }
+
+    #[test]
+    fn test_intervals()
+    {
+        let vcf = TabixConfig {
+            format: TabixFormat::Vcf,
+            zero_based: false,
+            seq_column: 1,
+            start_column: 2,
+            end_column: 0,
+            meta_char: b'#',
+            skip_lines: 0,
+        };
+        assert_eq!(vcf.interval("1\t100\t.\tACG\tA\t.\t.\tDP=3").unwrap(), ("1", 99, 102));
+        assert_eq!(vcf.interval("1\t100\t.\tN\t<DEL>\t.\t.\tSVTYPE=DEL;END=500").unwrap().2, 500);
+
+        let gff =
+            TabixConfig { format: TabixFormat::Generic, start_column: 4, end_column: 5, ..vcf };
+        let gene = "chr1\tsrc\tgene\t11\t20\t.\t+\t.\tID=g";
+        assert_eq!(gff.interval(gene).unwrap(), ("chr1", 10, 20));
+        assert!(gff.interval("chr1\tsrc\tgene\tx\t20").is_err());
+        assert!(gff.interval("chr1\tsrc").is_err());
+
+        assert_eq!(cigar_reference_len("10M2I5D3S"), 15);
+        assert_eq!(cigar_reference_len("*"), 1);
+    }
+}
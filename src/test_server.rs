@@ -0,0 +1,258 @@
+//! Minimal HTTP/1.1 server for testing the remote readers without network access.
+//!
+//! Serves in-memory files with HEAD, GET and single `Range` requests, and can
+//! be scripted to inject failures for the next requests.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A failure injected into the next request.
+#[derive(Debug, Clone)]
+pub(crate) enum Fault
+{
+    /// Respond with this status and an optional `Retry-After` in seconds
+    Status(u16, Option<u64>),
+    /// Announce the full body length but send only half of it
+    Truncate,
+    /// Wait before responding normally
+    Delay(Duration),
+}
+
+#[derive(Default)]
+struct State
+{
+    faults: VecDeque<Fault>,
+    /// Request heads as received, one string per request
+    requests: Vec<String>,
+    /// `ETag` sent with successful responses
+    etag: Option<String>,
+    /// Answer `Range` requests with the whole file
+    ignore_ranges: bool,
+    /// Send no `Content-Length`, ending bodies by closing the connection
+    omit_length: bool,
+    /// Answer `HEAD` requests with this status, like presigned URLs signed for `GET`
+    reject_head: Option<u16>,
+}
+
+/// A stub server running on a background thread for the rest of the test process.
+pub(crate) struct StubServer
+{
+    /// URL of the file served by [`StubServer::new`]
+    pub(crate) url: String,
+    base: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl StubServer
+{
+    /// Start serving `data` at `http://127.0.0.1:<port>/data`.
+    pub(crate) fn new(data: Vec<u8>) -> Self
+    {
+        Self::with_files(vec![("/data", data)])
+    }
+
+    /// Start serving files at the given paths; other paths get 404.
+    pub(crate) fn with_files(files: Vec<(&str, Vec<u8>)>) -> Self
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+        let files: Arc<HashMap<String, Vec<u8>>> =
+            Arc::new(files.into_iter().map(|(path, data)| (path.to_string(), data)).collect());
+
+        let thread_state = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten()
+            {
+                let state = Arc::clone(&thread_state);
+                let files = Arc::clone(&files);
+                thread::spawn(move || {
+                    let _ = handle(stream, &files, &state);
+                });
+            }
+        });
+
+        Self { url: format!("{}/data", base), base, state }
+    }
+
+    /// URL for a path on this server.
+    pub(crate) fn url_for(&self, path: &str) -> String
+    {
+        format!("{}{}", self.base, path)
+    }
+
+    /// Queue a failure for the next request that has not been answered yet.
+    pub(crate) fn push_fault(&self, fault: Fault)
+    {
+        self.state.lock().unwrap().faults.push_back(fault);
+    }
+
+    /// Send an `ETag` header with successful responses from now on.
+    pub(crate) fn set_etag(&self, etag: &str)
+    {
+        self.state.lock().unwrap().etag = Some(format!("\"{}\"", etag));
+    }
+
+    /// Ignore `Range` headers from now on, like servers without range support.
+    pub(crate) fn ignore_ranges(&self)
+    {
+        self.state.lock().unwrap().ignore_ranges = true;
+    }
+
+    /// Omit `Content-Length` from now on, like servers streaming their responses.
+    pub(crate) fn omit_content_length(&self)
+    {
+        self.state.lock().unwrap().omit_length = true;
+    }
+
+    /// Answer `HEAD` requests with `status` from now on.
+    pub(crate) fn reject_head(&self, status: u16)
+    {
+        self.state.lock().unwrap().reject_head = Some(status);
+    }
+
+    /// Number of requests received so far.
+    pub(crate) fn request_count(&self) -> usize
+    {
+        self.state.lock().unwrap().requests.len()
+    }
+
+    /// Request heads received so far.
+    pub(crate) fn requests(&self) -> Vec<String>
+    {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+fn handle(
+    stream: TcpStream,
+    files: &HashMap<String, Vec<u8>>,
+    state: &Mutex<State>,
+) -> std::io::Result<()>
+{
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut head = String::new();
+    loop
+    {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0
+        {
+            return Ok(());
+        }
+        if line == "\r\n"
+        {
+            break;
+        }
+        head.push_str(&line);
+    }
+
+    let (fault, etag, ignore_ranges, omit_length, reject_head) = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(head.clone());
+        let fault = state.faults.pop_front();
+        (fault, state.etag.clone(), state.ignore_ranges, state.omit_length, state.reject_head)
+    };
+
+    let mut stream = stream;
+    let is_head = head.starts_with("HEAD ");
+    if let Some(status) = reject_head.filter(|_| is_head)
+    {
+        let response = format!(
+            "HTTP/1.1 {} Rejected\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            status
+        );
+        return stream.write_all(response.as_bytes());
+    }
+    let path = head.split_whitespace().nth(1).unwrap_or("/");
+    let data = match files.get(path)
+    {
+        Some(data) => data.as_slice(),
+        None =>
+        {
+            let response =
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+            return stream.write_all(response.as_bytes());
+        }
+    };
+    let range = head
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("range").then(|| value.trim().to_string())
+        })
+        .and_then(|value| parse_range(&value, data.len() as u64))
+        .filter(|_| !ignore_ranges);
+
+    match fault
+    {
+        Some(Fault::Status(status, retry_after)) =>
+        {
+            let mut response = format!(
+                "HTTP/1.1 {} Injected\r\nContent-Length: 0\r\nConnection: close\r\n",
+                status
+            );
+            if let Some(secs) = retry_after
+            {
+                response.push_str(&format!("Retry-After: {}\r\n", secs));
+            }
+            response.push_str("\r\n");
+            return stream.write_all(response.as_bytes());
+        }
+        Some(Fault::Delay(delay)) => thread::sleep(delay),
+        _ =>
+        {}
+    }
+
+    let (status, body) = match range
+    {
+        Some((start, end)) => ("206 Partial Content", &data[start as usize..=end as usize]),
+        None => ("200 OK", data),
+    };
+    let mut response = format!("HTTP/1.1 {}\r\nConnection: close\r\n", status);
+    if !omit_length
+    {
+        response.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    if !ignore_ranges
+    {
+        response.push_str("Accept-Ranges: bytes\r\n");
+    }
+    if let Some((start, end)) = range
+    {
+        response.push_str(&format!("Content-Range: bytes {}-{}/{}\r\n", start, end, data.len()));
+    }
+    if let Some(etag) = etag
+    {
+        response.push_str(&format!("ETag: {}\r\n", etag));
+    }
+    response.push_str("\r\n");
+    stream.write_all(response.as_bytes())?;
+    if !is_head
+    {
+        let body = match fault
+        {
+            Some(Fault::Truncate) => &body[..body.len() / 2],
+            _ => body,
+        };
+        stream.write_all(body)?;
+    }
+    stream.flush()
+}
+
+/// Parse `bytes=start-end` or `bytes=start-`, clamped to the data length.
+fn parse_range(value: &str, len: u64) -> Option<(u64, u64)>
+{
+    if len == 0
+    {
+        return None;
+    }
+    let spec = value.strip_prefix("bytes=")?;
+    let (start, end) = spec.split_once('-')?;
+    let start: u64 = start.parse().ok()?;
+    let end = if end.is_empty() { len - 1 } else { end.parse::<u64>().ok()?.min(len - 1) };
+    (start <= end).then_some((start, end))
+}
@@ -0,0 +1,753 @@
+//! Protein translation of nucleotide sequences.
+//!
+//! This module provides the NCBI genetic code tables, translation in any of the
+//! six reading frames and an ORF finder. Ambiguous IUPAC codons translate to
+//! the amino acid all their expansions agree on (e.g. `GCN` → `A`), otherwise to `X`.
+//!
+//! Everything works on raw byte slices, such as the output of
+//! [`IndexedFastXReader::fetch_range`](crate::indexed::IndexedFastXReader::fetch_range),
+//! and on [`FastARecord`] sequences.
+//!
+//! # Example
+//!
+//! ```
+//! use fastx::translate::{Frame, GeneticCode};
+//!
+//! let code = GeneticCode::standard();
+//! assert_eq!(code.translate(b"ATGGCNTGA"), b"MA*");
+//! assert_eq!(code.translate_frame(b"TCATGCCAT", Frame::Reverse(0)), b"MA*");
+//!
+//! // Vertebrate mitochondrial code: TGA is Trp
+//! let mito = GeneticCode::from_id(2).unwrap();
+//! assert_eq!(mito.translate(b"TGA"), b"W");
+//! ```
+
+use crate::FastX::{FastARecord, FastXRead};
+
+/// An NCBI genetic code (translation table).
+///
+/// Codons are indexed in NCBI order (`TCAG` for each codon position).
+#[derive(Debug)]
+pub struct GeneticCode
+{
+    /// NCBI translation table number
+    pub id: u8,
+    /// NCBI table name
+    pub name: &'static str,
+    /// Amino acid per codon, `*` for stop
+    amino_acids: &'static [u8; 64],
+    /// `M` for codons that can act as translation start
+    starts: &'static [u8; 64],
+}
+
+macro_rules! genetic_code {
+    ($id:expr, $name:expr, $aas:expr, $starts:expr) => {
+        GeneticCode {
+            id: $id,
+            name: $name,
+            amino_acids: $aas,
+            starts: $starts,
+        }
+    };
+}
+
+/// All supported NCBI genetic codes.
+///
+/// Tables 27 (Karyorelict Nuclear), 28 (Condylostoma Nuclear) and 31 (Blastocrithidia
+/// Nuclear) are left out: their stop codons also code for amino acids depending on
+/// context, which a codon table cannot express.
+pub static GENETIC_CODES: &[GeneticCode] = &[
+    genetic_code!(
+        1,
+        "Standard",
+        b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"---M------**--*----M---------------M----------------------------"
+    ),
+    genetic_code!(
+        2,
+        "Vertebrate Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+        b"----------**--------------------MMMM----------**---M------------"
+    ),
+    genetic_code!(
+        3,
+        "Yeast Mitochondrial",
+        b"FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------**----------------------MM---------------M------------"
+    ),
+    genetic_code!(
+        4,
+        "Mold, Protozoan, and Coelenterate Mitochondrial and Mycoplasma/Spiroplasma",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--MM------**-------M------------MMMM---------------M------------"
+    ),
+    genetic_code!(
+        5,
+        "Invertebrate Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+        b"---M------**--------------------MMMM---------------M------------"
+    ),
+    genetic_code!(
+        6,
+        "Ciliate, Dasycladacean and Hexamita Nuclear",
+        b"FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--------------*--------------------M----------------------------"
+    ),
+    genetic_code!(
+        9,
+        "Echinoderm and Flatworm Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        b"----------**-----------------------M---------------M------------"
+    ),
+    genetic_code!(
+        10,
+        "Euplotid Nuclear",
+        b"FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------**-----------------------M----------------------------"
+    ),
+    genetic_code!(
+        11,
+        "Bacterial, Archaeal and Plant Plastid",
+        b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"---M------**--*----M------------MMMM---------------M------------"
+    ),
+    genetic_code!(
+        12,
+        "Alternative Yeast Nuclear",
+        b"FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------**--*----M---------------M----------------------------"
+    ),
+    genetic_code!(
+        13,
+        "Ascidian Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+        b"---M------**----------------------MM---------------M------------"
+    ),
+    genetic_code!(
+        14,
+        "Alternative Flatworm Mitochondrial",
+        b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        b"-----------*-----------------------M----------------------------"
+    ),
+    genetic_code!(
+        16,
+        "Chlorophycean Mitochondrial",
+        b"FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------*---*--------------------M----------------------------"
+    ),
+    genetic_code!(
+        21,
+        "Trematode Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        b"----------**-----------------------M---------------M------------"
+    ),
+    genetic_code!(
+        22,
+        "Scenedesmus obliquus Mitochondrial",
+        b"FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"------*---*---*--------------------M----------------------------"
+    ),
+    genetic_code!(
+        23,
+        "Thraustochytrium Mitochondrial",
+        b"FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--*-------**--*-----------------M--M---------------M------------"
+    ),
+    genetic_code!(
+        24,
+        "Rhabdopleuridae Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        b"---M------**-------M---------------M---------------M------------"
+    ),
+    genetic_code!(
+        25,
+        "Candidate Division SR1 and Gracilibacteria",
+        b"FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"---M------**-----------------------M---------------M------------"
+    ),
+    genetic_code!(
+        26,
+        "Pachysolen tannophilus Nuclear",
+        b"FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"----------**--*----M---------------M----------------------------"
+    ),
+    genetic_code!(
+        29,
+        "Mesodinium Nuclear",
+        b"FFLLSSSSYYYYCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--------------*--------------------M----------------------------"
+    ),
+    genetic_code!(
+        30,
+        "Peritrich Nuclear",
+        b"FFLLSSSSYYEECC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        b"--------------*--------------------M----------------------------"
+    ),
+    genetic_code!(
+        33,
+        "Cephalodiscidae Mitochondrial",
+        b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        b"---M-------*-------M---------------M---------------M------------"
+    ),
+];
+
+/// Bit set of the bases (T=1, C=2, A=4, G=8) an IUPAC code stands for.
+fn iupac_bases(b: u8) -> u8
+{
+    match b.to_ascii_uppercase()
+    {
+        b'T' | b'U' => 0b0001,
+        b'C' => 0b0010,
+        b'A' => 0b0100,
+        b'G' => 0b1000,
+        b'Y' => 0b0011,
+        b'W' => 0b0101,
+        b'K' => 0b1001,
+        b'M' => 0b0110,
+        b'S' => 0b1010,
+        b'R' => 0b1100,
+        b'H' => 0b0111,
+        b'B' => 0b1011,
+        b'D' => 0b1101,
+        b'V' => 0b1110,
+        b'N' => 0b1111,
+        _ => 0,
+    }
+}
+
+impl GeneticCode
+{
+    /// Look up a genetic code by its NCBI table number.
+    pub fn from_id(id: u8) -> Option<&'static GeneticCode>
+    {
+        GENETIC_CODES.iter().find(|code| code.id == id)
+    }
+
+    /// The standard genetic code (NCBI table 1).
+    pub fn standard() -> &'static GeneticCode
+    {
+        &GENETIC_CODES[0]
+    }
+
+    /// Apply `f` to the table indexes of every unambiguous expansion of a codon.
+    ///
+    /// Returns false if the codon contains a non-IUPAC byte.
+    fn for_each_expansion(codon: &[u8], mut f: impl FnMut(usize)) -> bool
+    {
+        let sets = [iupac_bases(codon[0]), iupac_bases(codon[1]), iupac_bases(codon[2])];
+        if sets.contains(&0)
+        {
+            return false;
+        }
+        for i in 0..4
+        {
+            if sets[0] & (1 << i) == 0
+            {
+                continue;
+            }
+            for j in 0..4
+            {
+                if sets[1] & (1 << j) == 0
+                {
+                    continue;
+                }
+                for k in 0..4
+                {
+                    if sets[2] & (1 << k) != 0
+                    {
+                        f(i * 16 + j * 4 + k);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Translate a single codon (the first three bytes of `codon`).
+    ///
+    /// Returns `X` if the codon is ambiguous, contains invalid bytes or is too short.
+    pub fn translate_codon(&self, codon: &[u8]) -> u8
+    {
+        if codon.len() < 3
+        {
+            return b'X';
+        }
+        let mut aa = None;
+        let mut consistent = true;
+        let valid = Self::for_each_expansion(codon, |i| {
+            let this = self.amino_acids[i];
+            match aa
+            {
+                None => aa = Some(this),
+                Some(prev) if prev != this => consistent = false,
+                _ =>
+                {}
+            }
+        });
+        match aa
+        {
+            Some(aa) if valid && consistent => aa,
+            _ => b'X',
+        }
+    }
+
+    /// Check whether a codon is a stop codon in all of its expansions.
+    pub fn is_stop(&self, codon: &[u8]) -> bool
+    {
+        self.translate_codon(codon) == b'*'
+    }
+
+    /// Check whether a codon is a start codon (including alternative starts) in all of its expansions.
+    pub fn is_start(&self, codon: &[u8]) -> bool
+    {
+        if codon.len() < 3
+        {
+            return false;
+        }
+        let mut all = true;
+        let valid = Self::for_each_expansion(codon, |i| all &= self.starts[i] == b'M');
+        valid && all
+    }
+
+    /// Translate a sequence in frame starting at its first base.
+    ///
+    /// A trailing partial codon is ignored. Stop codons are translated to `*`.
+    pub fn translate(&self, seq: &[u8]) -> Vec<u8>
+    {
+        seq.chunks_exact(3).map(|codon| self.translate_codon(codon)).collect()
+    }
+
+    /// Translate a coding sequence.
+    ///
+    /// Like [`translate`](Self::translate), but an initial alternative start codon
+    /// (e.g. `TTG` or `GTG`) is translated to `M`, as it is in vivo.
+    pub fn translate_cds(&self, seq: &[u8]) -> Vec<u8>
+    {
+        let mut peptide = self.translate(seq);
+        if !peptide.is_empty() && self.is_start(seq)
+        {
+            peptide[0] = b'M';
+        }
+        peptide
+    }
+
+    /// Translate a sequence in one of the six reading frames.
+    pub fn translate_frame(&self, seq: &[u8], frame: Frame) -> Vec<u8>
+    {
+        match frame
+        {
+            Frame::Forward(offset) => self.translate(seq.get(offset as usize..).unwrap_or(&[])),
+            Frame::Reverse(offset) =>
+            {
+                let rc = reverse_complement(seq);
+                self.translate(rc.get(offset as usize..).unwrap_or(&[]))
+            }
+        }
+    }
+
+    /// Translate a sequence in all six reading frames.
+    pub fn translate_six_frames(&self, seq: &[u8]) -> Vec<(Frame, Vec<u8>)>
+    {
+        let rc = reverse_complement(seq);
+        Frame::all()
+            .into_iter()
+            .map(|frame| {
+                let (strand, offset) = match frame
+                {
+                    Frame::Forward(offset) => (seq, offset),
+                    Frame::Reverse(offset) => (&rc[..], offset),
+                };
+                (frame, self.translate(strand.get(offset as usize..).unwrap_or(&[])))
+            })
+            .collect()
+    }
+
+    /// Translate the sequence of a record in the first forward frame.
+    ///
+    /// The returned record keeps the name of the input record.
+    pub fn translate_record<T: FastXRead + ?Sized>(&self, record: &T) -> FastARecord
+    {
+        FastARecord {
+            name: record.name().clone(),
+            raw_seq: self.translate(&record.seq()),
+        }
+    }
+}
+
+/// A reading frame.
+///
+/// The offset (0, 1 or 2) is counted from the start of the sequence for the
+/// forward strand, and from the end of the sequence for the reverse strand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame
+{
+    /// Forward strand with offset 0..3
+    Forward(u8),
+    /// Reverse complement strand with offset 0..3
+    Reverse(u8),
+}
+
+impl Frame
+{
+    /// The six reading frames in the order +1, +2, +3, -1, -2, -3.
+    pub fn all() -> [Frame; 6]
+    {
+        [
+            Frame::Forward(0),
+            Frame::Forward(1),
+            Frame::Forward(2),
+            Frame::Reverse(0),
+            Frame::Reverse(1),
+            Frame::Reverse(2),
+        ]
+    }
+}
+
+impl std::fmt::Display for Frame
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Frame::Forward(offset) => write!(f, "+{}", offset + 1),
+            Frame::Reverse(offset) => write!(f, "-{}", offset + 1),
+        }
+    }
+}
+
+/// Reverse complement a nucleotide sequence, including IUPAC codes.
+///
+/// Case is preserved, bytes without a complement are copied unchanged.
+pub fn reverse_complement(seq: &[u8]) -> Vec<u8>
+{
+    seq.iter().rev().map(|&b| complement(b)).collect()
+}
+
+fn complement(b: u8) -> u8
+{
+    let c = match b.to_ascii_uppercase()
+    {
+        b'A' => b'T',
+        b'T' | b'U' => b'A',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'R' => b'Y',
+        b'Y' => b'R',
+        b'K' => b'M',
+        b'M' => b'K',
+        b'B' => b'V',
+        b'V' => b'B',
+        b'D' => b'H',
+        b'H' => b'D',
+        other => other,
+    };
+    if b.is_ascii_lowercase()
+    {
+        c.to_ascii_lowercase()
+    }
+    else
+    {
+        c
+    }
+}
+
+/// Which codons may start an ORF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartMode
+{
+    /// Only `ATG`
+    Atg,
+    /// Any start codon of the genetic code, including alternative starts
+    Alternative,
+    /// No start codon required, ORFs span from stop to stop
+    StopToStop,
+}
+
+/// An open reading frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Orf
+{
+    /// Reading frame the ORF was found in
+    pub frame: Frame,
+    /// 0-based start on the forward strand (inclusive)
+    pub start: usize,
+    /// End on the forward strand (exclusive), including the stop codon if present
+    pub end: usize,
+    /// Translated peptide, without the stop codon
+    pub peptide: Vec<u8>,
+}
+
+impl Orf
+{
+    /// Build a peptide record named `{seq_id}_{start}-{end}({frame})`, 1-based inclusive coordinates.
+    pub fn to_record(&self, seq_id: &str) -> FastARecord
+    {
+        FastARecord {
+            name: format!("{}_{}-{}({})", seq_id, self.start + 1, self.end, self.frame),
+            raw_seq: self.peptide.clone(),
+        }
+    }
+}
+
+/// Finds open reading frames in nucleotide sequences.
+///
+/// For each stop codon the longest ORF ending there is reported, i.e. nested
+/// ORFs sharing a stop codon are not reported separately.
+///
+/// # Example
+///
+/// ```
+/// use fastx::translate::{Frame, OrfFinder};
+///
+/// let finder = OrfFinder::new().with_min_length(2);
+/// let orfs = finder.find(b"CCATGAAATTTTAACC");
+/// assert_eq!(orfs.len(), 1);
+/// assert_eq!(orfs[0].frame, Frame::Forward(2));
+/// assert_eq!((orfs[0].start, orfs[0].end), (2, 14));
+/// assert_eq!(orfs[0].peptide, b"MKF");
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrfFinder
+{
+    code: &'static GeneticCode,
+    start_mode: StartMode,
+    min_length: usize,
+    include_partial: bool,
+}
+
+impl Default for OrfFinder
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl OrfFinder
+{
+    /// Create an ORF finder using the standard code, `ATG` starts and a minimum length of 30 amino acids.
+    pub fn new() -> Self
+    {
+        Self {
+            code: GeneticCode::standard(),
+            start_mode: StartMode::Atg,
+            min_length: 30,
+            include_partial: false,
+        }
+    }
+
+    /// Set the genetic code.
+    pub fn with_code(mut self, code: &'static GeneticCode) -> Self
+    {
+        self.code = code;
+        self
+    }
+
+    /// Set which codons may start an ORF.
+    pub fn with_start_mode(mut self, start_mode: StartMode) -> Self
+    {
+        self.start_mode = start_mode;
+        self
+    }
+
+    /// Set the minimum ORF length in amino acids (excluding the stop codon).
+    pub fn with_min_length(mut self, min_length: usize) -> Self
+    {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Also report ORFs that run off the end of the sequence without a stop codon.
+    pub fn with_partial(mut self, include_partial: bool) -> Self
+    {
+        self.include_partial = include_partial;
+        self
+    }
+
+    fn is_start(&self, codon: &[u8]) -> bool
+    {
+        match self.start_mode
+        {
+            StartMode::Atg => codon.eq_ignore_ascii_case(b"ATG"),
+            StartMode::Alternative => self.code.is_start(codon),
+            StartMode::StopToStop => true,
+        }
+    }
+
+    /// Find ORFs in all six frames of a sequence.
+    ///
+    /// ORFs are returned ordered by frame (+1, +2, +3, -1, -2, -3) and position in the frame.
+    pub fn find(&self, seq: &[u8]) -> Vec<Orf>
+    {
+        let rc = reverse_complement(seq);
+        let mut orfs = Vec::new();
+        for frame in Frame::all()
+        {
+            let (strand, offset) = match frame
+            {
+                Frame::Forward(offset) => (seq, offset as usize),
+                Frame::Reverse(offset) => (&rc[..], offset as usize),
+            };
+            self.find_in_frame(strand, offset, frame, seq.len(), &mut orfs);
+        }
+        orfs
+    }
+
+    fn find_in_frame(
+        &self,
+        strand: &[u8],
+        offset: usize,
+        frame: Frame,
+        seq_len: usize,
+        orfs: &mut Vec<Orf>,
+    )
+    {
+        let mut orf_start: Option<usize> = None;
+        let mut pos = offset;
+        while pos + 3 <= strand.len()
+        {
+            let codon = &strand[pos..pos + 3];
+            if self.code.is_stop(codon)
+            {
+                if let Some(start) = orf_start.take()
+                {
+                    self.push_orf(strand, start, pos, pos + 3, frame, seq_len, orfs);
+                }
+            }
+            else if orf_start.is_none() && self.is_start(codon)
+            {
+                orf_start = Some(pos);
+            }
+            pos += 3;
+        }
+        if let Some(start) = orf_start
+        {
+            if self.include_partial
+            {
+                self.push_orf(strand, start, pos, pos, frame, seq_len, orfs);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push_orf(
+        &self,
+        strand: &[u8],
+        start: usize,
+        coding_end: usize,
+        end: usize,
+        frame: Frame,
+        seq_len: usize,
+        orfs: &mut Vec<Orf>,
+    )
+    {
+        if (coding_end - start) / 3 < self.min_length
+        {
+            return;
+        }
+        let peptide = match self.start_mode
+        {
+            StartMode::StopToStop => self.code.translate(&strand[start..coding_end]),
+            _ => self.code.translate_cds(&strand[start..coding_end]),
+        };
+        let (start, end) = match frame
+        {
+            Frame::Forward(_) => (start, end),
+            Frame::Reverse(_) => (seq_len - end, seq_len - start),
+        };
+        orfs.push(Orf {
+            frame,
+            start,
+            end,
+            peptide,
+        });
+    }
+
+    /// Find ORFs in a record and return them as peptide records.
+    ///
+    /// Records are named after the record's id, see [`Orf::to_record`].
+    pub fn find_records<T: FastXRead + ?Sized>(&self, record: &T) -> Vec<FastARecord>
+    {
+        self.find(&record.seq())
+            .iter()
+            .map(|orf| orf.to_record(record.id()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_tables_well_formed()
+    {
+        for code in GENETIC_CODES
+        {
+            assert_eq!(code.amino_acids.len(), 64);
+            assert_eq!(code.starts.len(), 64);
+            // ATG is a start codon in all supported tables
+            assert!(code.is_start(b"ATG"), "table {}", code.id);
+        }
+
+        // Nuclear codes of ciliates reassigning TAA and TAG
+        assert_eq!(GeneticCode::from_id(29).unwrap().translate(b"TAATAGTGA"), b"YY*");
+        assert_eq!(GeneticCode::from_id(30).unwrap().translate(b"TAATAGTGA"), b"EE*");
+        // Context-dependent stop codons are not supported
+        assert!([27, 28, 31].iter().all(|&id| GeneticCode::from_id(id).is_none()));
+    }
+
+    #[test]
+    fn test_ambiguous_codons()
+    {
+        let code = GeneticCode::standard();
+        assert_eq!(code.translate_codon(b"GCN"), b'A');
+        assert_eq!(code.translate_codon(b"TAR"), b'*');
+        assert_eq!(code.translate_codon(b"ATN"), b'X');
+        assert_eq!(code.translate_codon(b"A-G"), b'X');
+        assert_eq!(code.translate_codon(b"atg"), b'M');
+    }
+
+    #[test]
+    fn test_alternative_start()
+    {
+        let code = GeneticCode::from_id(11).unwrap();
+        assert_eq!(code.translate(b"GTGAAA"), b"VK");
+        assert_eq!(code.translate_cds(b"GTGAAA"), b"MK");
+        assert!(!GeneticCode::standard().is_start(b"GTG"));
+    }
+
+    #[test]
+    fn test_six_frames()
+    {
+        let seq = b"ATGCCCTAGG";
+        let frames = GeneticCode::standard().translate_six_frames(seq);
+        assert_eq!(frames[0], (Frame::Forward(0), b"MP*".to_vec()));
+        assert_eq!(frames[1], (Frame::Forward(1), b"CPR".to_vec()));
+        // reverse complement: CCTAGGGCAT
+        assert_eq!(frames[3], (Frame::Reverse(0), b"PRA".to_vec()));
+        assert_eq!(frames[4], (Frame::Reverse(1), b"LGH".to_vec()));
+    }
+
+    #[test]
+    fn test_reverse_orf_coordinates()
+    {
+        // reverse complement of ATGAAATAA
+        let seq = b"GGTTATTTCATGG";
+        let orfs = OrfFinder::new().with_min_length(1).find(seq);
+        let reverse: Vec<_> = orfs
+            .iter()
+            .filter(|orf| matches!(orf.frame, Frame::Reverse(_)))
+            .collect();
+        assert_eq!(reverse.len(), 1);
+        assert_eq!(reverse[0].peptide, b"MK");
+        assert_eq!(reverse_complement(&seq[reverse[0].start..reverse[0].end]), b"ATGAAATAA");
+
+        let records = OrfFinder::new().with_min_length(1).find_records(&FastARecord {
+            name: "s1 test".to_string(),
+            raw_seq: seq.to_vec(),
+        });
+        assert!(records.iter().any(|r| r.name == "s1_3-11(-3)"));
+    }
+}
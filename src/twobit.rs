@@ -0,0 +1,711 @@
+//! UCSC 2bit (.2bit) reference reader with random access.
+//!
+//! The .2bit format stores nucleotides packed two bits per base together with
+//! blocks of `N` and soft-masked (lowercase) regions. It is self-indexed, so no
+//! companion .fai or .gzi files are needed.
+//!
+//! Format (all integers in the byte order given by the signature):
+//! - Header: signature `0x1A412743`, version (0, or 1 for 64-bit offsets),
+//!   sequence count, reserved
+//! - Index: per sequence, name length (u8), name, offset of the sequence record
+//! - Sequence record: DNA size, N block count, N block starts and sizes,
+//!   mask block count, mask block starts and sizes, reserved, packed DNA
+//!   (`T`=0, `C`=1, `A`=2, `G`=3, first base in the high bits)
+
+use crate::indexed::SequenceFetch;
+use crate::FastX::{fasta_for_each, FastARecord, FastXRead};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+const TWOBIT_SIGNATURE: u32 = 0x1A41_2743;
+const TWOBIT_BASES: [u8; 4] = [b'T', b'C', b'A', b'G'];
+
+/// A sequence record header of a .2bit file.
+#[derive(Debug)]
+struct TwoBitRecord
+{
+    /// Number of bases
+    length: u64,
+    /// Sorted (start, size) blocks of `N`
+    n_blocks: Vec<(u64, u64)>,
+    /// Sorted (start, size) blocks of lowercase sequence
+    mask_blocks: Vec<(u64, u64)>,
+    /// File offset of the packed DNA
+    dna_offset: u64,
+}
+
+/// A .2bit reader supporting random access by sequence ID.
+///
+/// Provides the same fetch API as [`IndexedFastXReader`](crate::indexed::IndexedFastXReader),
+/// and implements [`SequenceFetch`] so region tooling works with both.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::twobit::TwoBitReader;
+/// use fastx::FastX::FastXRead;
+/// use std::path::Path;
+///
+/// let mut reader = TwoBitReader::from_path(Path::new("hg38.2bit")).unwrap();
+///
+/// let region = reader.fetch_range("chr1", 1_000_000, 1_000_100).unwrap();
+/// println!("{}", String::from_utf8_lossy(&region));
+///
+/// // Ignore soft-masking and return uppercase sequence
+/// let mut reader = reader.with_soft_mask(false);
+/// let record = reader.fetch("chrM").unwrap();
+/// println!("{}: {} bp", record.id(), record.seq_len());
+/// ```
+pub struct TwoBitReader<R: Read + Seek>
+{
+    inner: R,
+    big_endian: bool,
+    /// Sequence names in file order
+    names: Vec<String>,
+    /// Sequence name -> record offset
+    offsets: HashMap<String, u64>,
+    /// Parsed record headers
+    records: HashMap<String, Arc<TwoBitRecord>>,
+    /// Whether to lowercase masked blocks
+    soft_mask: bool,
+}
+
+impl TwoBitReader<File>
+{
+    /// Open a .2bit file from a local path.
+    pub fn from_path(path: &Path) -> io::Result<Self>
+    {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> TwoBitReader<R>
+{
+    /// Create a reader from any seekable source, parsing the header and index.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(reader)` - The reader ready for use
+    /// * `Err(io::Error)` - If the signature, version or index is invalid
+    pub fn new(mut inner: R) -> io::Result<Self>
+    {
+        inner.seek(SeekFrom::Start(0))?;
+        let mut header = [0u8; 16];
+        inner.read_exact(&mut header)?;
+
+        let signature = [header[0], header[1], header[2], header[3]];
+        let big_endian = if u32::from_le_bytes(signature) == TWOBIT_SIGNATURE
+        {
+            false
+        }
+        else if u32::from_be_bytes(signature) == TWOBIT_SIGNATURE
+        {
+            true
+        }
+        else
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid 2bit signature"));
+        };
+
+        let word = |i: usize| {
+            let bytes = [header[i], header[i + 1], header[i + 2], header[i + 3]];
+            if big_endian
+            {
+                u32::from_be_bytes(bytes)
+            }
+            else
+            {
+                u32::from_le_bytes(bytes)
+            }
+        };
+        let version = word(4);
+        if version > 1
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported 2bit version {}", version),
+            ));
+        }
+        let sequence_count = word(8);
+        // The count is untrusted, so pre-allocate for a plausible number only
+        let capacity = sequence_count.min(1 << 16) as usize;
+
+        let mut reader = Self {
+            inner,
+            big_endian,
+            names: Vec::with_capacity(capacity),
+            offsets: HashMap::with_capacity(capacity),
+            records: HashMap::new(),
+            soft_mask: true,
+        };
+
+        for _ in 0..sequence_count
+        {
+            let mut name_len = [0u8; 1];
+            reader.inner.read_exact(&mut name_len)?;
+            let mut name = vec![0u8; name_len[0] as usize];
+            reader.inner.read_exact(&mut name)?;
+            let name = String::from_utf8(name).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "2bit sequence name is not valid UTF-8")
+            })?;
+            let offset = if version == 1
+            {
+                reader.read_u64()?
+            }
+            else
+            {
+                reader.read_u32()? as u64
+            };
+            reader.offsets.insert(name.clone(), offset);
+            reader.names.push(name);
+        }
+
+        Ok(reader)
+    }
+
+    /// Set whether masked blocks are returned in lowercase (default) or uppercase.
+    pub fn with_soft_mask(mut self, soft_mask: bool) -> Self
+    {
+        self.soft_mask = soft_mask;
+        self
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32>
+    {
+        let mut buf = [0u8; 4];
+        self.inner.read_exact(&mut buf)?;
+        Ok(if self.big_endian
+        {
+            u32::from_be_bytes(buf)
+        }
+        else
+        {
+            u32::from_le_bytes(buf)
+        })
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64>
+    {
+        let mut buf = [0u8; 8];
+        self.inner.read_exact(&mut buf)?;
+        Ok(if self.big_endian
+        {
+            u64::from_be_bytes(buf)
+        }
+        else
+        {
+            u64::from_le_bytes(buf)
+        })
+    }
+
+    fn read_blocks(&mut self) -> io::Result<Vec<(u64, u64)>>
+    {
+        let count = self.read_u32()? as usize;
+        let mut starts = Vec::with_capacity(count.min(1 << 16));
+        for _ in 0..count
+        {
+            starts.push(self.read_u32()? as u64);
+        }
+        let mut blocks = Vec::with_capacity(starts.len());
+        for start in starts
+        {
+            blocks.push((start, self.read_u32()? as u64));
+        }
+        blocks.sort_unstable();
+        Ok(blocks)
+    }
+
+    /// Load (and cache) the record header of a sequence.
+    fn record(&mut self, seq_id: &str) -> io::Result<Arc<TwoBitRecord>>
+    {
+        if let Some(record) = self.records.get(seq_id)
+        {
+            return Ok(Arc::clone(record));
+        }
+
+        let offset = *self.offsets.get(seq_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Sequence '{}' not found in index", seq_id),
+            )
+        })?;
+
+        self.inner.seek(SeekFrom::Start(offset))?;
+        let length = self.read_u32()? as u64;
+        let n_blocks = self.read_blocks()?;
+        let mask_blocks = self.read_blocks()?;
+        let _reserved = self.read_u32()?;
+        let dna_offset = self.inner.stream_position()?;
+
+        let record = Arc::new(TwoBitRecord {
+            length,
+            n_blocks,
+            mask_blocks,
+            dna_offset,
+        });
+        self.records.insert(seq_id.to_string(), Arc::clone(&record));
+        Ok(record)
+    }
+
+    /// Fetch a whole sequence by its ID.
+    pub fn fetch(&mut self, seq_id: &str) -> io::Result<FastARecord>
+    {
+        let length = self.record(seq_id)?.length;
+        let raw_seq = if length == 0
+        {
+            Vec::new()
+        }
+        else
+        {
+            self.fetch_range(seq_id, 0, length)?
+        };
+        Ok(FastARecord {
+            name: seq_id.to_string(),
+            raw_seq,
+        })
+    }
+
+    /// Fetch a specific region of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `seq_id` - The sequence identifier
+    /// * `start` - 0-based start position
+    /// * `end` - End position (exclusive), clamped to the sequence length
+    pub fn fetch_range(&mut self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
+    {
+        let record = self.record(seq_id)?;
+
+        if start >= record.length
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Start position {} beyond sequence length {}", start, record.length),
+            ));
+        }
+        let end = end.min(record.length);
+        if end <= start
+        {
+            return Ok(Vec::new());
+        }
+
+        let first_byte = start / 4;
+        let last_byte = (end - 1) / 4;
+        let mut packed = vec![0u8; (last_byte - first_byte + 1) as usize];
+        self.inner.seek(SeekFrom::Start(record.dna_offset + first_byte))?;
+        self.inner.read_exact(&mut packed)?;
+
+        let mut seq = Vec::with_capacity((end - start) as usize);
+        for pos in start..end
+        {
+            let byte = packed[(pos / 4 - first_byte) as usize];
+            let shift = 6 - 2 * (pos % 4);
+            seq.push(TWOBIT_BASES[((byte >> shift) & 3) as usize]);
+        }
+
+        for_each_overlap(&record.n_blocks, start, end, |from, to| {
+            seq[from..to].fill(b'N');
+        });
+        if self.soft_mask
+        {
+            for_each_overlap(&record.mask_blocks, start, end, |from, to| {
+                seq[from..to].make_ascii_lowercase();
+            });
+        }
+
+        Ok(seq)
+    }
+
+    /// Get the length of a sequence.
+    pub fn sequence_length(&mut self, seq_id: &str) -> io::Result<u64>
+    {
+        Ok(self.record(seq_id)?.length)
+    }
+
+    /// Check if a sequence exists in the file.
+    pub fn contains(&self, seq_id: &str) -> bool
+    {
+        self.offsets.contains_key(seq_id)
+    }
+
+    /// Get all sequence names in file order.
+    pub fn sequence_names(&self) -> Vec<&str>
+    {
+        self.names.iter().map(|s| s.as_str()).collect()
+    }
+}
+
+impl<R: Read + Seek> SequenceFetch for TwoBitReader<R>
+{
+    fn fetch(&mut self, seq_id: &str) -> io::Result<FastARecord>
+    {
+        TwoBitReader::fetch(self, seq_id)
+    }
+
+    fn fetch_range(&mut self, seq_id: &str, start: u64, end: u64) -> io::Result<Vec<u8>>
+    {
+        TwoBitReader::fetch_range(self, seq_id, start, end)
+    }
+
+    fn sequence_length(&mut self, seq_id: &str) -> io::Result<u64>
+    {
+        TwoBitReader::sequence_length(self, seq_id)
+    }
+
+    fn contains(&self, seq_id: &str) -> bool
+    {
+        TwoBitReader::contains(self, seq_id)
+    }
+
+    fn sequence_names(&self) -> Vec<&str>
+    {
+        TwoBitReader::sequence_names(self)
+    }
+}
+
+/// Call `f` with the region-relative range of every block overlapping `[start, end)`.
+fn for_each_overlap(blocks: &[(u64, u64)], start: u64, end: u64, mut f: impl FnMut(usize, usize))
+{
+    // First block that could end after start
+    let first = blocks.partition_point(|&(block_start, _)| block_start < start);
+    let first = first.saturating_sub(1);
+    for &(block_start, block_size) in &blocks[first..]
+    {
+        if block_start >= end
+        {
+            break;
+        }
+        let block_end = block_start + block_size;
+        if block_end <= start
+        {
+            continue;
+        }
+        let from = block_start.max(start) - start;
+        let to = block_end.min(end) - start;
+        f(from as usize, to as usize);
+    }
+}
+
+/// Collect the (start, size) runs of positions matching `pred`.
+fn runs(seq: &[u8], pred: impl Fn(u8) -> bool) -> Vec<(u32, u32)>
+{
+    let mut blocks = Vec::new();
+    let mut run_start = None;
+    for (i, &b) in seq.iter().enumerate()
+    {
+        match (pred(b), run_start)
+        {
+            (true, None) => run_start = Some(i),
+            (false, Some(s)) =>
+            {
+                blocks.push((s as u32, (i - s) as u32));
+                run_start = None;
+            }
+            _ =>
+            {}
+        }
+    }
+    if let Some(s) = run_start
+    {
+        blocks.push((s as u32, (seq.len() - s) as u32));
+    }
+    blocks
+}
+
+/// Encode one sequence as a little-endian 2bit sequence record.
+fn encode_record(seq: &[u8]) -> io::Result<Vec<u8>>
+{
+    if seq.len() > u32::MAX as usize
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Sequence too long for the 2bit format",
+        ));
+    }
+
+    let n_blocks = runs(seq, |b| !matches!(b.to_ascii_uppercase(), b'A' | b'C' | b'G' | b'T'));
+    let mask_blocks = runs(seq, |b| b.is_ascii_lowercase());
+
+    let mut out = Vec::with_capacity(16 + 8 * (n_blocks.len() + mask_blocks.len()) + seq.len() / 4 + 1);
+    out.extend_from_slice(&(seq.len() as u32).to_le_bytes());
+    for blocks in [&n_blocks, &mask_blocks]
+    {
+        out.extend_from_slice(&(blocks.len() as u32).to_le_bytes());
+        for &(start, _) in blocks.iter()
+        {
+            out.extend_from_slice(&start.to_le_bytes());
+        }
+        for &(_, size) in blocks.iter()
+        {
+            out.extend_from_slice(&size.to_le_bytes());
+        }
+    }
+    out.extend_from_slice(&0u32.to_le_bytes());
+
+    for chunk in seq.chunks(4)
+    {
+        let mut byte = 0u8;
+        for (i, &b) in chunk.iter().enumerate()
+        {
+            let code = match b.to_ascii_uppercase()
+            {
+                b'C' => 1,
+                b'A' => 2,
+                b'G' => 3,
+                _ => 0,
+            };
+            byte |= code << (6 - 2 * i);
+        }
+        out.push(byte);
+    }
+    Ok(out)
+}
+
+/// Convert FASTA to the .2bit format.
+///
+/// Sequences are named by their ID (the header up to the first space). Non-`ACGT`
+/// bases are stored as `N` blocks and lowercase bases as mask blocks. A version 1
+/// file with 64-bit offsets is written if the output exceeds 4 GiB.
+///
+/// The index precedes the sequence data, so the input is read twice: once to size
+/// the records and once to write them. Only one encoded record is held in memory.
+///
+/// # Example
+///
+/// ```
+/// use fastx::twobit::{fasta_to_twobit, TwoBitReader};
+/// use std::io::Cursor;
+///
+/// let fasta = b">chr1 test\nACGTnnnn\nacgtAC\n";
+/// let mut twobit = Vec::new();
+/// fasta_to_twobit(Cursor::new(&fasta[..]), &mut twobit).unwrap();
+///
+/// let mut reader = TwoBitReader::new(Cursor::new(twobit)).unwrap();
+/// assert_eq!(reader.fetch_range("chr1", 2, 12).unwrap(), b"GTnnnnacgt");
+/// ```
+pub fn fasta_to_twobit<R: BufRead + Seek, W: Write>(mut reader: R, writer: W) -> io::Result<()>
+{
+    write_twobit(writer, |func| {
+        reader.seek(SeekFrom::Start(0))?;
+        fasta_for_each(&mut reader, func)
+    })
+}
+
+/// Convert a FASTA file (optionally gzip-compressed) to a .2bit file.
+///
+/// The FASTA file is read twice, see [`fasta_to_twobit`].
+pub fn fasta_path_to_twobit(fasta: &Path, twobit: &Path) -> io::Result<()>
+{
+    let writer = io::BufWriter::new(File::create(twobit)?);
+    write_twobit(writer, |func| fasta_for_each(crate::FastX::reader_from_path(fasta)?, func))
+}
+
+/// Write a .2bit file from two passes over the FASTA records made by `for_each`.
+fn write_twobit<W: Write>(
+    mut writer: W,
+    mut for_each: impl FnMut(&mut dyn FnMut(&FastARecord)) -> io::Result<()>,
+) -> io::Result<()>
+{
+    // First pass: names and encoded sizes
+    let mut names = Vec::new();
+    let mut sizes = Vec::new();
+    let mut error = None;
+    for_each(&mut |record| {
+        if error.is_some()
+        {
+            return;
+        }
+        if record.id().len() > u8::MAX as usize
+        {
+            error = Some(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Sequence name too long for the 2bit format: {}", record.id()),
+            ));
+            return;
+        }
+        match encode_record(&record.seq())
+        {
+            Ok(encoded) =>
+            {
+                names.push(record.id().to_string());
+                sizes.push(encoded.len() as u64);
+            }
+            Err(e) => error = Some(e),
+        }
+    })?;
+    if let Some(e) = error
+    {
+        return Err(e);
+    }
+
+    let index_size: u64 = names.iter().map(|n| 1 + n.len() as u64 + 4).sum();
+    let data_size: u64 = sizes.iter().sum();
+    let version: u32 = if 16 + index_size + data_size > u32::MAX as u64 { 1 } else { 0 };
+    let offset_size = if version == 1 { 8 } else { 4 };
+    let index_size: u64 = names.iter().map(|n| 1 + n.len() as u64 + offset_size).sum();
+
+    writer.write_all(&TWOBIT_SIGNATURE.to_le_bytes())?;
+    writer.write_all(&version.to_le_bytes())?;
+    writer.write_all(&(names.len() as u32).to_le_bytes())?;
+    writer.write_all(&0u32.to_le_bytes())?;
+
+    let mut offset = 16 + index_size;
+    for (name, size) in names.iter().zip(&sizes)
+    {
+        writer.write_all(&[name.len() as u8])?;
+        writer.write_all(name.as_bytes())?;
+        if version == 1
+        {
+            writer.write_all(&offset.to_le_bytes())?;
+        }
+        else
+        {
+            writer.write_all(&(offset as u32).to_le_bytes())?;
+        }
+        offset += size;
+    }
+
+    // Second pass: stream the records
+    let mut written = 0;
+    for_each(&mut |record| {
+        if error.is_some()
+        {
+            return;
+        }
+        let result = encode_record(&record.seq()).and_then(|encoded| {
+            if sizes.get(written) != Some(&(encoded.len() as u64))
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "FASTA input changed while converting to 2bit",
+                ));
+            }
+            writer.write_all(&encoded)
+        });
+        match result
+        {
+            Ok(()) => written += 1,
+            Err(e) => error = Some(e),
+        }
+    })?;
+    if let Some(e) = error
+    {
+        return Err(e);
+    }
+    if written != sizes.len()
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "FASTA input changed while converting to 2bit",
+        ));
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::io::Cursor;
+
+    fn build(fasta: &[u8]) -> TwoBitReader<Cursor<Vec<u8>>>
+    {
+        let mut twobit = Vec::new();
+        fasta_to_twobit(Cursor::new(fasta), &mut twobit).unwrap();
+        TwoBitReader::new(Cursor::new(twobit)).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip()
+    {
+        let mut reader = build(b">a desc\nACGTACGTAC\nNNacgtRT\n>b\nGGGG\n>empty\n\n");
+        assert_eq!(reader.sequence_names(), vec!["a", "b", "empty"]);
+        assert!(reader.contains("b"));
+        assert!(!reader.contains("c"));
+
+        let a = reader.fetch("a").unwrap();
+        assert_eq!(a.seq(), b"ACGTACGTACNNacgtNT");
+        assert_eq!(reader.fetch("b").unwrap().seq(), b"GGGG");
+        assert_eq!(reader.fetch("empty").unwrap().seq_len(), 0);
+    }
+
+    #[test]
+    fn test_path_conversion()
+    {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        let fasta_path = Path::new("test_twobit.fa.gz");
+        let twobit_path = Path::new("test_twobit.2bit");
+        let chr1: Vec<u8> = (0..10_000u32).map(|i| b"ACGTNacgt"[(i * 7 % 9) as usize]).collect();
+        let mut text = b">chr1\n".to_vec();
+        text.extend_from_slice(&chr1);
+        text.extend_from_slice(b"\n>chr2\nGATTACA\n");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&text).unwrap();
+        std::fs::write(fasta_path, encoder.finish().unwrap()).unwrap();
+
+        fasta_path_to_twobit(fasta_path, twobit_path).unwrap();
+        let mut reader = TwoBitReader::from_path(twobit_path).unwrap();
+        assert_eq!(reader.sequence_names(), vec!["chr1", "chr2"]);
+        assert_eq!(reader.fetch("chr1").unwrap().seq(), chr1);
+        assert_eq!(reader.fetch("chr2").unwrap().seq(), b"GATTACA");
+
+        // A partly consumed reader is rewound for both passes
+        let mut input = Cursor::new(text);
+        input.set_position(3);
+        let mut twobit = Vec::new();
+        fasta_to_twobit(&mut input, &mut twobit).unwrap();
+        assert_eq!(twobit, std::fs::read(twobit_path).unwrap());
+
+        std::fs::remove_file(fasta_path).unwrap();
+        std::fs::remove_file(twobit_path).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_range()
+    {
+        let mut reader = build(b">a\nACGTACGTACNNacgtRT\n");
+        assert_eq!(reader.fetch_range("a", 9, 14).unwrap(), b"CNNac");
+        assert_eq!(reader.fetch_range("a", 15, 100).unwrap(), b"tNT");
+        assert!(reader.fetch_range("a", 18, 20).is_err());
+        assert!(reader.fetch_range("missing", 0, 1).is_err());
+
+        let mut reader = reader.with_soft_mask(false);
+        assert_eq!(reader.fetch_range("a", 9, 14).unwrap(), b"CNNAC");
+    }
+
+    #[test]
+    fn test_big_endian_header()
+    {
+        // Hand-built big-endian file with one sequence "x" = "TCAG"
+        let mut data = Vec::new();
+        data.extend_from_slice(&TWOBIT_SIGNATURE.to_be_bytes());
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.push(1);
+        data.push(b'x');
+        data.extend_from_slice(&(16u32 + 6).to_be_bytes());
+        data.extend_from_slice(&4u32.to_be_bytes());
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.push(0b00_01_10_11);
+
+        let mut reader = TwoBitReader::new(Cursor::new(data.clone())).unwrap();
+        assert_eq!(reader.fetch_range("x", 0, 4).unwrap(), b"TCAG");
+
+        // Corrupt counts fail without allocating for them
+        let mut corrupt = data.clone();
+        corrupt[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(TwoBitReader::new(Cursor::new(corrupt)).is_err());
+        let mut corrupt = data;
+        corrupt[26..30].copy_from_slice(&u32::MAX.to_be_bytes());
+        let mut reader = TwoBitReader::new(Cursor::new(corrupt)).unwrap();
+        assert!(reader.fetch_range("x", 0, 4).is_err());
+    }
+}
@@ -0,0 +1,611 @@
+//! Random access into ordinary (non-BGZF) gzip files via a checkpoint index.
+//!
+//! Plain gzip files cannot be read from the middle, because every deflate block
+//! may refer back to the previous 32 KiB of output. Following zlib's `zran.c`,
+//! one sequential pass over the file records checkpoints at deflate block
+//! boundaries: the input bit offset, the uncompressed offset and the preceding
+//! 32 KiB window. [`ZranReader`] then resumes inflation from the nearest
+//! checkpoint before a requested position.
+//!
+//! The index is persisted as a `.zran` file next to the data, so the sequential
+//! pass is only needed once.
+//!
+//! # Index Format
+//!
+//! Binary, little-endian:
+//! - Magic `FXZRAN02`
+//! - Compressed source size (u64), CRC32 of its first 64 KiB (u32)
+//! - Span (u64), uncompressed size (u64), number of checkpoints (u64)
+//! - Per checkpoint: input bit offset (u64), uncompressed offset (u64),
+//!   length of the raw-deflate compressed window (u32), compressed window
+
+use crate::inflate::{Inflater, WINDOW_SIZE};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const ZRAN_MAGIC: &[u8; 8] = b"FXZRAN02";
+
+/// Length of the compressed prefix covered by the source checksum.
+const SOURCE_PREFIX: u64 = 64 * 1024;
+
+/// Default distance between checkpoints (1 MiB of uncompressed data).
+pub const DEFAULT_SPAN: u64 = 1024 * 1024;
+
+/// A resume point in a gzip stream.
+#[derive(Debug, Clone)]
+pub struct Checkpoint
+{
+    /// Input bit offset of the start of a deflate block
+    pub bit_offset: u64,
+    /// Uncompressed offset at that block
+    pub uncompressed_offset: u64,
+    /// The preceding (up to) 32 KiB of output, raw-deflate compressed
+    window: Vec<u8>,
+}
+
+impl Checkpoint
+{
+    fn window(&self) -> io::Result<Vec<u8>>
+    {
+        let mut window = Vec::with_capacity(WINDOW_SIZE);
+        DeflateDecoder::new(&self.window[..]).read_to_end(&mut window)?;
+        Ok(window)
+    }
+}
+
+/// A checkpoint index for random access into a gzip file.
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::zran::{ZranIndex, DEFAULT_SPAN};
+/// use std::fs::File;
+/// use std::path::Path;
+///
+/// // One sequential pass, then persist the index for later runs
+/// let index = ZranIndex::build(File::open("data.fa.gz").unwrap(), DEFAULT_SPAN).unwrap();
+/// index.write_to_path(Path::new("data.fa.gz.zran")).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ZranIndex
+{
+    /// Size of the compressed file the index was built from
+    pub source_size: u64,
+    /// CRC32 of the first 64 KiB of the compressed file
+    pub source_crc32: u32,
+    /// Approximate uncompressed distance between checkpoints
+    pub span: u64,
+    /// Total uncompressed size of the stream
+    pub uncompressed_size: u64,
+    /// Checkpoints sorted by offset
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+impl ZranIndex
+{
+    /// Build an index by decompressing a whole gzip stream once.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The gzip stream, positioned at its start
+    /// * `span` - Minimum uncompressed distance between checkpoints
+    pub fn build<R: Read>(reader: R, span: u64) -> io::Result<Self>
+    {
+        let mut inflater = Inflater::new(SourceReader::new(reader));
+        let mut history = Vec::with_capacity(4 * WINDOW_SIZE);
+        let mut total = 0u64;
+        let mut checkpoints: Vec<Checkpoint> = Vec::new();
+
+        loop
+        {
+            if inflater.at_block_start()
+                && checkpoints
+                    .last()
+                    .map(|cp| total - cp.uncompressed_offset >= span)
+                    .unwrap_or(true)
+            {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+                encoder.write_all(&history)?;
+                checkpoints.push(Checkpoint {
+                    bit_offset: inflater.bit_position(),
+                    uncompressed_offset: total,
+                    window: encoder.finish()?,
+                });
+            }
+
+            let before = history.len();
+            if !inflater.step(&mut history)?
+            {
+                break;
+            }
+            total += (history.len() - before) as u64;
+            if history.len() > WINDOW_SIZE
+            {
+                history.drain(..history.len() - WINDOW_SIZE);
+            }
+        }
+
+        // Trailing bytes after the last member still count towards the size
+        let mut source = inflater.into_inner();
+        io::copy(&mut source, &mut io::sink())?;
+
+        Ok(Self {
+            source_size: source.size,
+            source_crc32: source.crc.sum(),
+            span,
+            uncompressed_size: total,
+            checkpoints,
+        })
+    }
+
+    /// Load an index from a `.zran` file.
+    pub fn from_path(path: &Path) -> io::Result<Self>
+    {
+        let mut reader = io::BufReader::new(std::fs::File::open(path)?);
+        Self::from_reader(&mut reader)
+    }
+
+    /// Load an index from a reader.
+    pub fn from_reader<R: Read>(reader: &mut R) -> io::Result<Self>
+    {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != ZRAN_MAGIC
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid zran index magic"));
+        }
+
+        let source_size = read_u64(reader)?;
+        let mut crc = [0u8; 4];
+        reader.read_exact(&mut crc)?;
+        let span = read_u64(reader)?;
+        let uncompressed_size = read_u64(reader)?;
+        let count = read_u64(reader)?;
+        let mut checkpoints = Vec::with_capacity(count.min(1 << 20) as usize);
+        for _ in 0..count
+        {
+            let bit_offset = read_u64(reader)?;
+            let uncompressed_offset = read_u64(reader)?;
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            let mut window = vec![0u8; u32::from_le_bytes(len) as usize];
+            reader.read_exact(&mut window)?;
+            if checkpoints
+                .last()
+                .is_some_and(|cp: &Checkpoint| cp.uncompressed_offset > uncompressed_offset)
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "zran checkpoints not sorted by uncompressed offset",
+                ));
+            }
+            checkpoints.push(Checkpoint {
+                bit_offset,
+                uncompressed_offset,
+                window,
+            });
+        }
+
+        Ok(Self {
+            source_size,
+            source_crc32: u32::from_le_bytes(crc),
+            span,
+            uncompressed_size,
+            checkpoints,
+        })
+    }
+
+    /// Write the index to a `.zran` file.
+    pub fn write_to_path(&self, path: &Path) -> io::Result<()>
+    {
+        let mut writer = io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Write the index to a writer.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()>
+    {
+        writer.write_all(ZRAN_MAGIC)?;
+        writer.write_all(&self.source_size.to_le_bytes())?;
+        writer.write_all(&self.source_crc32.to_le_bytes())?;
+        writer.write_all(&self.span.to_le_bytes())?;
+        writer.write_all(&self.uncompressed_size.to_le_bytes())?;
+        writer.write_all(&(self.checkpoints.len() as u64).to_le_bytes())?;
+        for cp in &self.checkpoints
+        {
+            writer.write_all(&cp.bit_offset.to_le_bytes())?;
+            writer.write_all(&cp.uncompressed_offset.to_le_bytes())?;
+            writer.write_all(&(cp.window.len() as u32).to_le_bytes())?;
+            writer.write_all(&cp.window)?;
+        }
+        Ok(())
+    }
+
+    /// Check that the index was built from `source`.
+    ///
+    /// Compares the size of `source` and a checksum of its first 64 KiB with the
+    /// values recorded at build time, so a stale index for a replaced file is
+    /// rejected instead of decoding garbage. `source` is left at its start.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the source matches
+    /// * `Err(io::Error)` - With `InvalidData` if it does not
+    pub fn check_source<R: Read + Seek>(&self, source: &mut R) -> io::Result<()>
+    {
+        let size = source.seek(SeekFrom::End(0))?;
+        source.seek(SeekFrom::Start(0))?;
+        let mut prefix = Vec::with_capacity(size.min(SOURCE_PREFIX) as usize);
+        source.take(SOURCE_PREFIX).read_to_end(&mut prefix)?;
+        source.seek(SeekFrom::Start(0))?;
+        let mut crc = Crc::new();
+        crc.update(&prefix);
+
+        if size != self.source_size || crc.sum() != self.source_crc32
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "zran index does not match the gzip file (built for {} bytes, file has {})",
+                    self.source_size, size
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Get the last checkpoint at or before an uncompressed position.
+    pub fn checkpoint_for(&self, uncompressed_pos: u64) -> Option<&Checkpoint>
+    {
+        let i = self
+            .checkpoints
+            .partition_point(|cp| cp.uncompressed_offset <= uncompressed_pos);
+        if i == 0
+        {
+            None
+        }
+        else
+        {
+            Some(&self.checkpoints[i - 1])
+        }
+    }
+
+    /// Get the number of checkpoints.
+    pub fn len(&self) -> usize
+    {
+        self.checkpoints.len()
+    }
+
+    /// Check if the index has no checkpoints.
+    pub fn is_empty(&self) -> bool
+    {
+        self.checkpoints.is_empty()
+    }
+}
+
+/// Reader that records the size of its input and a checksum of the first bytes.
+struct SourceReader<R: Read>
+{
+    inner: R,
+    size: u64,
+    crc: Crc,
+}
+
+impl<R: Read> SourceReader<R>
+{
+    fn new(inner: R) -> Self
+    {
+        Self { inner, size: 0, crc: Crc::new() }
+    }
+}
+
+impl<R: Read> Read for SourceReader<R>
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+        let n = self.inner.read(buf)?;
+        if self.size < SOURCE_PREFIX
+        {
+            let covered = ((SOURCE_PREFIX - self.size) as usize).min(n);
+            self.crc.update(&buf[..covered]);
+        }
+        self.size += n as u64;
+        Ok(n)
+    }
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64>
+{
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// A gzip reader with seeking support via a [`ZranIndex`].
+///
+/// Works like [`BgzfReader`](crate::bgzf::BgzfReader) for ordinary gzip files:
+/// it implements `Read` and `BufRead` and can seek to uncompressed positions
+/// with [`seek_uncompressed`](ZranReader::seek_uncompressed).
+///
+/// # Example
+///
+/// ```no_run
+/// use fastx::zran::{ZranIndex, ZranReader};
+/// use std::fs::File;
+/// use std::io::Read;
+/// use std::path::Path;
+///
+/// let index = ZranIndex::from_path(Path::new("data.fa.gz.zran")).unwrap();
+/// let mut reader = ZranReader::new(File::open("data.fa.gz").unwrap(), index);
+/// reader.seek_uncompressed(1_000_000).unwrap();
+/// let mut buf = [0u8; 100];
+/// reader.read_exact(&mut buf).unwrap();
+/// ```
+pub struct ZranReader<R: Read + Seek>
+{
+    /// Inflater owning the compressed reader, None only if a restart failed
+    inflater: Option<Inflater<R>>,
+    /// Whether the inflater must be restarted before decoding
+    needs_restart: bool,
+    index: ZranIndex,
+    /// Output history: up to 32 KiB of window followed by new output
+    buf: Vec<u8>,
+    /// Uncompressed offset of `buf[0]`
+    buf_offset: u64,
+    /// Current position in `buf`
+    buf_pos: usize,
+    eof: bool,
+}
+
+impl<R: Read + Seek> ZranReader<R>
+{
+    /// Create a reader positioned at the start of the uncompressed stream.
+    pub fn new(inner: R, index: ZranIndex) -> Self
+    {
+        Self {
+            inflater: Some(Inflater::new(inner)),
+            needs_restart: true,
+            index,
+            buf: Vec::new(),
+            buf_offset: 0,
+            buf_pos: 0,
+            eof: false,
+        }
+    }
+
+    /// Get a reference to the checkpoint index.
+    pub fn index(&self) -> &ZranIndex
+    {
+        &self.index
+    }
+
+    /// Get the current uncompressed position.
+    pub fn current_position(&self) -> u64
+    {
+        self.buf_offset + self.buf_pos as u64
+    }
+
+    /// Restart inflation at the checkpoint before `uncompressed_pos`.
+    fn restart(&mut self, uncompressed_pos: u64) -> io::Result<()>
+    {
+        let (bit_offset, offset, window) = match self.index.checkpoint_for(uncompressed_pos)
+        {
+            Some(cp) => (cp.bit_offset, cp.uncompressed_offset, cp.window()?),
+            None => (0, 0, Vec::new()),
+        };
+
+        let mut inner = self
+            .inflater
+            .take()
+            .ok_or_else(|| io::Error::other("gzip reader unusable after failed seek"))?
+            .into_inner();
+        inner.seek(SeekFrom::Start(bit_offset / 8))?;
+        self.inflater = Some(if bit_offset == 0
+        {
+            Inflater::new(inner)
+        }
+        else
+        {
+            Inflater::resume(inner, bit_offset)?
+        });
+        self.needs_restart = false;
+
+        self.buf_offset = offset - window.len() as u64;
+        self.buf_pos = window.len();
+        self.buf = window;
+        self.eof = false;
+        Ok(())
+    }
+
+    /// Decode further until new output is available. Returns false at end of stream.
+    fn decode_more(&mut self) -> io::Result<bool>
+    {
+        if self.needs_restart
+        {
+            let pos = self.current_position();
+            self.restart(pos)?;
+        }
+        // Keep only the back-reference window before the current position
+        if self.buf_pos > WINDOW_SIZE
+        {
+            let drop = self.buf_pos - WINDOW_SIZE;
+            self.buf.drain(..drop);
+            self.buf_offset += drop as u64;
+            self.buf_pos -= drop;
+        }
+        let inflater = self
+            .inflater
+            .as_mut()
+            .ok_or_else(|| io::Error::other("gzip reader unusable after failed seek"))?;
+        let before = self.buf.len();
+        while self.buf.len() == before
+        {
+            if !inflater.step(&mut self.buf)?
+            {
+                self.eof = true;
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Seek to an uncompressed position.
+    ///
+    /// Positions inside the current buffer are served without decompression,
+    /// short forward seeks continue decoding and everything else resumes from
+    /// the nearest checkpoint.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(pos)` - The new position
+    /// * `Err(io::Error)` - If the position is beyond the end of the stream
+    pub fn seek_uncompressed(&mut self, uncompressed_pos: u64) -> io::Result<u64>
+    {
+        let buf_end = self.buf_offset + self.buf.len() as u64;
+        let in_buffer = uncompressed_pos >= self.buf_offset && uncompressed_pos <= buf_end;
+        if !in_buffer || self.needs_restart
+        {
+            let resume_at = self
+                .index
+                .checkpoint_for(uncompressed_pos)
+                .map(|cp| cp.uncompressed_offset)
+                .unwrap_or(0);
+            // Continuing forward is cheaper than restarting if no checkpoint lies in between
+            let continue_forward = !self.needs_restart
+                && uncompressed_pos > buf_end
+                && resume_at <= self.current_position();
+            if !continue_forward
+            {
+                self.restart(uncompressed_pos)?;
+            }
+        }
+
+        loop
+        {
+            let buf_end = self.buf_offset + self.buf.len() as u64;
+            if uncompressed_pos <= buf_end
+            {
+                self.buf_pos = (uncompressed_pos - self.buf_offset) as usize;
+                return Ok(uncompressed_pos);
+            }
+            self.buf_pos = self.buf.len();
+            if !self.decode_more()?
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Reached end of file before target position",
+                ));
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for ZranReader<R>
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> BufRead for ZranReader<R>
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]>
+    {
+        while self.buf_pos >= self.buf.len()
+        {
+            if self.eof || !self.decode_more()?
+            {
+                return Ok(&[]);
+            }
+        }
+        Ok(&self.buf[self.buf_pos..])
+    }
+
+    fn consume(&mut self, amt: usize)
+    {
+        self.buf_pos = (self.buf_pos + amt).min(self.buf.len());
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Cursor;
+
+    fn test_data() -> Vec<u8>
+    {
+        let mut state = 12345u32;
+        (0..600_000u32)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                b"ACGTTGCA\n"[((state >> 16) % 9) as usize]
+            })
+            .collect()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8>
+    {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_build_and_seek()
+    {
+        let data = test_data();
+        let gz = gzip(&data);
+        let index = ZranIndex::build(&gz[..], 64 * 1024).unwrap();
+        assert_eq!(index.uncompressed_size, data.len() as u64);
+        assert!(index.len() > 2);
+
+        let mut reader = ZranReader::new(Cursor::new(gz), index);
+        for &pos in &[500_000u64, 10, 250_000, 250_100, 599_990, 0]
+        {
+            reader.seek_uncompressed(pos).unwrap();
+            let mut buf = vec![0u8; 10];
+            reader.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, &data[pos as usize..pos as usize + 10]);
+        }
+        assert!(reader.seek_uncompressed(data.len() as u64 + 1).is_err());
+    }
+
+    #[test]
+    fn test_index_round_trip()
+    {
+        let data = test_data();
+        let gz = gzip(&data);
+        let index = ZranIndex::build(&gz[..], 100_000).unwrap();
+        let mut bytes = Vec::new();
+        index.write_to(&mut bytes).unwrap();
+        let loaded = ZranIndex::from_reader(&mut &bytes[..]).unwrap();
+        assert_eq!(loaded.len(), index.len());
+        assert_eq!(loaded.source_size, gz.len() as u64);
+        loaded.check_source(&mut Cursor::new(&gz)).unwrap();
+
+        // An index for a different file is rejected
+        let other = gzip(&data[..500_000]);
+        let err = loaded.check_source(&mut Cursor::new(&other)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let mut same_size = gz.clone();
+        same_size[100] ^= 1;
+        assert!(loaded.check_source(&mut Cursor::new(&same_size)).is_err());
+
+        let mut reader = ZranReader::new(Cursor::new(gz), loaded);
+        reader.seek_uncompressed(450_000).unwrap();
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, &data[450_000..]);
+    }
+}